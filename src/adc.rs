@@ -3,7 +3,7 @@ use rpos::thread_logln;
 use linux_embedded_hal::I2cdev;
 use std::time::Duration;
 
-use crate::messages::AdcRawMsg;
+use crate::{config::InputSource, messages::InputFrameMsg};
 use ads1x1x::{channel, Ads1x1x, SlaveAddr};
 
 const ADC_POLL_INTERVAL: Duration = Duration::from_micros(500);
@@ -39,19 +39,19 @@ fn adc_main(_argc: u32, _argv: *const &str) {
         .unwrap();
     adc.set_data_rate(ads1x1x::DataRate16Bit::Sps860).unwrap();
 
-    let adc_raw_tx = rpos::msg::get_new_tx_of_message::<AdcRawMsg>("adc_raw").unwrap();
+    let input_tx = rpos::msg::get_new_tx_of_message::<InputFrameMsg>("input_frame").unwrap();
 
     thread_logln!("adc thread started!");
 
     loop {
-        let value = [
+        let axes = vec![
             read_channel(&mut adc, || channel::SingleA0),
             read_channel(&mut adc, || channel::SingleA1),
             read_channel(&mut adc, || channel::SingleA2),
             read_channel(&mut adc, || channel::SingleA3),
         ];
 
-        adc_raw_tx.send(AdcRawMsg { value });
+        input_tx.send(InputFrameMsg::from_axes(InputSource::Adc, axes));
     }
 }
 
@@ -0,0 +1,222 @@
+//! Plays `audio_cue` messages: sounds are `<sounds_dir>/<name>.wav` files
+//! handed to an external player, haptic pulses switch a sysfs control file
+//! (LED-class vibrator or GPIO `value`) on for the requested time.
+
+use std::{
+    collections::BTreeSet,
+    fs,
+    path::PathBuf,
+    process::{Child, Command, Stdio},
+    time::{Duration, Instant},
+};
+
+use clap::Parser;
+use rpos::{msg::get_new_rx_of_message, thread_logln};
+
+use crate::{
+    client_process_args,
+    config::store,
+    messages::{AudioCueMsg, SystemConfigMsg},
+};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+#[derive(Parser)]
+#[command(name = "audio", about = "Plays sound and haptic cues")]
+struct Cli {
+    /// Directory holding `<sound>.wav` files.
+    #[arg(long, default_value = "sounds")]
+    sounds_dir: PathBuf,
+
+    /// Program started with the sound file as its only argument.
+    #[arg(long, default_value = "aplay")]
+    player: String,
+
+    /// Control file written `1`/`0` for haptic pulses, e.g.
+    /// `/sys/class/leds/vibrator/brightness`.
+    #[arg(long)]
+    haptic: Option<PathBuf>,
+}
+
+struct CuePlayer {
+    sounds_dir: PathBuf,
+    player: String,
+    haptic: Option<PathBuf>,
+    sound_percent: u8,
+    mute: bool,
+    haptic_until: Option<Instant>,
+    playing: Vec<Child>,
+    /// Sounds already reported missing, so a repeating cue logs once.
+    missing: BTreeSet<String>,
+}
+
+impl CuePlayer {
+    fn new(sounds_dir: PathBuf, player: String, haptic: Option<PathBuf>) -> Self {
+        let radio = store::load_radio_config().unwrap_or_default();
+        Self {
+            sounds_dir,
+            player,
+            haptic,
+            sound_percent: radio.audio.sound_percent,
+            mute: radio.audio.mute,
+            haptic_until: None,
+            playing: Vec::new(),
+            missing: BTreeSet::new(),
+        }
+    }
+
+    /// File to play for `name`, or `None` when sound is off or there is no
+    /// such file. Names are limited to file-name characters.
+    fn sound_path(&mut self, name: &str) -> Option<PathBuf> {
+        if self.mute || self.sound_percent == 0 {
+            return None;
+        }
+        let valid = !name.is_empty()
+            && name
+                .chars()
+                .all(|ch| ch.is_ascii_alphanumeric() || ch == '-' || ch == '_');
+        let path = self.sounds_dir.join(format!("{name}.wav"));
+        if valid && path.is_file() {
+            return Some(path);
+        }
+        if self.missing.insert(name.to_string()) {
+            thread_logln!("audio: no sound file for `{}`", name);
+        }
+        None
+    }
+
+    fn play(&mut self, cue: AudioCueMsg, now: Instant) {
+        match cue {
+            AudioCueMsg::Sound(name) => {
+                let Some(path) = self.sound_path(&name) else {
+                    return;
+                };
+                match Command::new(&self.player)
+                    .arg(&path)
+                    .stdout(Stdio::null())
+                    .stderr(Stdio::null())
+                    .spawn()
+                {
+                    Ok(child) => self.playing.push(child),
+                    Err(err) => thread_logln!("audio: {} failed: {}", self.player, err),
+                }
+            }
+            AudioCueMsg::Haptic { duration_ms } => {
+                if self.haptic.is_none() || duration_ms == 0 {
+                    return;
+                }
+                let until = now + Duration::from_millis(duration_ms as u64);
+                if self.haptic_until.is_none() {
+                    self.set_haptic(true);
+                }
+                self.haptic_until = Some(self.haptic_until.map_or(until, |old| old.max(until)));
+            }
+        }
+    }
+
+    /// Ends haptic pulses that ran out and reaps finished players.
+    fn tick(&mut self, now: Instant) {
+        if self.haptic_until.is_some_and(|until| now >= until) {
+            self.haptic_until = None;
+            self.set_haptic(false);
+        }
+        self.playing
+            .retain_mut(|child| matches!(child.try_wait(), Ok(None)));
+    }
+
+    fn set_haptic(&self, on: bool) {
+        let Some(path) = &self.haptic else {
+            return;
+        };
+        if let Err(err) = fs::write(path, if on { "1" } else { "0" }) {
+            thread_logln!("audio: haptic {} failed: {}", path.display(), err);
+        }
+    }
+}
+
+fn audio_main(argc: u32, argv: *const &str) {
+    let Some(args) = client_process_args::<Cli>(argc, argv) else {
+        return;
+    };
+
+    let mut cue_rx = get_new_rx_of_message::<AudioCueMsg>("audio_cue").unwrap();
+    let mut config_rx = get_new_rx_of_message::<SystemConfigMsg>("system_config").unwrap();
+    let mut player = CuePlayer::new(args.sounds_dir, args.player, args.haptic);
+    thread_logln!("audio started");
+
+    loop {
+        while let Some(config) = config_rx.try_read() {
+            player.sound_percent = config.sound_percent;
+        }
+        let now = Instant::now();
+        while let Some(cue) = cue_rx.try_read() {
+            player.play(cue, now);
+        }
+        player.tick(now);
+        std::thread::sleep(POLL_INTERVAL);
+    }
+}
+
+#[rpos::ctor::ctor]
+fn register() {
+    rpos::module::Module::register("audio", audio_main);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn player(name: &str) -> (CuePlayer, PathBuf) {
+        let root = std::env::temp_dir().join(format!("lintx_audio_{name}"));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        let player = CuePlayer {
+            sounds_dir: root.clone(),
+            player: "true".to_string(),
+            haptic: Some(root.join("haptic")),
+            sound_percent: 60,
+            mute: false,
+            haptic_until: None,
+            playing: Vec::new(),
+            missing: BTreeSet::new(),
+        };
+        (player, root)
+    }
+
+    #[test]
+    fn test_sound_path_checks_name_file_and_mute() {
+        let (mut player, root) = player("sound");
+        fs::write(root.join("timer1_elapsed.wav"), b"").unwrap();
+
+        assert_eq!(
+            player.sound_path("timer1_elapsed"),
+            Some(root.join("timer1_elapsed.wav"))
+        );
+        assert_eq!(player.sound_path("missing"), None);
+        assert_eq!(player.sound_path("../timer1_elapsed"), None);
+
+        player.mute = true;
+        assert_eq!(player.sound_path("timer1_elapsed"), None);
+        player.mute = false;
+        player.sound_percent = 0;
+        assert_eq!(player.sound_path("timer1_elapsed"), None);
+    }
+
+    #[test]
+    fn test_haptic_pulse_turns_off_after_longest_request() {
+        let (mut player, root) = player("haptic");
+        let haptic = root.join("haptic");
+        let start = Instant::now();
+        let ms = Duration::from_millis;
+
+        player.play(AudioCueMsg::Haptic { duration_ms: 100 }, start);
+        assert_eq!(fs::read_to_string(&haptic).unwrap(), "1");
+        player.play(AudioCueMsg::Haptic { duration_ms: 50 }, start + ms(20));
+
+        player.tick(start + ms(90));
+        assert_eq!(fs::read_to_string(&haptic).unwrap(), "1");
+        player.tick(start + ms(100));
+        assert_eq!(fs::read_to_string(&haptic).unwrap(), "0");
+        assert_eq!(player.haptic_until, None);
+    }
+}
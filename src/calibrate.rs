@@ -1,8 +1,30 @@
-use std::{io::Write, time::Duration};
-
-use rpos::{channel::Receiver, thread_logln};
-
-use crate::{messages::AdcRawMsg, CALIBRATE_FILENAME};
+use std::{
+    fs, io,
+    time::{Duration, Instant},
+};
+
+use clap::{Parser, ValueEnum};
+use rpos::{
+    msg::{get_new_rx_of_message, get_new_tx_of_message},
+    thread_logln,
+};
+
+use crate::{
+    client_process_args,
+    config::{store, InputConfig, InputSource, ModelCalibration},
+    messages::{
+        CalibrationCommandMsg, CalibrationStateMsg, CalibrationStep, CalibrationUpdatedMsg,
+        InputFrameMsg,
+    },
+};
+
+/// Frames kept for the center capture, about a second of input.
+const CENTER_FRAMES: usize = 100;
+const CENTER_MIN_FRAMES: usize = 10;
+/// Least raw travel from center that counts as a stick being moved.
+const MIN_TRAVEL: i32 = 100;
+/// How long a detected axis has to stay deflected before it is offered.
+const DETECT_HOLD: Duration = Duration::from_millis(500);
 
 pub trait EnumIter
 where
@@ -24,13 +46,26 @@ impl JoystickChannel {
     pub const STRS: &'static [&'static str] = &["Thrust", "Direction", "Aileron", "Elevator"];
 }
 
-#[derive(Default, Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[derive(Default, Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub struct ChannelInfo {
     pub name: String,
     pub index: u8,
     pub min: i16,
     pub max: i16,
+    /// Raw value at rest, captured by the center step. Zero for throttle,
+    /// which has no rest point.
+    #[serde(default)]
+    pub center: i16,
+    /// Raw distance from `center` that still reads as centered. Zero for
+    /// throttle and for calibrations made before center capture.
+    #[serde(default)]
+    pub deadband: i16,
     pub rev: bool,
+    /// Optional lookup table of (raw, output 0..10000) pairs that replaces
+    /// min/center/max, to straighten out nonlinear pots. Written by hand,
+    /// e.g. `points = [[120, 0], [1050, 5000], [1980, 10000]]`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub points: Vec<(i16, u16)>,
 }
 
 #[derive(serde::Serialize, serde::Deserialize)]
@@ -39,201 +74,493 @@ pub struct CalibrationData {
     pub channel_indexs: Vec<u8>,
 }
 
-enum CalibrateState {
-    Idle,
-    LowestCheck(u8),
-    MinMaxCheck,
-    Finish,
+/// An axis deflected past the detection threshold.
+#[derive(Debug, Clone, Copy)]
+struct Detection {
+    axis: u8,
+    /// Pushing low or left (throttle: high) raised (lowered) the raw value.
+    rev: bool,
+    since: Instant,
 }
+
+/// Guided calibration, driven by `CalibrationCommandMsg`s and fed input
+/// frames; never blocks, so the service loop can publish progress.
 struct Calibration {
-    state: CalibrateState,
+    step: CalibrationStep,
+    source: InputSource,
+    /// Profile to save to, resolved once `source` is known.
+    profile: String,
+    /// `JoystickChannel` being detected or confirmed.
+    channel: usize,
+    detection: Option<Detection>,
+    center_sample: CalSample,
+    centers: Vec<i16>,
+    deadbands: Vec<i16>,
+    /// Raw min/max seen per axis during the sweep.
+    ranges: Vec<(i16, i16)>,
+    axes: Vec<i16>,
     data: CalibrationData,
-    rx: Receiver<AdcRawMsg>,
+    status_text: String,
 }
 
 impl Calibration {
     fn new() -> Self {
         Calibration {
-            state: CalibrateState::Idle,
-            rx: rpos::msg::get_new_rx_of_message::<AdcRawMsg>("adc_raw").unwrap(),
+            step: CalibrationStep::Idle,
+            source: InputSource::Unknown,
+            profile: String::new(),
+            channel: 0,
+            detection: None,
+            center_sample: CalSample::default(),
+            centers: Vec::new(),
+            deadbands: Vec::new(),
+            ranges: Vec::new(),
+            axes: Vec::new(),
             data: CalibrationData {
                 channel_infos: Vec::new(),
                 channel_indexs: Vec::new(),
             },
+            status_text: String::new(),
         }
     }
 
-    fn do_sample(&mut self, prewait_seconds: u32, sample_seconds: u32) -> CalSample {
-        let mut sample = CalSample::new(self.rx.clone());
-        std::thread::sleep(Duration::from_secs(prewait_seconds as u64));
-        sample.sample_in_seconds(sample_seconds);
-        sample
+    fn handle_command(&mut self, cmd: CalibrationCommandMsg) {
+        self.status_text.clear();
+        match (cmd, self.step) {
+            (CalibrationCommandMsg::Start, _) => {
+                *self = Self::new();
+                self.step = CalibrationStep::Center;
+            }
+            (CalibrationCommandMsg::Cancel, _) => {
+                *self = Self::new();
+                self.status_text = "Calibration canceled".to_string();
+            }
+            (CalibrationCommandMsg::Next, CalibrationStep::Center) => self.capture_center(),
+            (CalibrationCommandMsg::Next, CalibrationStep::ConfirmAxis) => self.confirm_axis(),
+            (CalibrationCommandMsg::Next, CalibrationStep::MinMax) => self.finish(),
+            (CalibrationCommandMsg::Back, CalibrationStep::ConfirmAxis) => {
+                self.detection = None;
+                self.step = CalibrationStep::DetectAxis;
+            }
+            (
+                CalibrationCommandMsg::Back,
+                CalibrationStep::DetectAxis | CalibrationStep::MinMax,
+            ) if !self.data.channel_infos.is_empty() => {
+                self.data.channel_infos.pop();
+                self.data.channel_indexs.pop();
+                self.channel = self.data.channel_infos.len();
+                self.detection = None;
+                self.step = CalibrationStep::DetectAxis;
+            }
+            _ => {}
+        }
     }
 
-    fn do_step(&mut self) {
-        match self.state {
-            CalibrateState::Idle => {
-                thread_logln!("start calibrate joysticks!");
-                thread_logln!("step 0:push joysticks to center.");
-                std::thread::sleep(std::time::Duration::from_secs(5));
-                self.state = CalibrateState::LowestCheck(0);
-            }
-            CalibrateState::LowestCheck(x) => {
-                thread_logln!(
-                    "step {}:push channel:{} to lowest side or leftmost side",
-                    x + 1,
-                    JoystickChannel::STRS[x as usize]
-                );
-
-                let sample = self.do_sample(2, 3);
-                let chn = sample.find_largest_change_channel();
-                self.data.channel_indexs.push(chn);
-
-                let next_channel = x + 1;
-                if next_channel == JoystickChannel::ITER.len() as u8 {
-                    self.state = CalibrateState::MinMaxCheck;
-                } else {
-                    self.state = CalibrateState::LowestCheck(next_channel);
+    fn update(&mut self, frame: &InputFrameMsg, now: Instant) {
+        if self.step == CalibrationStep::Idle || self.step == CalibrationStep::Done {
+            return;
+        }
+        if self.source == InputSource::Unknown {
+            self.source = frame.source;
+        }
+        if frame.source != self.source {
+            return;
+        }
+        self.axes = frame.axes.clone();
+
+        match self.step {
+            CalibrationStep::Center => self.center_sample.push(frame.clone(), CENTER_FRAMES),
+            CalibrationStep::DetectAxis => self.detect_axis(frame, now),
+            CalibrationStep::MinMax => {
+                for (range, value) in self.ranges.iter_mut().zip(&frame.axes) {
+                    *range = (range.0.min(*value), range.1.max(*value));
                 }
             }
-            CalibrateState::MinMaxCheck => {
-                thread_logln!("last step: rotate all your joysticks to check min and max value.");
-                let sample = self.do_sample(0, 10);
-                for (index, _) in JoystickChannel::ITER.iter().enumerate() {
-                    let channel_index = self.data.channel_indexs[index];
-                    let min = sample.get_min_of_channel(channel_index);
-                    let max = sample.get_max_of_channel(channel_index);
-                    let chn = ChannelInfo {
-                        index: channel_index,
-                        min,
-                        max,
-                        rev: false,
-                        name: JoystickChannel::STRS[index].to_string(),
-                    };
-                    self.data.channel_infos.push(chn);
+            _ => {}
+        }
+    }
+
+    fn capture_center(&mut self) {
+        if self.center_sample.list.len() < CENTER_MIN_FRAMES {
+            self.status_text = "Waiting for input frames".to_string();
+            return;
+        }
+        self.centers = self.center_sample.get_average();
+        self.deadbands = (0..self.centers.len() as u8)
+            .map(|axis| {
+                // Twice the jitter amplitude seen at rest.
+                self.center_sample.get_max_of_channel(axis)
+                    - self.center_sample.get_min_of_channel(axis)
+            })
+            .collect();
+        self.channel = 0;
+        self.detection = None;
+        self.step = CalibrationStep::DetectAxis;
+    }
+
+    /// Offers the free axis deflected furthest from center once it has been
+    /// held for `DETECT_HOLD`. Throttle is pushed to its top instead, since it
+    /// usually rests at its low stop where pushing low moves nothing.
+    fn detect_axis(&mut self, frame: &InputFrameMsg, now: Instant) {
+        let candidate = (0..frame.axes.len().min(self.centers.len()))
+            .filter(|axis| !self.data.channel_indexs.contains(&(*axis as u8)))
+            .map(|axis| (axis, frame.axis(axis) as i32 - self.centers[axis] as i32))
+            .max_by_key(|(_, deviation)| deviation.abs());
+        let Some((axis, deviation)) = candidate else {
+            return;
+        };
+        let threshold = MIN_TRAVEL.max(self.deadbands[axis] as i32 * 4);
+        if deviation.abs() < threshold {
+            self.detection = None;
+            return;
+        }
+        match self.detection {
+            Some(detection) if detection.axis == axis as u8 => {
+                if now.duration_since(detection.since) >= DETECT_HOLD {
+                    self.step = CalibrationStep::ConfirmAxis;
                 }
-                self.state = CalibrateState::Finish;
             }
-            CalibrateState::Finish => {
-                thread_logln!("finished.");
-                for (index, channel_name) in JoystickChannel::STRS.iter().enumerate() {
-                    thread_logln!("{}:{:?}", channel_name, self.data.channel_infos[index]);
-                }
+            _ => {
+                self.detection = Some(Detection {
+                    axis: axis as u8,
+                    rev: if self.channel == JoystickChannel::Thrust as usize {
+                        deviation < 0
+                    } else {
+                        deviation > 0
+                    },
+                    since: now,
+                })
             }
         }
     }
+
+    fn confirm_axis(&mut self) {
+        let Some(detection) = self.detection.take() else {
+            return;
+        };
+        let axis = detection.axis as usize;
+        let name = JoystickChannel::STRS[self.channel];
+        // Throttle does not spring back, so its rest point is arbitrary.
+        let (center, deadband) = if self.channel == JoystickChannel::Thrust as usize {
+            (0, 0)
+        } else {
+            (self.centers[axis], self.deadbands[axis])
+        };
+        self.data.channel_indexs.push(detection.axis);
+        self.data.channel_infos.push(ChannelInfo {
+            name: name.to_string(),
+            index: detection.axis,
+            min: 0,
+            max: 0,
+            center,
+            deadband,
+            rev: detection.rev,
+            points: Vec::new(),
+        });
+        self.channel += 1;
+        if self.channel < JoystickChannel::ITER.len() {
+            self.step = CalibrationStep::DetectAxis;
+        } else {
+            self.ranges = self
+                .centers
+                .iter()
+                .map(|center| (*center, *center))
+                .collect();
+            self.step = CalibrationStep::MinMax;
+        }
+    }
+
+    /// Takes the sweep's ranges once every stick went both ways from its
+    /// center, and the throttle end to end, and saves.
+    fn finish(&mut self) {
+        for (channel, info) in self.data.channel_infos.iter().enumerate() {
+            let (min, max) = self.ranges[info.index as usize];
+            let short = if channel == JoystickChannel::Thrust as usize {
+                (max as i32 - min as i32) < MIN_TRAVEL * 2
+            } else {
+                (info.center as i32 - min as i32) < MIN_TRAVEL
+                    || (max as i32 - info.center as i32) < MIN_TRAVEL
+            };
+            if short {
+                self.status_text = format!("Move {} to both ends", info.name);
+                return;
+            }
+        }
+        for info in &mut self.data.channel_infos {
+            (info.min, info.max) = self.ranges[info.index as usize];
+        }
+        match save_calibration(&self.profile, &self.data) {
+            Ok(()) => self.step = CalibrationStep::Done,
+            Err(err) => self.status_text = format!("Save failed: {}", err),
+        }
+    }
+
+    fn instruction(&self) -> String {
+        let channel = JoystickChannel::STRS
+            .get(self.channel)
+            .copied()
+            .unwrap_or_default();
+        match self.step {
+            CalibrationStep::Idle => "Start to calibrate the sticks".to_string(),
+            CalibrationStep::Center => "Release all sticks to center, then Next".to_string(),
+            CalibrationStep::DetectAxis if self.channel == JoystickChannel::Thrust as usize => {
+                format!("Push {channel} to its highest end and hold")
+            }
+            CalibrationStep::DetectAxis => {
+                format!("Push {channel} to its lowest or leftmost end and hold")
+            }
+            CalibrationStep::ConfirmAxis => format!(
+                "{channel} is axis {}: Next to keep, Back to retry",
+                self.detection.map_or(0, |detection| detection.axis)
+            ),
+            CalibrationStep::MinMax => {
+                "Move all sticks to their ends a few times, then Next".to_string()
+            }
+            CalibrationStep::Done => format!("Calibration saved to profile `{}`", self.profile),
+        }
+    }
+
+    fn state(&self) -> CalibrationStateMsg {
+        CalibrationStateMsg {
+            step: self.step,
+            source: self.source,
+            profile: self.profile.clone(),
+            channel: JoystickChannel::STRS
+                .get(self.channel)
+                .map(|name| name.to_string())
+                .unwrap_or_default(),
+            detected_axis: (self.step == CalibrationStep::ConfirmAxis)
+                .then(|| self.detection.map(|detection| detection.axis))
+                .flatten(),
+            instruction: self.instruction(),
+            status_text: self.status_text.clone(),
+            axes: self.axes.clone(),
+            channels: self.data.channel_infos.clone(),
+        }
+    }
 }
 
+pub fn load_calibration(profile: &str) -> io::Result<CalibrationData> {
+    let content = fs::read_to_string(store::calibration_path(profile))?;
+    let mut data = toml::from_str::<CalibrationData>(&content)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+    for info in &mut data.channel_infos {
+        info.points.sort_by_key(|point| point.0);
+    }
+    Ok(data)
+}
+
+fn save_calibration(profile: &str, data: &CalibrationData) -> io::Result<()> {
+    let toml_str =
+        toml::to_string(data).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+    fs::create_dir_all(store::CALIBRATION_DIR)?;
+    store::write_atomic(&store::calibration_path(profile), &toml_str)
+}
+
+/// The radio's input settings with the calibration profiles they and the
+/// active model select, loaded once so the mixer only looks them up per
+/// frame.
+#[derive(Default)]
+pub struct CalibrationSet {
+    input: InputConfig,
+    model: ModelCalibration,
+    profiles: Vec<(String, CalibrationData)>,
+}
+
+impl CalibrationSet {
+    pub fn load(input: InputConfig, model: ModelCalibration) -> Self {
+        let mut names = vec![input.calibration_profile.clone(), model.profile.clone()];
+        names.extend(
+            input
+                .calibration_sources
+                .iter()
+                .chain(&model.sources)
+                .map(|entry| entry.profile.clone()),
+        );
+        let mut profiles: Vec<(String, CalibrationData)> = Vec::new();
+        for name in names {
+            if name.is_empty() || profiles.iter().any(|(loaded, _)| *loaded == name) {
+                continue;
+            }
+            match load_calibration(&name) {
+                Ok(data) => profiles.push((name, data)),
+                Err(err) => thread_logln!(
+                    "calibration profile `{}` not loaded: {}, please calibrate",
+                    name,
+                    err
+                ),
+            }
+        }
+        Self::with_profiles(input, model, profiles)
+    }
+
+    /// A set over profiles already loaded, by name.
+    pub fn with_profiles(
+        input: InputConfig,
+        model: ModelCalibration,
+        profiles: Vec<(String, CalibrationData)>,
+    ) -> Self {
+        Self {
+            input,
+            model,
+            profiles,
+        }
+    }
+
+    pub fn input(&self) -> &InputConfig {
+        &self.input
+    }
+
+    pub fn for_source(&self, source: InputSource) -> Option<&CalibrationData> {
+        let name = self.input.calibration_profile_for(&self.model, source);
+        self.profiles
+            .iter()
+            .find(|(loaded, _)| loaded == name)
+            .map(|(_, data)| data)
+    }
+}
+
+#[derive(Default)]
 struct CalSample {
-    list: Vec<AdcRawMsg>,
-    rx: Receiver<AdcRawMsg>,
+    list: Vec<InputFrameMsg>,
 }
 
 impl CalSample {
-    fn new(rx: Receiver<AdcRawMsg>) -> Self {
-        CalSample {
-            list: Vec::new(),
-            rx,
+    /// Adds a frame, dropping the oldest beyond `limit`.
+    fn push(&mut self, frame: InputFrameMsg, limit: usize) {
+        if self.list.len() >= limit {
+            self.list.remove(0);
         }
+        self.list.push(frame);
+    }
+
+    fn axis_count(&self) -> usize {
+        self.list.iter().map(|x| x.axes.len()).max().unwrap_or(0)
     }
 
     fn get_min_of_channel(&self, channel_index: u8) -> i16 {
         let a = self
             .list
             .iter()
-            .min_by_key(|x| x.value[channel_index as usize])
+            .min_by_key(|x| x.axis(channel_index as usize))
             .unwrap();
-        a.value[channel_index as usize]
+        a.axis(channel_index as usize)
     }
 
     fn get_max_of_channel(&self, channel_index: u8) -> i16 {
         let a = self
             .list
             .iter()
-            .max_by_key(|x| x.value[channel_index as usize])
+            .max_by_key(|x| x.axis(channel_index as usize))
             .unwrap();
-        a.value[channel_index as usize]
+        a.axis(channel_index as usize)
     }
 
-    fn get_average(&self) -> AdcRawMsg {
-        let mut ret = self.list.iter().fold(AdcRawMsg::default(), |acc, x| {
-            let mut tmp = AdcRawMsg::default();
-            for (index, (a, b)) in acc.value.iter().zip(x.value).enumerate() {
-                tmp.value[index] = a + b;
+    fn get_average(&self) -> Vec<i16> {
+        let mut sums = vec![0i32; self.axis_count()];
+        for x in self.list.iter() {
+            for (index, sum) in sums.iter_mut().enumerate() {
+                *sum += x.axis(index) as i32;
             }
-            tmp
-        });
-        for i in ret.value.iter_mut() {
-            *i = (*i) / self.list.len() as i16;
         }
-        ret
+        sums.iter()
+            .map(|sum| (sum / self.list.len() as i32) as i16)
+            .collect()
     }
+}
 
-    fn find_largest_change_channel(&self) -> u8 {
-        let cmp_func = |a: &&AdcRawMsg, b: &&AdcRawMsg| {
-            let sum_a: i32 = a.value.iter().map(|a| *a as i32).sum();
-            let sum_b: i32 = b.value.iter().map(|a| *a as i32).sum();
-            sum_a.cmp(&sum_b)
-        };
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum CliCommand {
+    Start,
+    Next,
+    Back,
+    Cancel,
+}
 
-        let max = self.list.iter().max_by(cmp_func).unwrap();
-        let min = self.list.iter().min_by(cmp_func).unwrap();
-
-        let mut max_diff = 0;
-        let mut ret: u8 = 0;
-        thread_logln!("max:{:?}", max);
-        thread_logln!("min:{:?}", min);
-        for (index, (a, b)) in max.value.iter().zip(min.value).enumerate() {
-            let sub = (*a as i32 - b as i32).abs();
-            if sub > max_diff {
-                max_diff = sub;
-                ret = index as u8;
-            }
-        }
-        thread_logln!("index:{}", ret);
-        ret
-    }
+#[derive(Parser)]
+#[command(name = "calibrate", about = "Guided stick calibration service")]
+struct Cli {
+    /// Sends a command to the running service instead of starting one.
+    #[arg(value_enum)]
+    command: Option<CliCommand>,
 
-    fn sample_in_seconds(&mut self, seconds: u32) {
-        let start_time = std::time::Instant::now();
-        loop {
-            let data = self.rx.read();
-            self.list.push(data);
+    /// Makes this the radio's calibration profile and reloads the mixer.
+    #[arg(long)]
+    select: Option<String>,
+}
 
-            if start_time.elapsed().as_secs() >= seconds as u64 {
-                break;
-            }
+fn calibrate_main(argc: u32, argv: *const &str) {
+    let Some(args) = client_process_args::<Cli>(argc, argv) else {
+        return;
+    };
+
+    if let Some(profile) = args.select {
+        match store::set_calibration_profile(&profile) {
+            Ok(()) => get_new_tx_of_message::<CalibrationUpdatedMsg>("calibration_updated")
+                .unwrap()
+                .send(CalibrationUpdatedMsg { profile }),
+            Err(err) => thread_logln!("select calibration `{}` failed: {}", profile, err),
         }
+        return;
     }
 
-    fn sample_by_counts(&mut self, count: u32) {
-        while (self.list.len() as u32) < count {
-            let data = self.rx.read();
-            self.list.push(data);
-        }
+    if let Some(command) = args.command {
+        let cmd = match command {
+            CliCommand::Start => CalibrationCommandMsg::Start,
+            CliCommand::Next => CalibrationCommandMsg::Next,
+            CliCommand::Back => CalibrationCommandMsg::Back,
+            CliCommand::Cancel => CalibrationCommandMsg::Cancel,
+        };
+        get_new_tx_of_message::<CalibrationCommandMsg>("calibration_cmd")
+            .unwrap()
+            .send(cmd);
+        return;
     }
-}
 
-fn calibrate_main(_argc: u32, _argv: *const &str) {
+    let mut cmd_rx = get_new_rx_of_message::<CalibrationCommandMsg>("calibration_cmd").unwrap();
+    let mut input_rx = get_new_rx_of_message::<InputFrameMsg>("input_frame").unwrap();
+    let state_tx = get_new_tx_of_message::<CalibrationStateMsg>("calibration_state").unwrap();
+    let updated_tx = get_new_tx_of_message::<CalibrationUpdatedMsg>("calibration_updated").unwrap();
     let mut cal = Calibration::new();
+    let mut last_logged = String::new();
+    thread_logln!("calibrate service started");
+
     loop {
-        match cal.state {
-            CalibrateState::Finish => {
-                cal.do_step();
-                break;
-            }
-            _ => cal.do_step(),
+        let was_done = cal.step == CalibrationStep::Done;
+        while let Some(cmd) = cmd_rx.try_read() {
+            cal.handle_command(cmd);
+        }
+        while let Some(frame) = input_rx.try_read() {
+            cal.update(&frame, Instant::now());
+        }
+        if cal.profile.is_empty() && cal.source != InputSource::Unknown {
+            let radio = store::load_radio_config().unwrap_or_default();
+            let model = store::load_active_model().unwrap_or_default();
+            cal.profile = radio
+                .input
+                .calibration_profile_for(&model.calibration, cal.source)
+                .to_string();
+        }
+
+        let state = cal.state();
+        // Mirrors the page's prompts for calibrating from a console.
+        let log_line = if state.status_text.is_empty() {
+            state.instruction.clone()
+        } else {
+            format!("{} ({})", state.instruction, state.status_text)
+        };
+        if log_line != last_logged {
+            thread_logln!("calibrate: {}", log_line);
+            last_logged = log_line;
         }
+        if !was_done && state.step == CalibrationStep::Done {
+            updated_tx.send(CalibrationUpdatedMsg {
+                profile: state.profile.clone(),
+            });
+        }
+        state_tx.send(state);
+        std::thread::sleep(Duration::from_millis(20));
     }
-    _ = std::fs::remove_file(CALIBRATE_FILENAME);
-    let mut file = std::fs::OpenOptions::new()
-        .read(false)
-        .write(true)
-        .create_new(true)
-        .open(CALIBRATE_FILENAME)
-        .unwrap();
-    let str_write = toml::to_string(&cal.data).unwrap();
-    file.write(str_write.as_bytes()).unwrap();
 }
 
 #[rpos::ctor::ctor]
@@ -244,6 +571,11 @@ fn register() {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{
+        config::{ControlRole, ModelConfig},
+        input_router::InputRouter,
+        mixer::read_inputs,
+    };
     use serde::Serialize;
     #[derive(Serialize)]
     struct SaveInfo {
@@ -251,6 +583,10 @@ mod tests {
         b: Vec<u8>,
     }
 
+    fn mock_frame(axes: Vec<i16>) -> InputFrameMsg {
+        InputFrameMsg::from_axes(InputSource::Mock, axes)
+    }
+
     #[test]
     fn test_save_cal_data() {
         let mut a = CalibrationData {
@@ -263,57 +599,87 @@ mod tests {
         let b = toml::to_string(&a).unwrap();
         thread_logln!("{}", b);
     }
+
+    #[test]
+    fn test_load_cal_data_without_center() {
+        let data = toml::from_str::<CalibrationData>(
+            "channel_indexs = [0]\n\n[[channel_infos]]\nname = \"Thrust\"\nindex = 0\nmin = 100\nmax = 900\nrev = false\n",
+        )
+        .unwrap();
+        assert_eq!(data.channel_infos[0].center, 0);
+        assert_eq!(data.channel_infos[0].deadband, 0);
+        assert!(data.channel_infos[0].points.is_empty());
+
+        let mut info = data.channel_infos[0].clone();
+        info.points = vec![(100, 0), (480, 5000), (900, 10000)];
+        let data = CalibrationData {
+            channel_infos: vec![info.clone()],
+            channel_indexs: vec![0],
+        };
+        let loaded = toml::from_str::<CalibrationData>(&toml::to_string(&data).unwrap()).unwrap();
+        assert_eq!(loaded.channel_infos, vec![info]);
+    }
+
+    #[test]
+    fn test_calibration_set_picks_source_profile() {
+        let profile = |name: &str| {
+            (
+                name.to_string(),
+                CalibrationData {
+                    channel_infos: Vec::new(),
+                    channel_indexs: vec![name.len() as u8],
+                },
+            )
+        };
+        let mut input = InputConfig::default();
+        input
+            .calibration_sources
+            .push(crate::config::SourceCalibration {
+                source: InputSource::Joydev,
+                profile: "usb".to_string(),
+            });
+        let set = CalibrationSet {
+            input,
+            model: ModelCalibration::default(),
+            profiles: vec![profile("joystick.toml"), profile("usb")],
+        };
+        assert_eq!(
+            set.for_source(InputSource::Adc).unwrap().channel_indexs,
+            vec![13]
+        );
+        assert_eq!(
+            set.for_source(InputSource::Joydev).unwrap().channel_indexs,
+            vec![3]
+        );
+        assert!(CalibrationSet::default()
+            .for_source(InputSource::Adc)
+            .is_none());
+    }
+
     #[test]
     fn test_calsample_average() {
-        let mut rx = rpos::msg::get_new_rx_of_message::<AdcRawMsg>("adc_raw").unwrap();
-        let mut sample = CalSample::new(rx.clone());
-        sample.list.push(AdcRawMsg { value: [100; 4] });
-        sample.list.push(AdcRawMsg { value: [200; 4] });
+        let mut sample = CalSample::default();
+        sample.list.push(mock_frame(vec![100; 4]));
+        sample.list.push(mock_frame(vec![200; 4]));
 
         let average = sample.get_average();
 
-        for i in average.value {
+        assert_eq!(average.len(), 4);
+        for i in average {
             assert_eq!(i, 150);
         }
     }
 
-    #[test]
-    fn test_calsample_find_largest_changes_channel() {
-        let mut rx = rpos::msg::get_new_rx_of_message::<AdcRawMsg>("adc_raw").unwrap();
-        let mut sample = CalSample::new(rx.clone());
-        sample.list.push(AdcRawMsg {
-            value: [101, 99, 103, 102],
-        });
-        sample.list.push(AdcRawMsg {
-            value: [295, 290, 301, 299],
-        });
-
-        assert_eq!(sample.find_largest_change_channel(), 2);
-    }
-
     #[test]
     fn test_calsample_get_min_max() {
-        let mut rx = rpos::msg::get_new_rx_of_message::<AdcRawMsg>("adc_raw").unwrap();
-        let mut sample = CalSample::new(rx.clone());
+        let mut sample = CalSample::default();
         const CHANNEL_NUM: usize = JoystickChannel::ITER.len();
-        sample.list.push(AdcRawMsg {
-            value: [50; CHANNEL_NUM],
-        });
-        sample.list.push(AdcRawMsg {
-            value: [100; CHANNEL_NUM],
-        });
-        sample.list.push(AdcRawMsg {
-            value: [200; CHANNEL_NUM],
-        });
-        sample.list.push(AdcRawMsg {
-            value: [300; CHANNEL_NUM],
-        });
-        sample.list.push(AdcRawMsg {
-            value: [400; CHANNEL_NUM],
-        });
-        sample.list.push(AdcRawMsg {
-            value: [500; CHANNEL_NUM],
-        });
+        sample.list.push(mock_frame(vec![50; CHANNEL_NUM]));
+        sample.list.push(mock_frame(vec![100; CHANNEL_NUM]));
+        sample.list.push(mock_frame(vec![200; CHANNEL_NUM]));
+        sample.list.push(mock_frame(vec![300; CHANNEL_NUM]));
+        sample.list.push(mock_frame(vec![400; CHANNEL_NUM]));
+        sample.list.push(mock_frame(vec![500; CHANNEL_NUM]));
 
         for i in 0..CHANNEL_NUM {
             assert_eq!(sample.get_min_of_channel(i as u8), 50);
@@ -322,26 +688,168 @@ mod tests {
     }
 
     #[test]
-    fn test_calibrate() {
+    fn test_calibration_steps() {
         let mut cal = Calibration::new();
-        std::thread::spawn(|| {
-            let tx = rpos::msg::get_new_tx_of_message::<AdcRawMsg>("adc_raw").unwrap();
+        let mut now = Instant::now();
+        let mut feed = |cal: &mut Calibration, axes: [i16; 4]| {
+            now += Duration::from_millis(100);
+            cal.update(&mock_frame(axes.to_vec()), now);
+        };
 
-            loop {
-                let msg = AdcRawMsg::default();
-                tx.send(msg);
-                std::thread::sleep(std::time::Duration::from_millis(100));
+        cal.handle_command(CalibrationCommandMsg::Start);
+        cal.handle_command(CalibrationCommandMsg::Next);
+        assert_eq!(cal.step, CalibrationStep::Center);
+        assert_eq!(cal.state().status_text, "Waiting for input frames");
+        for jitter in 0..CENTER_MIN_FRAMES as i16 {
+            feed(&mut cal, [500 + jitter % 3, 500, 500, 500 - jitter % 5]);
+        }
+        cal.handle_command(CalibrationCommandMsg::Next);
+        assert_eq!(cal.step, CalibrationStep::DetectAxis);
+        assert_eq!(cal.deadbands, vec![2, 0, 0, 4]);
+
+        // Thrust on axis 2; a short twitch of axis 0 is not offered.
+        feed(&mut cal, [900, 500, 500, 500]);
+        feed(&mut cal, [500, 500, 500, 500]);
+        for _ in 0..7 {
+            feed(&mut cal, [500, 500, 900, 500]);
+        }
+        let state = cal.state();
+        assert_eq!(
+            (state.step, state.detected_axis),
+            (CalibrationStep::ConfirmAxis, Some(2))
+        );
+        cal.handle_command(CalibrationCommandMsg::Next);
+
+        // Pushing Direction low raises axis 3, so it is reversed; the
+        // first pick is retried.
+        for _ in 0..7 {
+            feed(&mut cal, [100, 500, 500, 500]);
+        }
+        cal.handle_command(CalibrationCommandMsg::Back);
+        assert_eq!(cal.step, CalibrationStep::DetectAxis);
+        for axes in [
+            [500, 500, 500, 900],
+            [500, 100, 500, 500],
+            [100, 500, 500, 500],
+        ] {
+            for _ in 0..7 {
+                feed(&mut cal, axes);
             }
-        });
+            cal.handle_command(CalibrationCommandMsg::Next);
+        }
+        assert_eq!(cal.step, CalibrationStep::MinMax);
+        assert_eq!(cal.data.channel_indexs, vec![2, 3, 1, 0]);
+        assert!(!cal.data.channel_infos[0].rev);
+        assert!(cal.data.channel_infos[1].rev);
+        assert_eq!(cal.data.channel_infos[0].deadband, 0);
+        assert_eq!(cal.data.channel_infos[1].deadband, 4);
+
+        feed(&mut cal, [0, 0, 400, 0]);
+        cal.handle_command(CalibrationCommandMsg::Next);
+        assert_eq!(cal.state().status_text, "Move Thrust to both ends");
+        cal.handle_command(CalibrationCommandMsg::Cancel);
+        assert_eq!(cal.step, CalibrationStep::Idle);
+        assert!(cal.data.channel_infos.is_empty());
+    }
 
-        loop {
-            match cal.state {
-                CalibrateState::Finish => {
-                    cal.do_step();
-                    break;
-                }
-                _ => cal.do_step(),
+    #[test]
+    fn test_throttle_resting_at_low_stop() {
+        let mut cal = Calibration::new();
+        let mut now = Instant::now();
+        let mut feed = |cal: &mut Calibration, axes: [i16; 4]| {
+            now += Duration::from_millis(100);
+            cal.update(&mock_frame(axes.to_vec()), now);
+        };
+
+        cal.handle_command(CalibrationCommandMsg::Start);
+        for _ in 0..CENTER_MIN_FRAMES {
+            feed(&mut cal, [100, 500, 500, 500]);
+        }
+        cal.handle_command(CalibrationCommandMsg::Next);
+        assert_eq!(
+            cal.state().instruction,
+            "Push Thrust to its highest end and hold"
+        );
+
+        for axes in [
+            [1900, 500, 500, 500],
+            [100, 100, 500, 500],
+            [100, 500, 100, 500],
+            [100, 500, 500, 100],
+        ] {
+            for _ in 0..7 {
+                feed(&mut cal, axes);
+            }
+            cal.handle_command(CalibrationCommandMsg::Next);
+        }
+        assert_eq!(cal.step, CalibrationStep::MinMax);
+        assert_eq!(cal.data.channel_indexs, vec![0, 1, 2, 3]);
+        let thrust = &cal.data.channel_infos[0];
+        assert_eq!((thrust.center, thrust.rev), (0, false));
+
+        // The throttle only ever moves up from its rest point.
+        feed(&mut cal, [1900, 500, 500, 500]);
+        cal.handle_command(CalibrationCommandMsg::Next);
+        assert_eq!(cal.state().status_text, "Move Direction to both ends");
+    }
+
+    #[test]
+    fn test_detected_axes_drive_the_mixer() {
+        let _guard = store::TestCwdGuard::enter();
+        let mut cal = Calibration::new();
+        let mut now = Instant::now();
+        let mut feed = |cal: &mut Calibration, axes: [i16; 4]| {
+            now += Duration::from_millis(100);
+            cal.update(
+                &InputFrameMsg::from_axes(InputSource::Adc, axes.to_vec()),
+                now,
+            );
+        };
+
+        cal.handle_command(CalibrationCommandMsg::Start);
+        cal.profile = "wizard".to_string();
+        for _ in 0..CENTER_MIN_FRAMES {
+            feed(&mut cal, [500, 500, 100, 500]);
+        }
+        cal.handle_command(CalibrationCommandMsg::Next);
+        // Thrust, Direction, Aileron (reversed) and Elevator, none on the
+        // axis the default input mapping names.
+        for axes in [
+            [500, 500, 900, 500],
+            [100, 500, 100, 500],
+            [500, 500, 100, 900],
+            [500, 100, 100, 500],
+        ] {
+            for _ in 0..7 {
+                feed(&mut cal, axes);
             }
+            cal.handle_command(CalibrationCommandMsg::Next);
         }
+        feed(&mut cal, [0, 0, 100, 0]);
+        feed(&mut cal, [1000; 4]);
+        cal.handle_command(CalibrationCommandMsg::Next);
+        assert_eq!(cal.step, CalibrationStep::Done);
+
+        let input = InputConfig {
+            calibration_profile: "wizard".to_string(),
+            ..InputConfig::default()
+        };
+        let set = CalibrationSet::load(input, ModelCalibration::default());
+        let mut router = InputRouter::new(Duration::from_secs(1));
+        let at = Instant::now();
+        router.update(
+            InputFrameMsg::from_axes(InputSource::Adc, vec![1000, 500, 1000, 0]),
+            at,
+        );
+        let raw = read_inputs(&router, &ModelConfig::default(), &set, at);
+        assert_eq!(
+            raw,
+            vec![
+                (ControlRole::Thrust, 10000),
+                (ControlRole::Direction, 10000),
+                (ControlRole::Aileron, 10000),
+                (ControlRole::Elevator, 5000),
+            ]
+        );
     }
 }
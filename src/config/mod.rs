@@ -27,13 +27,25 @@ pub struct ModelConfig {
     #[serde(default)]
     pub input_mapping: InputMapping,
     #[serde(default)]
+    pub calibration: ModelCalibration,
+    #[serde(default)]
     pub mixer: MixerConfig,
     #[serde(default)]
     pub output: OutputConfig,
     #[serde(default)]
     pub telemetry: TelemetryConfig,
     #[serde(default)]
+    pub elrs: ElrsConfig,
+    #[serde(default)]
     pub profiles: Vec<RateProfile>,
+    #[serde(default)]
+    pub logical_switches: Vec<LogicalSwitchConfig>,
+    #[serde(default)]
+    pub flight_modes: Vec<FlightModeConfig>,
+    #[serde(default)]
+    pub special_functions: Vec<SpecialFunctionConfig>,
+    #[serde(default)]
+    pub timers: Vec<TimerConfig>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -54,12 +66,53 @@ pub struct AudioConfig {
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct InputConfig {
+    /// Calibration profile name, a file under `store::CALIBRATION_DIR`.
     #[serde(default)]
     pub calibration_profile: String,
+    /// Profiles for sources with their own sticks, e.g. a USB joystick.
+    #[serde(default)]
+    pub calibration_sources: Vec<SourceCalibration>,
     #[serde(default)]
     pub source_priority: Vec<InputSource>,
 }
 
+impl InputConfig {
+    /// Calibration profile for frames from `source`. Per-source entries win
+    /// over plain profiles, and the model's over the radio's at each level,
+    /// since a source's sticks keep their calibration across models.
+    pub fn calibration_profile_for<'a>(
+        &'a self,
+        model: &'a ModelCalibration,
+        source: InputSource,
+    ) -> &'a str {
+        let for_source = |sources: &'a [SourceCalibration]| {
+            sources
+                .iter()
+                .find(|entry| entry.source == source)
+                .map(|entry| entry.profile.as_str())
+        };
+        for_source(&model.sources)
+            .or_else(|| for_source(&self.calibration_sources))
+            .or_else(|| (!model.profile.is_empty()).then_some(model.profile.as_str()))
+            .unwrap_or(&self.calibration_profile)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SourceCalibration {
+    pub source: InputSource,
+    pub profile: String,
+}
+
+/// A model's override of the radio's calibration profiles; empty keeps them.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub struct ModelCalibration {
+    #[serde(default)]
+    pub profile: String,
+    #[serde(default)]
+    pub sources: Vec<SourceCalibration>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct InputMapping {
     #[serde(default)]
@@ -72,6 +125,8 @@ pub struct InputChannel {
     #[serde(default)]
     pub source: InputSource,
     #[serde(default)]
+    pub kind: InputKind,
+    #[serde(default)]
     pub index: u8,
     #[serde(default)]
     pub reversed: bool,
@@ -79,10 +134,299 @@ pub struct InputChannel {
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct MixerConfig {
+    #[serde(default)]
+    pub inputs: Vec<MixerInput>,
+    #[serde(default)]
+    pub mixes: Vec<MixLine>,
+    #[serde(default)]
+    pub curves: Vec<CurveConfig>,
     #[serde(default)]
     pub outputs: Vec<MixerOutput>,
 }
 
+/// Input stage: rate, expo and curve applied to a stick before any mix line sees it.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct MixerInput {
+    pub role: ControlRole,
+    #[serde(default = "default_weight")]
+    pub weight: i16,
+    #[serde(default)]
+    pub expo: i16,
+    #[serde(default)]
+    pub curve: CurveRef,
+    #[serde(default)]
+    pub curve_name: String,
+}
+
+/// One contribution to an output channel. All active lines targeting the same
+/// output are summed; outputs without any line follow their own input 1:1.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct MixLine {
+    pub output: ControlRole,
+    pub source: ControlRole,
+    #[serde(default = "default_weight")]
+    pub weight: i16,
+    #[serde(default)]
+    pub offset: i16,
+    #[serde(default)]
+    pub curve: CurveRef,
+    #[serde(default)]
+    pub expo: i16,
+    #[serde(default)]
+    pub curve_name: String,
+    #[serde(default)]
+    pub condition: Option<MixCondition>,
+    /// Flight mode names this line is active in; empty means every mode.
+    #[serde(default)]
+    pub flight_modes: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum MixCondition {
+    SourceAbove { source: ControlRole, value: i16 },
+    SourceBelow { source: ControlRole, value: i16 },
+    Switch { switch: SwitchRef },
+}
+
+/// One logical switch. Switches are evaluated every mixer cycle in list order,
+/// so `SwitchRef::Logical` pointing at a later entry sees the previous cycle.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct LogicalSwitchConfig {
+    #[serde(default)]
+    pub name: String,
+    pub function: LogicalFunction,
+    /// Extra switch that must also be on, like EdgeTX's "AND switch".
+    #[serde(default)]
+    pub and_switch: Option<SwitchRef>,
+    /// The function has to hold true this long before the switch turns on.
+    #[serde(default)]
+    pub delay_ms: u32,
+    /// Turns the switch off again after this long; 0 keeps it on.
+    #[serde(default)]
+    pub duration_ms: u32,
+}
+
+/// A flight mode. The first mode whose `switch` is on is active; a mode
+/// without a switch is the fallback when nothing else matches.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct FlightModeConfig {
+    pub name: String,
+    #[serde(default)]
+    pub switch: Option<SwitchRef>,
+    /// How long this mode takes to blend in when it becomes active.
+    #[serde(default)]
+    pub fade_in_ms: u32,
+    /// How long this mode takes to blend out when another mode takes over.
+    #[serde(default)]
+    pub fade_out_ms: u32,
+    /// `RateProfile::name` to use; empty leaves rates and expo untouched.
+    #[serde(default)]
+    pub profile: String,
+    #[serde(default)]
+    pub trims: Vec<FlightModeTrim>,
+}
+
+/// Per-mode trim in config units (-1000..1000), added to the shaped input.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct FlightModeTrim {
+    pub role: ControlRole,
+    pub value: i16,
+}
+
+/// Per-model timer. Persistent timers keep their accumulated run time in the
+/// model's `ModelState` so it survives restarts.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TimerConfig {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub direction: TimerDirection,
+    /// Where a `Down` timer starts counting from.
+    #[serde(default)]
+    pub start_secs: u32,
+    #[serde(default)]
+    pub run: TimerRun,
+    #[serde(default)]
+    pub minute_alert: bool,
+    /// `Down` timers announce every second of the last `countdown_secs`.
+    #[serde(default)]
+    pub countdown_secs: u32,
+    #[serde(default)]
+    pub persistent: bool,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TimerDirection {
+    #[default]
+    Up,
+    Down,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TimerRun {
+    #[default]
+    Always,
+    /// Runs while the switch is on.
+    Switch { switch: SwitchRef },
+    /// Runs while the throttle is above idle.
+    Throttle,
+    /// Runs at a speed proportional to the throttle position.
+    ThrottleProportional,
+    /// Starts the first time the switch turns on (e.g. arming) and keeps
+    /// running until the timer is reset.
+    StartOnSwitch { switch: SwitchRef },
+}
+
+/// EdgeTX-style special function: runs `action` when `switch` turns on.
+/// Channel overrides and logging stay active for as long as the switch is on.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SpecialFunctionConfig {
+    pub switch: SwitchRef,
+    pub action: SpecialAction,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SpecialAction {
+    /// Holds output slot `channel` at `value` (-1000..1000), past limits.
+    OverrideChannel {
+        channel: u8,
+        value: i16,
+    },
+    ResetTimer {
+        timer: u8,
+    },
+    /// Switches to the model with this `ModelConfig::id`.
+    SelectModel {
+        model: String,
+    },
+    PlaySound {
+        sound: String,
+    },
+    Haptic {
+        duration_ms: u32,
+    },
+    /// Appends the output channels to a CSV file under `logs/`.
+    Logging {
+        #[serde(default = "default_log_interval")]
+        interval_ms: u32,
+    },
+    ElrsBind,
+    /// Clears telemetry min/max and derived sensors, e.g. before a new flight.
+    ResetTelemetry,
+    OpenPage {
+        page: UiPageRef,
+    },
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum UiPageRef {
+    Launcher,
+    System,
+    Control,
+    Models,
+    Flight,
+    Cloud,
+    Sensor,
+    Trainer,
+    Elrs,
+    About,
+}
+
+/// Thresholds `x` are in config units (-1000..1000) for inputs and channels
+/// and in raw sensor units for telemetry.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum LogicalFunction {
+    AGreaterX {
+        a: LogicalSource,
+        x: i32,
+    },
+    ALessX {
+        a: LogicalSource,
+        x: i32,
+    },
+    AbsGreaterX {
+        a: LogicalSource,
+        x: i32,
+    },
+    AbsLessX {
+        a: LogicalSource,
+        x: i32,
+    },
+    AGreaterB {
+        a: LogicalSource,
+        b: LogicalSource,
+    },
+    ALessB {
+        a: LogicalSource,
+        b: LogicalSource,
+    },
+    And {
+        a: SwitchRef,
+        b: SwitchRef,
+    },
+    Or {
+        a: SwitchRef,
+        b: SwitchRef,
+    },
+    Xor {
+        a: SwitchRef,
+        b: SwitchRef,
+    },
+    /// On for one cycle when `a` turns off after being on for `min_ms..=max_ms`;
+    /// `max_ms` 0 means no upper bound.
+    Edge {
+        a: SwitchRef,
+        #[serde(default)]
+        min_ms: u32,
+        #[serde(default)]
+        max_ms: u32,
+    },
+    /// Latches on when `set` is on and off when `reset` is on; reset wins.
+    Sticky {
+        set: SwitchRef,
+        reset: SwitchRef,
+    },
+    /// Free-running blinker: on for `on_ms`, then off for `off_ms`.
+    Timer {
+        on_ms: u32,
+        off_ms: u32,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LogicalSource {
+    /// Shaped input value after the mixer's input stage.
+    Input(ControlRole),
+    /// Output slot in `OutputConfig::channel_order`, from the previous cycle.
+    Channel(u8),
+    /// Telemetry sensor by `TelemetrySensorConfig::key`.
+    Telemetry(String),
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SwitchRef {
+    /// Index into `ModelConfig::logical_switches`.
+    Logical(u8),
+    /// On while the shaped input is above center.
+    Input(ControlRole),
+}
+
+/// Custom curve: `points` are evenly spaced over -1000..1000 on the input axis.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CurveConfig {
+    pub name: String,
+    #[serde(default)]
+    pub points: Vec<i16>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct MixerOutput {
     pub role: ControlRole,
@@ -93,6 +437,10 @@ pub struct MixerOutput {
     #[serde(default)]
     pub curve: CurveRef,
     #[serde(default)]
+    pub expo: i16,
+    #[serde(default)]
+    pub curve_name: String,
+    #[serde(default)]
     pub limits: OutputLimits,
 }
 
@@ -104,6 +452,96 @@ pub struct OutputConfig {
     pub channel_order: Vec<ControlRole>,
     #[serde(default)]
     pub failsafe: Vec<i16>,
+    #[serde(default)]
+    pub sbus: SbusConfig,
+    #[serde(default)]
+    pub ppm: PpmConfig,
+    #[serde(default)]
+    pub crsf: CrsfConfig,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CrsfConfig {
+    /// Rate proposed to the module once connected; 0 keeps whatever it
+    /// answers on. High packet rates need more than 115200.
+    #[serde(default = "default_crsf_baudrate")]
+    pub baudrate: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SbusConfig {
+    /// 14 for standard SBUS, 7 for fast receivers and flight controllers.
+    #[serde(default = "default_sbus_frame_interval")]
+    pub frame_interval_ms: u16,
+    /// Roles sent as digital channels 17 and 18, on above center.
+    #[serde(default)]
+    pub digital_channels: Vec<ControlRole>,
+    /// Standard SBUS idles low. A UART idles high, so this needs
+    /// `inverter_gpio` or a UART inverted in the device tree.
+    #[serde(default = "default_sbus_inverted")]
+    pub inverted: bool,
+    /// sysfs GPIO `value` file enabling the board's line inverter (e.g. an
+    /// XOR gate), set from `inverted` when the driver opens.
+    #[serde(default)]
+    pub inverter_gpio: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PpmConfig {
+    #[serde(default = "default_ppm_channels")]
+    pub channels: u8,
+    /// Whole train including the sync gap.
+    #[serde(default = "default_ppm_frame_length")]
+    pub frame_length_us: u32,
+    /// Separator pulse before every channel and the sync gap.
+    #[serde(default = "default_ppm_pulse")]
+    pub pulse_us: u16,
+    #[serde(default)]
+    pub polarity: PpmPolarity,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum PpmPolarity {
+    /// Line idles high, pulses pull it low.
+    #[default]
+    Negative,
+    /// Line idles low, pulses drive it high.
+    Positive,
+}
+
+/// What LinTx remembers about a model between runs. It lives in
+/// `store::STATE_DIR`, apart from the model file, so saving it never rewrites
+/// what the user wrote there.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub struct ModelState {
+    /// Accumulated run time per timer in `ModelConfig::timers` order; only
+    /// persistent timers pick it up again.
+    #[serde(default)]
+    pub timer_values_ms: Vec<u64>,
+    /// CRSF rate the module last ran at, probed first on the next connect.
+    #[serde(default)]
+    pub crsf_baudrate: u32,
+}
+
+/// ELRS module parameters saved with the model and pushed back from the
+/// ELRS page, since every airframe wants its own packet rate and power.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub struct ElrsConfig {
+    #[serde(default)]
+    pub params: Vec<ElrsParamValue>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ElrsParamValue {
+    /// Folder path and label, e.g. `TX Power/Max Power`.
+    pub key: String,
+    /// Raw value; the option index for selections.
+    pub value: i64,
+    /// Displayed value. Selections are matched by this first, as option
+    /// lists differ between firmware versions.
+    #[serde(default)]
+    pub text: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -114,6 +552,8 @@ pub struct TelemetryConfig {
     pub sensors: Vec<TelemetrySensorConfig>,
 }
 
+/// One telemetry sensor. The published value is
+/// `raw * ratio_percent / 100 + offset`, shown with `precision` decimals.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct TelemetrySensorConfig {
     #[serde(default)]
@@ -122,8 +562,54 @@ pub struct TelemetrySensorConfig {
     pub unit: String,
     #[serde(default)]
     pub enabled: bool,
+    #[serde(default = "default_sensor_ratio")]
+    pub ratio_percent: i32,
+    #[serde(default)]
+    pub offset: i32,
+    #[serde(default)]
+    pub precision: u8,
+    /// The sensor reads as lost when it has not updated for this long; 0 never times out.
+    #[serde(default = "default_sensor_lost_timeout")]
+    pub lost_timeout_ms: u32,
+    /// Computed from other sensors instead of read from a frame; `key` is then
+    /// only a name.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub derived: Option<DerivedSensor>,
+}
+
+impl TelemetrySensorConfig {
+    pub fn new(key: &str, unit: &str) -> Self {
+        Self {
+            key: key.to_string(),
+            unit: unit.to_string(),
+            enabled: true,
+            ratio_percent: default_sensor_ratio(),
+            offset: 0,
+            precision: 0,
+            lost_timeout_ms: default_sensor_lost_timeout(),
+            derived: None,
+        }
+    }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum DerivedSensor {
+    /// mAh drawn, integrated from a current sensor in 0.1 A.
+    Consumption { current: String },
+    /// Meters from the first GPS fix of the flight.
+    Distance,
+    /// Per-cell voltage in 0.01 V from a pack voltage sensor in 0.1 V. `cells`
+    /// of 0 detects the cell count from the first reading.
+    CellVoltage {
+        voltage: String,
+        #[serde(default)]
+        cells: u8,
+    },
+}
+
+/// Dual rates applied to the aileron/elevator/direction inputs. Rates are a
+/// percentage of stick throw; `expo_percent` is added on top of the input expo.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct RateProfile {
     #[serde(default = "default_profile_name")]
@@ -150,6 +636,17 @@ pub enum InputSource {
     Unknown,
 }
 
+/// Which part of an input frame an `InputChannel::index` points into.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum InputKind {
+    #[default]
+    Axis,
+    Switch,
+    Button,
+    Trim,
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
 #[serde(rename_all = "snake_case")]
 pub enum ControlRole {
@@ -184,6 +681,22 @@ pub enum OutputProtocol {
     Sbus,
 }
 
+impl ControlRole {
+    pub fn short_name(self) -> &'static str {
+        match self {
+            Self::Thrust => "THR",
+            Self::Direction => "RUD",
+            Self::Aileron => "AIL",
+            Self::Elevator => "ELE",
+            Self::Arm => "ARM",
+            Self::Mode => "MODE",
+            Self::Aux1 => "AUX1",
+            Self::Aux2 => "AUX2",
+            Self::Unknown => "--",
+        }
+    }
+}
+
 impl OutputProtocol {
     pub fn display_name(self) -> &'static str {
         match self {
@@ -225,10 +738,16 @@ impl Default for ModelConfig {
             id: default_model_id(),
             name: default_model_name(),
             input_mapping: InputMapping::default(),
+            calibration: ModelCalibration::default(),
             mixer: MixerConfig::default(),
             output: OutputConfig::default(),
             telemetry: TelemetryConfig::default(),
+            elrs: ElrsConfig::default(),
             profiles: vec![RateProfile::default()],
+            logical_switches: Vec::new(),
+            flight_modes: Vec::new(),
+            special_functions: Vec::new(),
+            timers: Vec::new(),
         }
     }
 }
@@ -255,6 +774,7 @@ impl Default for InputConfig {
     fn default() -> Self {
         Self {
             calibration_profile: "joystick.toml".to_string(),
+            calibration_sources: Vec::new(),
             source_priority: vec![InputSource::Adc, InputSource::Crsf, InputSource::Mock],
         }
     }
@@ -267,24 +787,28 @@ impl Default for InputMapping {
                 InputChannel {
                     role: ControlRole::Thrust,
                     source: InputSource::Adc,
+                    kind: InputKind::Axis,
                     index: 0,
                     reversed: false,
                 },
                 InputChannel {
                     role: ControlRole::Direction,
                     source: InputSource::Adc,
+                    kind: InputKind::Axis,
                     index: 1,
                     reversed: false,
                 },
                 InputChannel {
                     role: ControlRole::Aileron,
                     source: InputSource::Adc,
+                    kind: InputKind::Axis,
                     index: 2,
                     reversed: false,
                 },
                 InputChannel {
                     role: ControlRole::Elevator,
                     source: InputSource::Adc,
+                    kind: InputKind::Axis,
                     index: 3,
                     reversed: false,
                 },
@@ -296,6 +820,9 @@ impl Default for InputMapping {
 impl Default for MixerConfig {
     fn default() -> Self {
         Self {
+            inputs: Vec::new(),
+            mixes: Vec::new(),
+            curves: Vec::new(),
             outputs: vec![
                 MixerOutput::new(ControlRole::Thrust),
                 MixerOutput::new(ControlRole::Direction),
@@ -313,11 +840,29 @@ impl MixerOutput {
             weight: default_weight(),
             offset: 0,
             curve: CurveRef::Linear,
+            expo: 0,
+            curve_name: String::new(),
             limits: OutputLimits::default(),
         }
     }
 }
 
+impl MixLine {
+    pub fn new(output: ControlRole, source: ControlRole) -> Self {
+        Self {
+            output,
+            source,
+            weight: default_weight(),
+            offset: 0,
+            curve: CurveRef::Linear,
+            expo: 0,
+            curve_name: String::new(),
+            condition: None,
+            flight_modes: Vec::new(),
+        }
+    }
+}
+
 impl Default for OutputConfig {
     fn default() -> Self {
         Self {
@@ -327,8 +872,45 @@ impl Default for OutputConfig {
                 ControlRole::Elevator,
                 ControlRole::Thrust,
                 ControlRole::Direction,
+                ControlRole::Arm,
+                ControlRole::Mode,
+                ControlRole::Aux1,
+                ControlRole::Aux2,
             ],
-            failsafe: vec![0, 0, 0, 0],
+            failsafe: vec![0, 0, -1000, 0, 0, 0, 0, 0],
+            sbus: SbusConfig::default(),
+            ppm: PpmConfig::default(),
+            crsf: CrsfConfig::default(),
+        }
+    }
+}
+
+impl Default for CrsfConfig {
+    fn default() -> Self {
+        Self {
+            baudrate: default_crsf_baudrate(),
+        }
+    }
+}
+
+impl Default for PpmConfig {
+    fn default() -> Self {
+        Self {
+            channels: default_ppm_channels(),
+            frame_length_us: default_ppm_frame_length(),
+            pulse_us: default_ppm_pulse(),
+            polarity: PpmPolarity::default(),
+        }
+    }
+}
+
+impl Default for SbusConfig {
+    fn default() -> Self {
+        Self {
+            frame_interval_ms: default_sbus_frame_interval(),
+            digital_channels: Vec::new(),
+            inverted: default_sbus_inverted(),
+            inverter_gpio: String::new(),
         }
     }
 }
@@ -338,21 +920,9 @@ impl Default for TelemetryConfig {
         Self {
             enabled: true,
             sensors: vec![
-                TelemetrySensorConfig {
-                    key: "rssi".to_string(),
-                    unit: "percent".to_string(),
-                    enabled: true,
-                },
-                TelemetrySensorConfig {
-                    key: "link_quality".to_string(),
-                    unit: "percent".to_string(),
-                    enabled: true,
-                },
-                TelemetrySensorConfig {
-                    key: "remote_battery".to_string(),
-                    unit: "percent".to_string(),
-                    enabled: true,
-                },
+                TelemetrySensorConfig::new("rssi", "dbm"),
+                TelemetrySensorConfig::new("link_quality", "percent"),
+                TelemetrySensorConfig::new("remote_battery", "percent"),
             ],
         }
     }
@@ -421,6 +991,42 @@ fn default_expo() -> u8 {
     0
 }
 
+fn default_log_interval() -> u32 {
+    100
+}
+
+fn default_sensor_ratio() -> i32 {
+    100
+}
+
+fn default_sensor_lost_timeout() -> u32 {
+    5000
+}
+
+fn default_sbus_frame_interval() -> u16 {
+    14
+}
+
+fn default_sbus_inverted() -> bool {
+    true
+}
+
+fn default_ppm_channels() -> u8 {
+    8
+}
+
+fn default_ppm_frame_length() -> u32 {
+    22_500
+}
+
+fn default_ppm_pulse() -> u16 {
+    300
+}
+
+fn default_crsf_baudrate() -> u32 {
+    400_000
+}
+
 fn default_limit_min() -> i16 {
     -1000
 }
@@ -468,4 +1074,155 @@ mod tests {
         assert_eq!(radio, restored_radio);
         assert_eq!(model, restored_model);
     }
+
+    #[test]
+    fn test_calibration_profile_resolution() {
+        let mut input = InputConfig::default();
+        let mut model = ModelCalibration::default();
+        assert_eq!(
+            input.calibration_profile_for(&model, InputSource::Joydev),
+            "joystick.toml"
+        );
+
+        model.profile = "heli".to_string();
+        input.calibration_sources.push(SourceCalibration {
+            source: InputSource::Joydev,
+            profile: "usb_stick".to_string(),
+        });
+        assert_eq!(
+            input.calibration_profile_for(&model, InputSource::Adc),
+            "heli"
+        );
+        assert_eq!(
+            input.calibration_profile_for(&model, InputSource::Joydev),
+            "usb_stick"
+        );
+
+        model.sources.push(SourceCalibration {
+            source: InputSource::Joydev,
+            profile: "usb_stick_heli".to_string(),
+        });
+        assert_eq!(
+            input.calibration_profile_for(&model, InputSource::Joydev),
+            "usb_stick_heli"
+        );
+    }
+
+    #[test]
+    fn test_logical_switches_parse_from_toml() {
+        let model: ModelConfig = toml::from_str(
+            r#"
+            [[logical_switches]]
+            name = "armed"
+            function = { kind = "a_greater_x", a = { input = "arm" }, x = 0 }
+
+            [[logical_switches]]
+            function = { kind = "sticky", set = { logical = 0 }, reset = { input = "mode" } }
+            and_switch = { logical = 0 }
+            delay_ms = 500
+
+            [[mixer.mixes]]
+            output = "thrust"
+            source = "thrust"
+            condition = { kind = "switch", switch = { logical = 1 } }
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(model.logical_switches.len(), 2);
+        assert_eq!(
+            model.logical_switches[0].function,
+            LogicalFunction::AGreaterX {
+                a: LogicalSource::Input(ControlRole::Arm),
+                x: 0,
+            }
+        );
+        assert_eq!(model.logical_switches[1].delay_ms, 500);
+        assert_eq!(
+            model.mixer.mixes[0].condition,
+            Some(MixCondition::Switch {
+                switch: SwitchRef::Logical(1),
+            })
+        );
+
+        let restored: ModelConfig = toml::from_str(&toml::to_string(&model).unwrap()).unwrap();
+        assert_eq!(model, restored);
+    }
+
+    #[test]
+    fn test_special_functions_parse_from_toml() {
+        let model: ModelConfig = toml::from_str(
+            r#"
+            [[special_functions]]
+            switch = { logical = 0 }
+            action = { kind = "override_channel", channel = 2, value = -1000 }
+
+            [[special_functions]]
+            switch = { input = "aux2" }
+            action = { kind = "logging" }
+
+            [[special_functions]]
+            switch = { input = "aux1" }
+            action = { kind = "open_page", page = "elrs" }
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            model
+                .special_functions
+                .iter()
+                .map(|function| function.action.clone())
+                .collect::<Vec<_>>(),
+            vec![
+                SpecialAction::OverrideChannel {
+                    channel: 2,
+                    value: -1000,
+                },
+                SpecialAction::Logging { interval_ms: 100 },
+                SpecialAction::OpenPage {
+                    page: UiPageRef::Elrs,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_telemetry_sensors_parse_with_defaults_and_derived() {
+        let model: ModelConfig = toml::from_str(
+            r#"
+            [[telemetry.sensors]]
+            key = "voltage"
+            unit = "v"
+            enabled = true
+            precision = 1
+
+            [[telemetry.sensors]]
+            key = "cell"
+            unit = "v"
+            enabled = true
+            precision = 2
+            derived = { kind = "cell_voltage", voltage = "voltage" }
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            model.telemetry.sensors,
+            vec![
+                TelemetrySensorConfig {
+                    precision: 1,
+                    ..TelemetrySensorConfig::new("voltage", "v")
+                },
+                TelemetrySensorConfig {
+                    precision: 2,
+                    derived: Some(DerivedSensor::CellVoltage {
+                        voltage: "voltage".to_string(),
+                        cells: 0,
+                    }),
+                    ..TelemetrySensorConfig::new("cell", "v")
+                },
+            ]
+        );
+    }
 }
@@ -1,18 +1,34 @@
 use std::{
+    collections::BTreeMap,
     fs,
-    io::{self, ErrorKind},
+    io::{self, ErrorKind, Write},
     path::{Path, PathBuf},
+    sync::Mutex,
+    time::SystemTime,
 };
 
 use super::{
-    ControlRole, CurveRef, MixerOutput, ModelConfig, OutputProtocol, RadioConfig, RateProfile,
+    ControlRole, ElrsParamValue, FlightModeConfig, FlightModeTrim, MixLine, MixerOutput,
+    ModelConfig, ModelState, OutputProtocol, RadioConfig, RateProfile, SwitchRef, TimerConfig,
+    TimerDirection, TimerRun,
 };
 
 pub const RADIO_CONFIG_PATH: &str = "radio.toml";
 pub const MODELS_DIR: &str = "models";
+pub const CALIBRATION_DIR: &str = "calibration";
+pub const STATE_DIR: &str = "state";
+
+/// Modification time and size of a file; a change in either counts as an
+/// edit.
+pub type FileStamp = (SystemTime, u64);
+
+/// Stamps the files written through `write_atomic` were left with, so
+/// `config_watch` can tell LinTx's own saves from outside edits.
+static WRITTEN: Mutex<BTreeMap<PathBuf, FileStamp>> = Mutex::new(BTreeMap::new());
 
 pub fn ensure_default_layout() -> io::Result<()> {
     fs::create_dir_all(MODELS_DIR)?;
+    fs::create_dir_all(CALIBRATION_DIR)?;
 
     if !Path::new(RADIO_CONFIG_PATH).exists() {
         save_radio_config(&RadioConfig::default())?;
@@ -25,6 +41,12 @@ pub fn ensure_default_layout() -> io::Result<()> {
     }
 
     let radio = load_radio_config()?;
+    // Older releases kept the one calibration next to the binary.
+    let profile_path = calibration_path(&radio.input.calibration_profile);
+    if Path::new(crate::CALIBRATE_FILENAME).exists() && !profile_path.exists() {
+        fs::copy(crate::CALIBRATE_FILENAME, profile_path)?;
+    }
+
     if load_model_config(&radio.active_model).is_err() {
         let fallback_id = if load_model_config("quad_x").is_ok() {
             "quad_x".to_string()
@@ -49,7 +71,7 @@ pub fn load_radio_config() -> io::Result<RadioConfig> {
 pub fn save_radio_config(config: &RadioConfig) -> io::Result<()> {
     let content = toml::to_string_pretty(config)
         .map_err(|err| io::Error::new(ErrorKind::InvalidData, err))?;
-    fs::write(RADIO_CONFIG_PATH, content)
+    write_atomic(Path::new(RADIO_CONFIG_PATH), &content)
 }
 
 pub fn list_models() -> io::Result<Vec<ModelConfig>> {
@@ -74,7 +96,48 @@ pub fn save_model_config(config: &ModelConfig) -> io::Result<()> {
     fs::create_dir_all(MODELS_DIR)?;
     let content = toml::to_string_pretty(config)
         .map_err(|err| io::Error::new(ErrorKind::InvalidData, err))?;
-    fs::write(model_path(&config.id), content)
+    write_atomic(&model_path(&config.id), &content)
+}
+
+/// Saved runtime state of model `id`; the default if nothing was saved yet.
+pub fn load_model_state(id: &str) -> io::Result<ModelState> {
+    match fs::read_to_string(state_path(id)) {
+        Ok(content) => {
+            toml::from_str(&content).map_err(|err| io::Error::new(ErrorKind::InvalidData, err))
+        }
+        Err(err) if err.kind() == ErrorKind::NotFound => Ok(ModelState::default()),
+        Err(err) => Err(err),
+    }
+}
+
+/// Changes the saved state of model `id`. Callers in different threads are
+/// serialized so no one's fields get lost.
+pub fn update_model_state(id: &str, update: impl FnOnce(&mut ModelState)) -> io::Result<()> {
+    static STATE_LOCK: Mutex<()> = Mutex::new(());
+    let _guard = STATE_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    let mut state = load_model_state(id)?;
+    update(&mut state);
+    let content = toml::to_string_pretty(&state)
+        .map_err(|err| io::Error::new(ErrorKind::InvalidData, err))?;
+    fs::create_dir_all(STATE_DIR)?;
+    write_atomic(&state_path(id), &content)
+}
+
+/// Remembers the accumulated timer values of model `id`.
+pub fn save_timer_values(id: &str, values_ms: &[u64]) -> io::Result<()> {
+    update_model_state(id, |state| state.timer_values_ms = values_ms.to_vec())
+}
+
+/// Remembers the CRSF rate the module of model `id` was connected at.
+pub fn save_crsf_baudrate(id: &str, baudrate: u32) -> io::Result<()> {
+    update_model_state(id, |state| state.crsf_baudrate = baudrate)
+}
+
+/// Replaces the ELRS parameter snapshot of a model.
+pub fn save_elrs_params(id: &str, params: Vec<ElrsParamValue>) -> io::Result<()> {
+    let mut model = load_model_config(id)?;
+    model.elrs.params = params;
+    save_model_config(&model)
 }
 
 pub fn load_active_model() -> io::Result<ModelConfig> {
@@ -90,6 +153,19 @@ pub fn set_active_model(id: &str) -> io::Result<ModelConfig> {
     Ok(model)
 }
 
+/// File of a calibration profile; a `.toml` suffix on `profile` is optional.
+pub fn calibration_path(profile: &str) -> PathBuf {
+    let name = profile.strip_suffix(".toml").unwrap_or(profile);
+    Path::new(CALIBRATION_DIR).join(format!("{}.toml", sanitize_id(name)))
+}
+
+/// Makes `profile` the radio's calibration profile.
+pub fn set_calibration_profile(profile: &str) -> io::Result<()> {
+    let mut radio = load_radio_config()?;
+    radio.input.calibration_profile = profile.to_string();
+    save_radio_config(&radio)
+}
+
 fn list_model_paths() -> io::Result<Vec<PathBuf>> {
     let mut paths = Vec::new();
     if !Path::new(MODELS_DIR).exists() {
@@ -108,7 +184,47 @@ fn list_model_paths() -> io::Result<Vec<PathBuf>> {
 }
 
 fn model_path(id: &str) -> PathBuf {
-    Path::new(MODELS_DIR).join(format!("{}.toml", sanitize_id(id)))
+    Path::new(MODELS_DIR).join(model_file_name(id))
+}
+
+fn state_path(id: &str) -> PathBuf {
+    Path::new(STATE_DIR).join(model_file_name(id))
+}
+
+/// Replaces `path` in one step, so readers and power cuts see either the old
+/// or the new content, and remembers its stamp for `is_own_write`.
+pub fn write_atomic(path: &Path, content: &str) -> io::Result<()> {
+    let tmp = path.with_extension("toml.tmp");
+    let mut file = fs::File::create(&tmp)?;
+    file.write_all(content.as_bytes())?;
+    file.sync_all()?;
+    fs::rename(&tmp, path)?;
+    if let Some(stamp) = file_stamp(path) {
+        WRITTEN
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .insert(path.to_path_buf(), stamp);
+    }
+    Ok(())
+}
+
+pub fn file_stamp(path: &Path) -> Option<FileStamp> {
+    let meta = fs::metadata(path).ok()?;
+    Some((meta.modified().ok()?, meta.len()))
+}
+
+/// Whether `path` is as `write_atomic` left it.
+pub fn is_own_write(path: &Path, stamp: FileStamp) -> bool {
+    WRITTEN
+        .lock()
+        .unwrap_or_else(|err| err.into_inner())
+        .get(path)
+        == Some(&stamp)
+}
+
+/// File name a model with `id` is saved under in `MODELS_DIR`.
+pub fn model_file_name(id: &str) -> String {
+    format!("{}.toml", sanitize_id(id))
 }
 
 fn sanitize_id(id: &str) -> String {
@@ -145,6 +261,16 @@ fn sample_quad() -> ModelConfig {
         yaw_rate: 180,
         expo_percent: 20,
     }];
+    // Five minute pack timer that only runs with throttle up.
+    model.timers = vec![TimerConfig {
+        name: "Flight".to_string(),
+        direction: TimerDirection::Down,
+        start_secs: 300,
+        run: TimerRun::Throttle,
+        minute_alert: true,
+        countdown_secs: 10,
+        persistent: false,
+    }];
     model
 }
 
@@ -161,13 +287,55 @@ fn sample_plane() -> ModelConfig {
             output.weight = 80;
         }
     }
-    model.profiles = vec![RateProfile {
-        name: "cruise".to_string(),
-        roll_rate: 120,
-        pitch_rate: 100,
-        yaw_rate: 80,
-        expo_percent: 10,
-    }];
+    // Rudder follows its own stick plus 30% of aileron for coordinated turns
+    // while cruising.
+    model.mixer.mixes = vec![
+        MixLine::new(ControlRole::Direction, ControlRole::Direction),
+        MixLine {
+            weight: 30,
+            flight_modes: vec!["cruise".to_string()],
+            ..MixLine::new(ControlRole::Direction, ControlRole::Aileron)
+        },
+    ];
+    model.profiles = vec![
+        RateProfile {
+            name: "cruise".to_string(),
+            roll_rate: 120,
+            pitch_rate: 100,
+            yaw_rate: 80,
+            expo_percent: 10,
+        },
+        RateProfile {
+            name: "landing".to_string(),
+            roll_rate: 60,
+            pitch_rate: 60,
+            yaw_rate: 80,
+            expo_percent: 20,
+        },
+    ];
+    // AUX1 (once mapped in `input_mapping`) selects landing: softer rates and
+    // some elevator trim.
+    model.flight_modes = vec![
+        FlightModeConfig {
+            name: "cruise".to_string(),
+            switch: None,
+            fade_in_ms: 300,
+            fade_out_ms: 300,
+            profile: "cruise".to_string(),
+            trims: Vec::new(),
+        },
+        FlightModeConfig {
+            name: "landing".to_string(),
+            switch: Some(SwitchRef::Input(ControlRole::Aux1)),
+            fade_in_ms: 300,
+            fade_out_ms: 300,
+            profile: "landing".to_string(),
+            trims: vec![FlightModeTrim {
+                role: ControlRole::Elevator,
+                value: 50,
+            }],
+        },
+    ];
     model
 }
 
@@ -178,18 +346,13 @@ fn sample_rover() -> ModelConfig {
     model.output.protocol = OutputProtocol::UsbHid;
     model.mixer.outputs = vec![
         MixerOutput {
-            role: ControlRole::Thrust,
             weight: 60,
             offset: -150,
-            curve: CurveRef::Linear,
-            limits: super::OutputLimits::default(),
+            ..MixerOutput::new(ControlRole::Thrust)
         },
         MixerOutput {
-            role: ControlRole::Direction,
             weight: 140,
-            offset: 0,
-            curve: CurveRef::Linear,
-            limits: super::OutputLimits::default(),
+            ..MixerOutput::new(ControlRole::Direction)
         },
         MixerOutput::new(ControlRole::Aileron),
         MixerOutput::new(ControlRole::Elevator),
@@ -204,47 +367,52 @@ fn sample_rover() -> ModelConfig {
     model
 }
 
+/// An empty working directory for a test that reads or writes the config
+/// layout. The working directory is process-wide, so such tests run one at
+/// a time.
 #[cfg(test)]
-mod tests {
-    use std::{
-        sync::{LazyLock, Mutex},
-        time::{SystemTime, UNIX_EPOCH},
-    };
-
-    static TEST_MUTEX: LazyLock<Mutex<()>> = LazyLock::new(|| Mutex::new(()));
-
-    use super::*;
-
-    struct TestCwdGuard {
-        original: PathBuf,
-        test_dir: PathBuf,
-    }
+pub struct TestCwdGuard {
+    _serial: std::sync::MutexGuard<'static, ()>,
+    original: PathBuf,
+    test_dir: PathBuf,
+}
 
-    impl TestCwdGuard {
-        fn new() -> Self {
-            let original = std::env::current_dir().unwrap();
-            let unique = SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap()
-                .as_nanos();
-            let test_dir = std::env::temp_dir().join(format!("lintx-config-test-{unique}"));
-            fs::create_dir_all(&test_dir).unwrap();
-            std::env::set_current_dir(&test_dir).unwrap();
-            Self { original, test_dir }
+#[cfg(test)]
+impl TestCwdGuard {
+    pub fn enter() -> Self {
+        static TEST_MUTEX: Mutex<()> = Mutex::new(());
+        let serial = TEST_MUTEX.lock().unwrap_or_else(|err| err.into_inner());
+        let original = std::env::current_dir().unwrap();
+        let unique = SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let test_dir = std::env::temp_dir().join(format!("lintx-config-test-{unique}"));
+        fs::create_dir_all(&test_dir).unwrap();
+        std::env::set_current_dir(&test_dir).unwrap();
+        Self {
+            _serial: serial,
+            original,
+            test_dir,
         }
     }
+}
 
-    impl Drop for TestCwdGuard {
-        fn drop(&mut self) {
-            let _ = std::env::set_current_dir(&self.original);
-            let _ = fs::remove_dir_all(&self.test_dir);
-        }
+#[cfg(test)]
+impl Drop for TestCwdGuard {
+    fn drop(&mut self) {
+        let _ = std::env::set_current_dir(&self.original);
+        let _ = fs::remove_dir_all(&self.test_dir);
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
     #[test]
     fn test_ensure_default_layout_creates_models_and_radio() {
-        let _serial = TEST_MUTEX.lock().unwrap();
-        let _guard = TestCwdGuard::new();
+        let _guard = TestCwdGuard::enter();
         ensure_default_layout().unwrap();
         assert!(Path::new(RADIO_CONFIG_PATH).exists());
         assert!(Path::new(MODELS_DIR).exists());
@@ -252,13 +420,44 @@ mod tests {
         assert_eq!(load_active_model().unwrap().id, "quad_x");
     }
 
+    #[test]
+    fn test_bundled_models_match_samples() {
+        let bundled = [
+            include_str!("../../models/quad_x.toml"),
+            include_str!("../../models/fixed_wing.toml"),
+            include_str!("../../models/rover.toml"),
+        ];
+        for (text, sample) in bundled.into_iter().zip(sample_models()) {
+            let model: ModelConfig = toml::from_str(text).unwrap();
+            assert_eq!(model, sample, "models/{}.toml is out of date", sample.id);
+        }
+    }
+
     #[test]
     fn test_set_active_model_updates_radio_config() {
-        let _serial = TEST_MUTEX.lock().unwrap();
-        let _guard = TestCwdGuard::new();
+        let _guard = TestCwdGuard::enter();
         ensure_default_layout().unwrap();
         let model = set_active_model("rover").unwrap();
         assert_eq!(model.id, "rover");
         assert_eq!(load_radio_config().unwrap().active_model, "rover");
     }
+
+    #[test]
+    fn test_runtime_values_go_to_state_file() {
+        let _guard = TestCwdGuard::enter();
+        ensure_default_layout().unwrap();
+        let model_file = fs::read_to_string(model_path("quad_x")).unwrap();
+
+        assert_eq!(load_model_state("quad_x").unwrap(), ModelState::default());
+        save_timer_values("quad_x", &[42_000]).unwrap();
+        save_crsf_baudrate("quad_x", 921_600).unwrap();
+        let state = load_model_state("quad_x").unwrap();
+        assert_eq!(state.timer_values_ms, [42_000]);
+        assert_eq!(state.crsf_baudrate, 921_600);
+        assert_eq!(
+            fs::read_to_string(model_path("quad_x")).unwrap(),
+            model_file
+        );
+        assert!(!state_path("quad_x").with_extension("toml.tmp").exists());
+    }
 }
@@ -0,0 +1,425 @@
+//! Watches `radio.toml` and the model files for edits made outside the UI,
+//! e.g. in an editor or pushed over SSH. Valid files are republished on
+//! `system_config` / `active_model`; invalid ones are rejected and reported
+//! on `config_change` while the running config stays as it was. Files
+//! LinTx saved itself are not reported or republished.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use clap::Parser;
+use rpos::{
+    msg::{get_new_rx_of_message, get_new_tx_of_message},
+    thread_logln,
+};
+
+use crate::{
+    client_process_args,
+    config::{
+        store::{self, FileStamp},
+        ModelConfig, RadioConfig,
+    },
+    messages::{ActiveModelMsg, ConfigChangeMsg, RadioInputMsg, SystemConfigMsg},
+};
+
+#[derive(Parser)]
+#[command(
+    name = "config_watch",
+    about = "Reloads radio and model config on file change"
+)]
+struct Cli {
+    /// How often the files are checked, in milliseconds.
+    #[arg(long, default_value_t = 1000)]
+    interval_ms: u64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum WatchEvent {
+    Accepted(PathBuf),
+    Rejected(PathBuf, String),
+    Radio(RadioConfig),
+    ActiveModel(Box<ModelConfig>),
+}
+
+struct ConfigWatcher {
+    radio_path: PathBuf,
+    models_dir: PathBuf,
+    stamps: BTreeMap<PathBuf, FileStamp>,
+    /// Changes seen in the last scan, taken once they held until the next.
+    pending: BTreeMap<PathBuf, Option<FileStamp>>,
+    /// Last accepted radio config and the active model in effect.
+    radio: RadioConfig,
+    active: ModelConfig,
+}
+
+impl ConfigWatcher {
+    fn new(radio_path: PathBuf, models_dir: PathBuf) -> Self {
+        let mut watcher = Self {
+            radio_path,
+            models_dir,
+            stamps: BTreeMap::new(),
+            pending: BTreeMap::new(),
+            radio: RadioConfig::default(),
+            active: ModelConfig::default(),
+        };
+        watcher.stamps = watcher.current_stamps();
+        if let Ok(radio) = watcher.load_radio() {
+            watcher.active = watcher
+                .load_model(&watcher.model_path(&radio.active_model))
+                .unwrap_or_default();
+            watcher.radio = radio;
+        }
+        watcher
+    }
+
+    /// Takes a model switch made elsewhere (the Models page, a special
+    /// function) so the matching `radio.toml` write is not republished.
+    fn set_active(&mut self, model: ModelConfig) {
+        self.radio.active_model = model.id.clone();
+        self.active = model;
+    }
+
+    fn model_path(&self, id: &str) -> PathBuf {
+        self.models_dir.join(store::model_file_name(id))
+    }
+
+    fn current_stamps(&self) -> BTreeMap<PathBuf, FileStamp> {
+        let mut paths = vec![self.radio_path.clone()];
+        if let Ok(entries) = fs::read_dir(&self.models_dir) {
+            paths.extend(
+                entries
+                    .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                    .filter(|path| path.extension().and_then(|ext| ext.to_str()) == Some("toml")),
+            );
+        }
+        paths
+            .into_iter()
+            .filter_map(|path| Some((path.clone(), store::file_stamp(&path)?)))
+            .collect()
+    }
+
+    /// Takes in a file LinTx saved itself without reporting it; the thread
+    /// that saved it already has the change.
+    fn absorb_own_write(&mut self, path: &Path) {
+        if path == self.radio_path {
+            if let Ok(radio) = self.load_radio() {
+                self.radio = radio;
+            }
+        } else if path == self.model_path(&self.radio.active_model) {
+            if let Ok(model) = self.load_model(path) {
+                self.active = model;
+            }
+        }
+    }
+
+    fn load_radio(&self) -> Result<RadioConfig, String> {
+        let content = fs::read_to_string(&self.radio_path).map_err(|err| err.to_string())?;
+        let radio = toml::from_str::<RadioConfig>(&content).map_err(|err| err.to_string())?;
+        self.load_model(&self.model_path(&radio.active_model))
+            .map_err(|err| format!("active model `{}`: {}", radio.active_model, err))?;
+        Ok(radio)
+    }
+
+    fn load_model(&self, path: &Path) -> Result<ModelConfig, String> {
+        let content = fs::read_to_string(path).map_err(|err| err.to_string())?;
+        let model = toml::from_str::<ModelConfig>(&content).map_err(|err| err.to_string())?;
+        let file_name = path.file_name().and_then(|name| name.to_str());
+        if file_name != Some(store::model_file_name(&model.id).as_str()) {
+            return Err(format!("id `{}` does not match the file name", model.id));
+        }
+        Ok(model)
+    }
+
+    /// Checks files changed since the last scan, in path order. A change is
+    /// only read once its stamp held for a whole scan, so a file still being
+    /// written is not rejected half way.
+    fn scan(&mut self) -> Vec<WatchEvent> {
+        let stamps = self.current_stamps();
+        let paths: BTreeSet<PathBuf> = stamps.keys().chain(self.stamps.keys()).cloned().collect();
+        let mut pending = BTreeMap::new();
+        let mut changed = Vec::new();
+        for path in paths {
+            let stamp = stamps.get(&path).copied();
+            if self.stamps.get(&path).copied() == stamp {
+                continue;
+            }
+            if self.pending.get(&path) != Some(&stamp) {
+                pending.insert(path, stamp);
+                continue;
+            }
+            match stamp {
+                Some(stamp) => {
+                    self.stamps.insert(path.clone(), stamp);
+                    if store::is_own_write(&path, stamp) {
+                        self.absorb_own_write(&path);
+                        continue;
+                    }
+                }
+                None => {
+                    self.stamps.remove(&path);
+                }
+            }
+            changed.push(path);
+        }
+        self.pending = pending;
+
+        let mut events = Vec::new();
+        let mut reload_active = false;
+        for path in changed {
+            if path == self.radio_path {
+                match self.load_radio() {
+                    Ok(radio) => {
+                        reload_active |= radio.active_model != self.radio.active_model;
+                        self.radio = radio.clone();
+                        events.push(WatchEvent::Accepted(path));
+                        events.push(WatchEvent::Radio(radio));
+                    }
+                    Err(err) => events.push(WatchEvent::Rejected(path, err)),
+                }
+                continue;
+            }
+
+            let is_active = path == self.model_path(&self.radio.active_model);
+            if !path.exists() {
+                if is_active {
+                    events.push(WatchEvent::Rejected(
+                        path,
+                        "active model file removed".to_string(),
+                    ));
+                } else {
+                    events.push(WatchEvent::Accepted(path));
+                }
+                continue;
+            }
+            match self.load_model(&path) {
+                Ok(_) => {
+                    reload_active |= is_active;
+                    events.push(WatchEvent::Accepted(path));
+                }
+                Err(err) => events.push(WatchEvent::Rejected(path, err)),
+            }
+        }
+
+        if reload_active {
+            // Both files were validated above.
+            if let Ok(model) = self.load_model(&self.model_path(&self.radio.active_model)) {
+                if model != self.active {
+                    self.active = model.clone();
+                    events.push(WatchEvent::ActiveModel(Box::new(model)));
+                }
+            }
+        }
+        events
+    }
+}
+
+fn config_watch_main(argc: u32, argv: *const &str) {
+    let Some(args) = client_process_args::<Cli>(argc, argv) else {
+        return;
+    };
+
+    let mut active_model_rx = get_new_rx_of_message::<ActiveModelMsg>("active_model").unwrap();
+    let active_model_tx = get_new_tx_of_message::<ActiveModelMsg>("active_model").unwrap();
+    let config_tx = get_new_tx_of_message::<SystemConfigMsg>("system_config").unwrap();
+    let input_tx = get_new_tx_of_message::<RadioInputMsg>("radio_input").unwrap();
+    let change_tx = get_new_tx_of_message::<ConfigChangeMsg>("config_change").unwrap();
+
+    let mut watcher = ConfigWatcher::new(
+        PathBuf::from(store::RADIO_CONFIG_PATH),
+        PathBuf::from(store::MODELS_DIR),
+    );
+    thread_logln!("config_watch started");
+
+    loop {
+        while let Some(msg) = active_model_rx.try_read() {
+            watcher.set_active(msg.model);
+        }
+
+        let previous_input = watcher.radio.input.clone();
+        for event in watcher.scan() {
+            match event {
+                WatchEvent::Accepted(path) => {
+                    thread_logln!("config_watch: reloaded {}", path.display());
+                    change_tx.send(ConfigChangeMsg {
+                        path: path.display().to_string(),
+                        error: String::new(),
+                    });
+                }
+                WatchEvent::Rejected(path, error) => {
+                    thread_logln!("config_watch: rejected {}: {}", path.display(), error);
+                    change_tx.send(ConfigChangeMsg {
+                        path: path.display().to_string(),
+                        error,
+                    });
+                }
+                WatchEvent::Radio(radio) => {
+                    config_tx.send(SystemConfigMsg {
+                        backlight_percent: radio.ui.backlight_percent,
+                        sound_percent: radio.audio.sound_percent,
+                    });
+                    if radio.input != previous_input {
+                        input_tx.send(RadioInputMsg {
+                            input: radio.input.clone(),
+                        });
+                    }
+                }
+                WatchEvent::ActiveModel(model) => {
+                    active_model_tx.send(ActiveModelMsg { model: *model })
+                }
+            }
+        }
+
+        std::thread::sleep(Duration::from_millis(args.interval_ms));
+    }
+}
+
+#[rpos::ctor::ctor]
+fn register() {
+    rpos::module::Module::register("config_watch", config_watch_main);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::InputSource;
+
+    /// A radio.toml and models dir of its own under the temp dir.
+    fn layout(name: &str) -> (PathBuf, PathBuf) {
+        let root = std::env::temp_dir().join(format!("lintx_config_watch_{name}"));
+        let _ = fs::remove_dir_all(&root);
+        let models_dir = root.join("models");
+        fs::create_dir_all(&models_dir).unwrap();
+        let radio_path = root.join("radio.toml");
+        write_model(&models_dir, &ModelConfig::default());
+        fs::write(
+            &radio_path,
+            toml::to_string(&RadioConfig::default()).unwrap(),
+        )
+        .unwrap();
+        (radio_path, models_dir)
+    }
+
+    fn write_model(models_dir: &Path, model: &ModelConfig) {
+        fs::write(
+            models_dir.join(store::model_file_name(&model.id)),
+            toml::to_string(model).unwrap(),
+        )
+        .unwrap();
+    }
+
+    /// Forgets the stamps, as mtimes may not move within one test, and
+    /// scans until the changes settled.
+    fn rescan(watcher: &mut ConfigWatcher) -> Vec<WatchEvent> {
+        watcher.stamps.clear();
+        assert!(watcher.scan().is_empty());
+        watcher.scan()
+    }
+
+    #[test]
+    fn test_active_model_edit_is_republished() {
+        let (radio_path, models_dir) = layout("active");
+        let mut watcher = ConfigWatcher::new(radio_path.clone(), models_dir.clone());
+        assert!(watcher.scan().is_empty());
+
+        let model = ModelConfig {
+            name: "Edited".to_string(),
+            ..ModelConfig::default()
+        };
+        write_model(&models_dir, &model);
+        let events = rescan(&mut watcher);
+        assert_eq!(
+            events.last(),
+            Some(&WatchEvent::ActiveModel(Box::new(model)))
+        );
+
+        // Unchanged content is not published again.
+        assert!(!rescan(&mut watcher)
+            .iter()
+            .any(|event| matches!(event, WatchEvent::ActiveModel(_))));
+    }
+
+    #[test]
+    fn test_radio_input_edit_is_published() {
+        let (radio_path, models_dir) = layout("radio_input");
+        let mut watcher = ConfigWatcher::new(radio_path.clone(), models_dir);
+        assert!(watcher.scan().is_empty());
+
+        let mut radio = RadioConfig::default();
+        radio.input.source_priority = vec![InputSource::Crsf, InputSource::Adc];
+        fs::write(&radio_path, toml::to_string(&radio).unwrap()).unwrap();
+        let events = rescan(&mut watcher);
+        assert_eq!(events.last(), Some(&WatchEvent::Radio(radio.clone())));
+        assert_eq!(watcher.radio.input, radio.input);
+    }
+
+    #[test]
+    fn test_invalid_files_are_rejected() {
+        let (radio_path, models_dir) = layout("invalid");
+        let mut watcher = ConfigWatcher::new(radio_path.clone(), models_dir.clone());
+        let model_path = watcher.model_path(&ModelConfig::default().id);
+
+        fs::write(&model_path, "name = [").unwrap();
+        let events = rescan(&mut watcher);
+        assert!(events
+            .iter()
+            .any(|event| matches!(event, WatchEvent::Rejected(path, _) if *path == model_path)));
+        // The radio file now names a broken model, so it is refused too.
+        assert!(events
+            .iter()
+            .any(|event| matches!(event, WatchEvent::Rejected(path, _) if *path == radio_path)));
+        assert!(!events
+            .iter()
+            .any(|event| matches!(event, WatchEvent::ActiveModel(_))));
+
+        let renamed = ModelConfig {
+            id: "other".to_string(),
+            ..ModelConfig::default()
+        };
+        fs::write(&model_path, toml::to_string(&renamed).unwrap()).unwrap();
+        assert!(matches!(
+            rescan(&mut watcher).first(),
+            Some(WatchEvent::Rejected(_, error)) if error.contains("file name")
+        ));
+    }
+
+    #[test]
+    fn test_own_writes_and_unsettled_files_are_skipped() {
+        let (radio_path, models_dir) = layout("own");
+        let mut watcher = ConfigWatcher::new(radio_path, models_dir.clone());
+        let model_path = watcher.model_path(&ModelConfig::default().id);
+
+        let saved = ModelConfig {
+            name: "Saved".to_string(),
+            ..ModelConfig::default()
+        };
+        store::write_atomic(&model_path, &toml::to_string(&saved).unwrap()).unwrap();
+        assert!(watcher.scan().is_empty());
+        assert!(watcher.scan().is_empty());
+        assert_eq!(watcher.active, saved);
+
+        // Still being written: nothing is read until it holds for a scan.
+        let edited = ModelConfig {
+            name: "Edited".to_string(),
+            ..ModelConfig::default()
+        };
+        write_model(&models_dir, &edited);
+        assert!(watcher.scan().is_empty());
+        let edited = ModelConfig {
+            name: "Edited again".to_string(),
+            ..ModelConfig::default()
+        };
+        write_model(&models_dir, &edited);
+        assert!(watcher.scan().is_empty());
+        assert_eq!(
+            watcher.scan(),
+            vec![
+                WatchEvent::Accepted(model_path),
+                WatchEvent::ActiveModel(Box::new(edited))
+            ]
+        );
+    }
+}
@@ -0,0 +1,223 @@
+//! Serial side of a CRSF link: finds the baud rate the other end talks at
+//! and moves both ends to a faster one with a speed proposal.
+
+use std::{
+    io::{self, Read, Write},
+    time::{Duration, Instant},
+};
+
+use rpos::thread_logln;
+use serialport::SerialPort;
+
+use super::{Command, Frame, ADDRESS_MODULE, ADDRESS_RADIO, ADDRESS_SYNC};
+
+/// Rates ELRS modules and receivers run CRSF at, slowest first.
+pub const CRSF_BAUDRATES: [u32; 5] = [115200, 400000, 921600, 1870000, 3750000];
+
+const READ_TIMEOUT: Duration = Duration::from_millis(20);
+/// How long a rate is listened on before trying the next one.
+const PROBE_TIMEOUT: Duration = Duration::from_millis(200);
+const PROPOSAL_TIMEOUT: Duration = Duration::from_millis(500);
+/// UART id of the handset port in a speed proposal.
+const PORT_ID: u8 = 0;
+
+pub fn open_port(dev_name: &str, baudrate: u32) -> io::Result<Box<dyn SerialPort>> {
+    serialport::new(dev_name, baudrate)
+        .timeout(READ_TIMEOUT)
+        .open()
+        .map_err(io::Error::from)
+}
+
+/// Rates to probe: the one that worked last time, the target, then the
+/// standard list. Zero entries are skipped.
+pub fn probe_order(known: u32, target: u32) -> Vec<u32> {
+    let mut order = Vec::new();
+    for baudrate in [known, target].into_iter().chain(CRSF_BAUDRATES) {
+        if baudrate != 0 && !order.contains(&baudrate) {
+            order.push(baudrate);
+        }
+    }
+    order
+}
+
+/// Reads frames until `done` returns a value or `timeout` passes.
+fn read_until<P, T>(
+    port: &mut P,
+    timeout: Duration,
+    mut done: impl FnMut(&[u8]) -> Option<T>,
+) -> io::Result<Option<T>>
+where
+    P: Read + ?Sized,
+{
+    let deadline = Instant::now() + timeout;
+    let mut buf = Vec::with_capacity(256);
+    let mut scratch = [0u8; 128];
+    while Instant::now() < deadline {
+        match port.read(&mut scratch) {
+            Ok(n) if n > 0 => buf.extend_from_slice(&scratch[..n]),
+            Ok(_) => std::thread::sleep(Duration::from_millis(5)),
+            Err(ref e) if e.kind() == io::ErrorKind::TimedOut => {}
+            Err(e) => return Err(e),
+        }
+        for frame in super::extract_frames(&mut buf) {
+            if let Some(value) = done(&frame) {
+                return Ok(Some(value));
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Whether a CRC-valid frame arrives. With `ping` a device ping is sent
+/// first, for ends that stay quiet until asked.
+fn answers<P: Read + Write + ?Sized>(port: &mut P, ping: bool) -> io::Result<bool> {
+    if ping {
+        let frame = Frame::DevicePing {
+            dest: super::ADDRESS_BROADCAST,
+            origin: ADDRESS_RADIO,
+        };
+        port.write_all(&frame.encode(ADDRESS_SYNC))?;
+    }
+    Ok(read_until(port, PROBE_TIMEOUT, |_| Some(()))?.is_some())
+}
+
+/// Opens `dev_name` at the first of `candidates` the other end answers on.
+pub fn probe(
+    dev_name: &str,
+    candidates: &[u32],
+    ping: bool,
+) -> io::Result<(Box<dyn SerialPort>, u32)> {
+    for &baudrate in candidates {
+        let mut port = open_port(dev_name, baudrate)?;
+        if answers(&mut *port, ping)? {
+            return Ok((port, baudrate));
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("no CRSF device answers on {}", dev_name),
+    ))
+}
+
+/// Asks the module to switch to `baudrate`. `Ok(false)` when it refuses or
+/// does not support the proposal.
+pub fn propose_baudrate<P: Read + Write + ?Sized>(port: &mut P, baudrate: u32) -> io::Result<bool> {
+    let proposal = Command::speed_proposal(ADDRESS_MODULE, ADDRESS_RADIO, PORT_ID, baudrate);
+    port.write_all(&Frame::Command(proposal).encode(ADDRESS_SYNC))?;
+    port.flush()?;
+    let accepted = read_until(port, PROPOSAL_TIMEOUT, |frame| match Frame::decode(frame) {
+        Some((_, Frame::Command(command))) => command
+            .speed_response()
+            .and_then(|(port_id, accepted)| (port_id == PORT_ID).then_some(accepted)),
+        _ => None,
+    })?;
+    Ok(accepted == Some(true))
+}
+
+/// Connects to the module on `dev_name` and moves it to `target` baud.
+/// `known` is the rate that worked last time and is probed first. Returns
+/// the open port and the rate it runs at, which is the probed one when the
+/// module refuses `target`. A zero `target` keeps the probed rate.
+pub fn connect(dev_name: &str, target: u32, known: u32) -> io::Result<(Box<dyn SerialPort>, u32)> {
+    let (mut port, baudrate) = probe(dev_name, &probe_order(known, target), true)?;
+    if target == 0 || target == baudrate {
+        return Ok((port, baudrate));
+    }
+
+    if !propose_baudrate(&mut *port, target)? {
+        thread_logln!("crsf module keeps {} baud, {} refused", baudrate, target);
+        return Ok((port, baudrate));
+    }
+    drop(port);
+    // The module switches after answering; give it a moment.
+    std::thread::sleep(Duration::from_millis(50));
+    let mut port = open_port(dev_name, target)?;
+    if answers(&mut *port, true)? {
+        return Ok((port, target));
+    }
+    thread_logln!("crsf module silent after switching to {} baud", target);
+    drop(port);
+    probe(dev_name, &probe_order(baudrate, 0), true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crsf::{COMMAND_GENERAL, SUBCOMMAND_SPEED_RESPONSE};
+
+    /// Serial port stand-in that plays back `rx` and records writes.
+    struct FakePort {
+        rx: Vec<u8>,
+        written: Vec<u8>,
+    }
+
+    impl Read for FakePort {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.rx.is_empty() {
+                return Err(io::ErrorKind::TimedOut.into());
+            }
+            let n = buf.len().min(self.rx.len());
+            buf[..n].copy_from_slice(&self.rx[..n]);
+            self.rx.drain(..n);
+            Ok(n)
+        }
+    }
+
+    impl Write for FakePort {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.written.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn speed_response(status: u8) -> Vec<u8> {
+        Frame::Command(Command {
+            dest: ADDRESS_RADIO,
+            origin: ADDRESS_MODULE,
+            command: COMMAND_GENERAL,
+            payload: vec![SUBCOMMAND_SPEED_RESPONSE, PORT_ID, status],
+        })
+        .encode(ADDRESS_RADIO)
+    }
+
+    #[test]
+    fn test_probe_order_tries_known_rate_first() {
+        assert_eq!(
+            probe_order(921600, 400000),
+            vec![921600, 400000, 115200, 1870000, 3750000]
+        );
+        assert_eq!(probe_order(0, 0), CRSF_BAUDRATES.to_vec());
+    }
+
+    #[test]
+    fn test_propose_baudrate_waits_for_response() {
+        // Telemetry ahead of the response is skipped.
+        let mut rx = Frame::Vario(12).encode(ADDRESS_RADIO);
+        rx.extend_from_slice(&speed_response(1));
+        let mut port = FakePort {
+            rx,
+            written: Vec::new(),
+        };
+        assert!(propose_baudrate(&mut port, 921600).unwrap());
+        assert_eq!(
+            port.written,
+            Frame::Command(Command::speed_proposal(
+                ADDRESS_MODULE,
+                ADDRESS_RADIO,
+                PORT_ID,
+                921600
+            ))
+            .encode(ADDRESS_SYNC)
+        );
+
+        let mut port = FakePort {
+            rx: speed_response(0),
+            written: Vec::new(),
+        };
+        assert!(!propose_baudrate(&mut port, 3750000).unwrap());
+    }
+}
@@ -0,0 +1,914 @@
+//! CRSF (Crossfire) frame codec shared by every CRSF user: RC output and
+//! input, telemetry and the ELRS configuration agent.
+//!
+//! A frame on the wire is `[address, len, type, payload.., crc8]`, where `len`
+//! counts type, payload and CRC. Extended frames (0x28 and up) start their
+//! payload with destination and origin addresses.
+//!
+//! `link` opens serial ports at a rate the other end answers on.
+
+pub mod link;
+
+use crc::{Crc, CRC_8_DVB_S2};
+
+const CRSF_CRC: Crc<u8> = Crc::<u8>::new(&CRC_8_DVB_S2);
+
+pub const ADDRESS_BROADCAST: u8 = 0x00;
+/// Sync byte, also the flight controller address.
+pub const ADDRESS_SYNC: u8 = 0xC8;
+pub const ADDRESS_RADIO: u8 = 0xEA;
+pub const ADDRESS_RECEIVER: u8 = 0xEC;
+pub const ADDRESS_MODULE: u8 = 0xEE;
+pub const ADDRESS_HANDSET: u8 = 0xEF;
+
+pub const FRAME_GPS: u8 = 0x02;
+pub const FRAME_VARIO: u8 = 0x07;
+pub const FRAME_BATTERY: u8 = 0x08;
+pub const FRAME_BARO_ALTITUDE: u8 = 0x09;
+pub const FRAME_LINK_STATISTICS: u8 = 0x14;
+pub const FRAME_RC_CHANNELS: u8 = 0x16;
+pub const FRAME_ATTITUDE: u8 = 0x1E;
+pub const FRAME_FLIGHT_MODE: u8 = 0x21;
+pub const FRAME_PING_DEVICES: u8 = 0x28;
+pub const FRAME_DEVICE_INFO: u8 = 0x29;
+pub const FRAME_PARAM_ENTRY: u8 = 0x2B;
+pub const FRAME_PARAM_READ: u8 = 0x2C;
+pub const FRAME_PARAM_WRITE: u8 = 0x2D;
+pub const FRAME_ELRS_STATUS: u8 = 0x2E;
+pub const FRAME_COMMAND: u8 = 0x32;
+pub const FRAME_RADIO_ID: u8 = 0x3A;
+
+pub const COMMAND_GENERAL: u8 = 0x0A;
+pub const SUBCOMMAND_SPEED_PROPOSAL: u8 = 0x70;
+pub const SUBCOMMAND_SPEED_RESPONSE: u8 = 0x71;
+pub const COMMAND_CRSF: u8 = 0x10;
+pub const SUBCOMMAND_BIND: u8 = 0x01;
+/// Radio ID subtype carrying the module's RC timing.
+pub const RADIO_ID_TIMING_CORRECTION: u8 = 0x10;
+
+/// Largest `len` byte; the whole frame is two bytes more.
+pub const MAX_FRAME_LEN: usize = 64;
+const MAX_PACKET_SIZE: usize = MAX_FRAME_LEN + 2;
+
+pub const RC_CHANNELS: usize = 16;
+/// RC channel values for 988us, 1500us and 2012us.
+pub const CHANNEL_VALUE_MIN: u16 = 172;
+pub const CHANNEL_VALUE_MID: u16 = 992;
+pub const CHANNEL_VALUE_MAX: u16 = 1811;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LinkStatistics {
+    /// Uplink RSSI per antenna, dBm without the sign.
+    pub uplink_rssi_1: u8,
+    pub uplink_rssi_2: u8,
+    /// Percent.
+    pub uplink_link_quality: u8,
+    pub uplink_snr: i8,
+    pub active_antenna: u8,
+    pub rf_mode: u8,
+    /// Index into the power table, see `telemetry`.
+    pub uplink_tx_power: u8,
+    pub downlink_rssi: u8,
+    pub downlink_link_quality: u8,
+    pub downlink_snr: i8,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Battery {
+    /// 0.1 V.
+    pub voltage: u16,
+    /// 0.1 A.
+    pub current: u16,
+    /// mAh drawn, 24 bits on the wire.
+    pub capacity: u32,
+    pub remaining: u8,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Gps {
+    /// Degrees * 1e7.
+    pub latitude: i32,
+    pub longitude: i32,
+    /// 0.1 km/h.
+    pub ground_speed: u16,
+    /// 0.01 degrees.
+    pub heading: u16,
+    /// Meters + 1000.
+    pub altitude: u16,
+    pub satellites: u8,
+}
+
+/// Radians * 10000.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Attitude {
+    pub pitch: i16,
+    pub roll: i16,
+    pub yaw: i16,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DeviceInfo {
+    pub dest: u8,
+    pub origin: u8,
+    pub name: String,
+    pub serial_number: u32,
+    pub hardware_id: u32,
+    pub firmware_id: u32,
+    pub parameter_count: u8,
+    pub parameter_version: u8,
+}
+
+impl DeviceInfo {
+    /// ELRS keeps `major.minor.patch` in the low three bytes.
+    pub fn firmware_version(&self) -> String {
+        let [_, major, minor, patch] = self.firmware_id.to_be_bytes();
+        format!("{major}.{minor}.{patch}")
+    }
+}
+
+/// One chunk of a parameter's description; chunks are joined by the reader.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParamEntry {
+    pub dest: u8,
+    pub origin: u8,
+    pub field_id: u8,
+    pub chunks_remaining: u8,
+    pub data: Vec<u8>,
+}
+
+/// ELRS transmitter status, answered to parameter pings.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ElrsStatus {
+    pub dest: u8,
+    pub origin: u8,
+    pub bad_packets: u8,
+    pub good_packets: u16,
+    pub flags: u8,
+    pub message: String,
+}
+
+/// Command frame; `payload` follows the command id and the CRC8_BA
+/// over the whole payload is added and checked by the codec.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Command {
+    pub dest: u8,
+    pub origin: u8,
+    pub command: u8,
+    pub payload: Vec<u8>,
+}
+
+impl Command {
+    /// Asks `dest` to switch the UART `port_id` to `baudrate`.
+    pub fn speed_proposal(dest: u8, origin: u8, port_id: u8, baudrate: u32) -> Self {
+        let mut payload = vec![SUBCOMMAND_SPEED_PROPOSAL, port_id];
+        payload.extend_from_slice(&baudrate.to_be_bytes());
+        Self {
+            dest,
+            origin,
+            command: COMMAND_GENERAL,
+            payload,
+        }
+    }
+
+    /// Port id and whether the proposal was accepted, for a speed response.
+    pub fn speed_response(&self) -> Option<(u8, bool)> {
+        match (self.command, self.payload.as_slice()) {
+            (COMMAND_GENERAL, [SUBCOMMAND_SPEED_RESPONSE, port_id, status, ..]) => {
+                Some((*port_id, *status != 0))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Frame {
+    Gps(Gps),
+    /// Vertical speed in cm/s.
+    Vario(i16),
+    Battery(Battery),
+    /// Packed altitude (dm + 10000, or meters with the MSB set) and
+    /// optionally vertical speed in cm/s.
+    BaroAltitude {
+        altitude: u16,
+        vario: Option<i16>,
+    },
+    LinkStatistics(LinkStatistics),
+    /// 11-bit channel values.
+    RcChannels([u16; RC_CHANNELS]),
+    Attitude(Attitude),
+    FlightMode(String),
+    DevicePing {
+        dest: u8,
+        origin: u8,
+    },
+    DeviceInfo(DeviceInfo),
+    ParamEntry(ParamEntry),
+    ParamRead {
+        dest: u8,
+        origin: u8,
+        field_id: u8,
+        chunk: u8,
+    },
+    /// `value` is a byte for numeric fields or a NUL-terminated string.
+    ParamWrite {
+        dest: u8,
+        origin: u8,
+        field_id: u8,
+        value: Vec<u8>,
+    },
+    ElrsStatus(ElrsStatus),
+    Command(Command),
+    /// RC period the module wants and how far the last RC frame was off,
+    /// both in 0.1 us. A positive offset means the frame came early.
+    TimingCorrection {
+        dest: u8,
+        origin: u8,
+        interval: u32,
+        offset: i32,
+    },
+    /// Any frame type this codec does not know.
+    Other {
+        frame_type: u8,
+        payload: Vec<u8>,
+    },
+}
+
+fn be_u16(bytes: &[u8], at: usize) -> u16 {
+    u16::from_be_bytes([bytes[at], bytes[at + 1]])
+}
+
+fn be_u32(bytes: &[u8], at: usize) -> u32 {
+    u32::from_be_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]])
+}
+
+fn c_string(bytes: &[u8]) -> (String, usize) {
+    let end = bytes
+        .iter()
+        .position(|byte| *byte == 0)
+        .unwrap_or(bytes.len());
+    (
+        String::from_utf8_lossy(&bytes[..end]).to_string(),
+        (end + 1).min(bytes.len()),
+    )
+}
+
+fn push_c_string(out: &mut Vec<u8>, value: &str) {
+    out.extend_from_slice(value.as_bytes());
+    out.push(0);
+}
+
+fn pack_channels(channels: &[u16; RC_CHANNELS]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(22);
+    let mut bits: u32 = 0;
+    let mut bit_count = 0;
+    for value in channels {
+        bits |= (*value as u32 & 0x7FF) << bit_count;
+        bit_count += 11;
+        while bit_count >= 8 {
+            out.push(bits as u8);
+            bits >>= 8;
+            bit_count -= 8;
+        }
+    }
+    out
+}
+
+fn unpack_channels(bytes: &[u8]) -> [u16; RC_CHANNELS] {
+    let mut channels = [0; RC_CHANNELS];
+    let mut bits: u32 = 0;
+    let mut bit_count = 0;
+    let mut bytes = bytes.iter();
+    for channel in channels.iter_mut() {
+        while bit_count < 11 {
+            bits |= (*bytes.next().unwrap_or(&0) as u32) << bit_count;
+            bit_count += 8;
+        }
+        *channel = (bits & 0x7FF) as u16;
+        bits >>= 11;
+        bit_count -= 11;
+    }
+    channels
+}
+
+impl Frame {
+    pub fn frame_type(&self) -> u8 {
+        match self {
+            Self::Gps(_) => FRAME_GPS,
+            Self::Vario(_) => FRAME_VARIO,
+            Self::Battery(_) => FRAME_BATTERY,
+            Self::BaroAltitude { .. } => FRAME_BARO_ALTITUDE,
+            Self::LinkStatistics(_) => FRAME_LINK_STATISTICS,
+            Self::RcChannels(_) => FRAME_RC_CHANNELS,
+            Self::Attitude(_) => FRAME_ATTITUDE,
+            Self::FlightMode(_) => FRAME_FLIGHT_MODE,
+            Self::DevicePing { .. } => FRAME_PING_DEVICES,
+            Self::DeviceInfo(_) => FRAME_DEVICE_INFO,
+            Self::ParamEntry(_) => FRAME_PARAM_ENTRY,
+            Self::ParamRead { .. } => FRAME_PARAM_READ,
+            Self::ParamWrite { .. } => FRAME_PARAM_WRITE,
+            Self::ElrsStatus(_) => FRAME_ELRS_STATUS,
+            Self::Command(_) => FRAME_COMMAND,
+            Self::TimingCorrection { .. } => FRAME_RADIO_ID,
+            Self::Other { frame_type, .. } => *frame_type,
+        }
+    }
+
+    fn payload(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        match self {
+            Self::Gps(gps) => {
+                out.extend_from_slice(&gps.latitude.to_be_bytes());
+                out.extend_from_slice(&gps.longitude.to_be_bytes());
+                out.extend_from_slice(&gps.ground_speed.to_be_bytes());
+                out.extend_from_slice(&gps.heading.to_be_bytes());
+                out.extend_from_slice(&gps.altitude.to_be_bytes());
+                out.push(gps.satellites);
+            }
+            Self::Vario(vario) => out.extend_from_slice(&vario.to_be_bytes()),
+            Self::Battery(battery) => {
+                out.extend_from_slice(&battery.voltage.to_be_bytes());
+                out.extend_from_slice(&battery.current.to_be_bytes());
+                out.extend_from_slice(&battery.capacity.to_be_bytes()[1..]);
+                out.push(battery.remaining);
+            }
+            Self::BaroAltitude { altitude, vario } => {
+                out.extend_from_slice(&altitude.to_be_bytes());
+                if let Some(vario) = vario {
+                    out.extend_from_slice(&vario.to_be_bytes());
+                }
+            }
+            Self::LinkStatistics(link) => out.extend_from_slice(&[
+                link.uplink_rssi_1,
+                link.uplink_rssi_2,
+                link.uplink_link_quality,
+                link.uplink_snr as u8,
+                link.active_antenna,
+                link.rf_mode,
+                link.uplink_tx_power,
+                link.downlink_rssi,
+                link.downlink_link_quality,
+                link.downlink_snr as u8,
+            ]),
+            Self::RcChannels(channels) => out = pack_channels(channels),
+            Self::Attitude(attitude) => {
+                out.extend_from_slice(&attitude.pitch.to_be_bytes());
+                out.extend_from_slice(&attitude.roll.to_be_bytes());
+                out.extend_from_slice(&attitude.yaw.to_be_bytes());
+            }
+            Self::FlightMode(mode) => push_c_string(&mut out, mode),
+            Self::DevicePing { dest, origin } => out.extend_from_slice(&[*dest, *origin]),
+            Self::DeviceInfo(info) => {
+                out.extend_from_slice(&[info.dest, info.origin]);
+                push_c_string(&mut out, &info.name);
+                out.extend_from_slice(&info.serial_number.to_be_bytes());
+                out.extend_from_slice(&info.hardware_id.to_be_bytes());
+                out.extend_from_slice(&info.firmware_id.to_be_bytes());
+                out.extend_from_slice(&[info.parameter_count, info.parameter_version]);
+            }
+            Self::ParamEntry(entry) => {
+                out.extend_from_slice(&[
+                    entry.dest,
+                    entry.origin,
+                    entry.field_id,
+                    entry.chunks_remaining,
+                ]);
+                out.extend_from_slice(&entry.data);
+            }
+            Self::ParamRead {
+                dest,
+                origin,
+                field_id,
+                chunk,
+            } => out.extend_from_slice(&[*dest, *origin, *field_id, *chunk]),
+            Self::ParamWrite {
+                dest,
+                origin,
+                field_id,
+                value,
+            } => {
+                out.extend_from_slice(&[*dest, *origin, *field_id]);
+                out.extend_from_slice(value);
+            }
+            Self::ElrsStatus(status) => {
+                out.extend_from_slice(&[status.dest, status.origin, status.bad_packets]);
+                out.extend_from_slice(&status.good_packets.to_be_bytes());
+                out.push(status.flags);
+                push_c_string(&mut out, &status.message);
+            }
+            Self::Command(command) => {
+                out.extend_from_slice(&[command.dest, command.origin, command.command]);
+                out.extend_from_slice(&command.payload);
+                out.push(crc8_ba(&out));
+            }
+            Self::TimingCorrection {
+                dest,
+                origin,
+                interval,
+                offset,
+            } => {
+                out.extend_from_slice(&[*dest, *origin, RADIO_ID_TIMING_CORRECTION]);
+                out.extend_from_slice(&interval.to_be_bytes());
+                out.extend_from_slice(&offset.to_be_bytes());
+            }
+            Self::Other { payload, .. } => out.extend_from_slice(payload),
+        }
+        out
+    }
+
+    /// Full frame with length and CRC, sent to `address`.
+    pub fn encode(&self, address: u8) -> Vec<u8> {
+        encode_raw(address, self.frame_type(), &self.payload())
+    }
+
+    /// Decodes one complete frame and returns its address. Frames with a bad
+    /// length or CRC, or a payload too short for their type, give `None`.
+    pub fn decode(bytes: &[u8]) -> Option<(u8, Self)> {
+        if !check_crc(bytes) || bytes.len() != bytes[1] as usize + 2 {
+            return None;
+        }
+        let frame_type = bytes[2];
+        let payload = &bytes[3..bytes.len() - 1];
+        let extended = frame_type >= FRAME_PING_DEVICES;
+        if extended && payload.len() < 2 {
+            return None;
+        }
+
+        let frame = match frame_type {
+            FRAME_GPS if payload.len() >= 15 => Self::Gps(Gps {
+                latitude: be_u32(payload, 0) as i32,
+                longitude: be_u32(payload, 4) as i32,
+                ground_speed: be_u16(payload, 8),
+                heading: be_u16(payload, 10),
+                altitude: be_u16(payload, 12),
+                satellites: payload[14],
+            }),
+            FRAME_VARIO if payload.len() >= 2 => Self::Vario(be_u16(payload, 0) as i16),
+            FRAME_BATTERY if payload.len() >= 8 => Self::Battery(Battery {
+                voltage: be_u16(payload, 0),
+                current: be_u16(payload, 2),
+                capacity: u32::from_be_bytes([0, payload[4], payload[5], payload[6]]),
+                remaining: payload[7],
+            }),
+            FRAME_BARO_ALTITUDE if payload.len() >= 2 => Self::BaroAltitude {
+                altitude: be_u16(payload, 0),
+                vario: (payload.len() >= 4).then(|| be_u16(payload, 2) as i16),
+            },
+            FRAME_LINK_STATISTICS if payload.len() >= 10 => Self::LinkStatistics(LinkStatistics {
+                uplink_rssi_1: payload[0],
+                uplink_rssi_2: payload[1],
+                uplink_link_quality: payload[2],
+                uplink_snr: payload[3] as i8,
+                active_antenna: payload[4],
+                rf_mode: payload[5],
+                uplink_tx_power: payload[6],
+                downlink_rssi: payload[7],
+                downlink_link_quality: payload[8],
+                downlink_snr: payload[9] as i8,
+            }),
+            FRAME_RC_CHANNELS if payload.len() >= 22 => Self::RcChannels(unpack_channels(payload)),
+            FRAME_ATTITUDE if payload.len() >= 6 => Self::Attitude(Attitude {
+                pitch: be_u16(payload, 0) as i16,
+                roll: be_u16(payload, 2) as i16,
+                yaw: be_u16(payload, 4) as i16,
+            }),
+            FRAME_FLIGHT_MODE => Self::FlightMode(c_string(payload).0),
+            FRAME_PING_DEVICES => Self::DevicePing {
+                dest: payload[0],
+                origin: payload[1],
+            },
+            FRAME_DEVICE_INFO => {
+                let (name, used) = c_string(&payload[2..]);
+                let rest = &payload[2 + used..];
+                if rest.len() < 14 {
+                    return None;
+                }
+                Self::DeviceInfo(DeviceInfo {
+                    dest: payload[0],
+                    origin: payload[1],
+                    name,
+                    serial_number: be_u32(rest, 0),
+                    hardware_id: be_u32(rest, 4),
+                    firmware_id: be_u32(rest, 8),
+                    parameter_count: rest[12],
+                    parameter_version: rest[13],
+                })
+            }
+            FRAME_PARAM_ENTRY if payload.len() >= 4 => Self::ParamEntry(ParamEntry {
+                dest: payload[0],
+                origin: payload[1],
+                field_id: payload[2],
+                chunks_remaining: payload[3],
+                data: payload[4..].to_vec(),
+            }),
+            FRAME_PARAM_READ if payload.len() >= 4 => Self::ParamRead {
+                dest: payload[0],
+                origin: payload[1],
+                field_id: payload[2],
+                chunk: payload[3],
+            },
+            FRAME_PARAM_WRITE if payload.len() >= 3 => Self::ParamWrite {
+                dest: payload[0],
+                origin: payload[1],
+                field_id: payload[2],
+                value: payload[3..].to_vec(),
+            },
+            FRAME_ELRS_STATUS if payload.len() >= 6 => Self::ElrsStatus(ElrsStatus {
+                dest: payload[0],
+                origin: payload[1],
+                bad_packets: payload[2],
+                good_packets: be_u16(payload, 3),
+                flags: payload[5],
+                message: c_string(&payload[6..]).0,
+            }),
+            FRAME_COMMAND if payload.len() >= 4 => {
+                let (body, crc) = payload.split_at(payload.len() - 1);
+                if crc8_ba(body) != crc[0] {
+                    return None;
+                }
+                Self::Command(Command {
+                    dest: body[0],
+                    origin: body[1],
+                    command: body[2],
+                    payload: body[3..].to_vec(),
+                })
+            }
+            FRAME_RADIO_ID if payload.len() >= 11 && payload[2] == RADIO_ID_TIMING_CORRECTION => {
+                Self::TimingCorrection {
+                    dest: payload[0],
+                    origin: payload[1],
+                    interval: be_u32(payload, 3),
+                    offset: be_u32(payload, 7) as i32,
+                }
+            }
+            FRAME_GPS
+            | FRAME_VARIO
+            | FRAME_BATTERY
+            | FRAME_BARO_ALTITUDE
+            | FRAME_LINK_STATISTICS
+            | FRAME_RC_CHANNELS
+            | FRAME_ATTITUDE
+            | FRAME_PARAM_ENTRY
+            | FRAME_PARAM_READ
+            | FRAME_PARAM_WRITE
+            | FRAME_ELRS_STATUS
+            | FRAME_COMMAND => return None,
+            _ => Self::Other {
+                frame_type,
+                payload: payload.to_vec(),
+            },
+        };
+        Some((bytes[0], frame))
+    }
+}
+
+/// Frames an arbitrary type and payload, e.g. for scripts.
+pub fn encode_raw(address: u8, frame_type: u8, payload: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(payload.len() + 4);
+    out.push(address);
+    out.push((payload.len() + 2) as u8);
+    out.push(frame_type);
+    out.extend_from_slice(payload);
+    out.push(CRSF_CRC.checksum(&out[2..]));
+    out
+}
+
+/// Checks the CRC8 (DVB-S2) over type and payload.
+pub fn check_crc(frame: &[u8]) -> bool {
+    if frame.len() < 5 {
+        return false;
+    }
+    let len = frame[1] as usize;
+    if len < 2 || frame.len() < len + 2 {
+        return false;
+    }
+    CRSF_CRC.checksum(&frame[2..len + 1]) == frame[len + 1]
+}
+
+/// Inner CRC of command frames (polynomial 0xBA).
+pub fn crc8_ba(data: &[u8]) -> u8 {
+    let mut crc = 0u8;
+    for byte in data {
+        crc = CRC8_BA_TABLE[(crc ^ byte) as usize];
+    }
+    crc
+}
+
+/// Splits CRC-valid frames off the front of `buf`, dropping garbage.
+pub fn extract_frames(buf: &mut Vec<u8>) -> Vec<Vec<u8>> {
+    let mut frames = Vec::new();
+    let mut cursor = 0usize;
+
+    while buf.len().saturating_sub(cursor) >= 3 {
+        if !matches!(buf[cursor], ADDRESS_RADIO | ADDRESS_SYNC | ADDRESS_MODULE) {
+            cursor += 1;
+            continue;
+        }
+
+        let packet_len = buf[cursor + 1] as usize + 2;
+        if !(5..=MAX_PACKET_SIZE).contains(&packet_len) {
+            cursor += 1;
+            continue;
+        }
+
+        if cursor + packet_len > buf.len() {
+            break;
+        }
+
+        let frame = &buf[cursor..cursor + packet_len];
+        if check_crc(frame) {
+            frames.push(frame.to_vec());
+            cursor += packet_len;
+        } else {
+            cursor += 1;
+        }
+    }
+
+    if cursor > 0 {
+        buf.drain(..cursor);
+    }
+    frames
+}
+
+const CRC8_BA_TABLE: [u8; 256] = [
+    0x00, 0xBA, 0xCE, 0x74, 0x26, 0x9C, 0xE8, 0x52, 0x4C, 0xF6, 0x82, 0x38, 0x6A, 0xD0, 0xA4, 0x1E,
+    0x98, 0x22, 0x56, 0xEC, 0xBE, 0x04, 0x70, 0xCA, 0xD4, 0x6E, 0x1A, 0xA0, 0xF2, 0x48, 0x3C, 0x86,
+    0x8A, 0x30, 0x44, 0xFE, 0xAC, 0x16, 0x62, 0xD8, 0xC6, 0x7C, 0x08, 0xB2, 0xE0, 0x5A, 0x2E, 0x94,
+    0x12, 0xA8, 0xDC, 0x66, 0x34, 0x8E, 0xFA, 0x40, 0x5E, 0xE4, 0x90, 0x2A, 0x78, 0xC2, 0xB6, 0x0C,
+    0xAE, 0x14, 0x60, 0xDA, 0x88, 0x32, 0x46, 0xFC, 0xE2, 0x58, 0x2C, 0x96, 0xC4, 0x7E, 0x0A, 0xB0,
+    0x36, 0x8C, 0xF8, 0x42, 0x10, 0xAA, 0xDE, 0x64, 0x7A, 0xC0, 0xB4, 0x0E, 0x5C, 0xE6, 0x92, 0x28,
+    0x24, 0x9E, 0xEA, 0x50, 0x02, 0xB8, 0xCC, 0x76, 0x68, 0xD2, 0xA6, 0x1C, 0x4E, 0xF4, 0x80, 0x3A,
+    0xBC, 0x06, 0x72, 0xC8, 0x9A, 0x20, 0x54, 0xEE, 0xF0, 0x4A, 0x3E, 0x84, 0xD6, 0x6C, 0x18, 0xA2,
+    0xE6, 0x5C, 0x28, 0x92, 0xC0, 0x7A, 0x0E, 0xB4, 0xAA, 0x10, 0x64, 0xDE, 0x8C, 0x36, 0x42, 0xF8,
+    0x7E, 0xC4, 0xB0, 0x0A, 0x58, 0xE2, 0x96, 0x2C, 0x32, 0x88, 0xFC, 0x46, 0x14, 0xAE, 0xDA, 0x60,
+    0x6C, 0xD6, 0xA2, 0x18, 0x4A, 0xF0, 0x84, 0x3E, 0x20, 0x9A, 0xEE, 0x54, 0x06, 0xBC, 0xC8, 0x72,
+    0xF4, 0x4E, 0x3A, 0x80, 0xD2, 0x68, 0x1C, 0xA6, 0xB8, 0x02, 0x76, 0xCC, 0x9E, 0x24, 0x50, 0xEA,
+    0x48, 0xF2, 0x86, 0x3C, 0x6E, 0xD4, 0xA0, 0x1A, 0x04, 0xBE, 0xCA, 0x70, 0x22, 0x98, 0xEC, 0x56,
+    0xD0, 0x6A, 0x1E, 0xA4, 0xF6, 0x4C, 0x38, 0x82, 0x9C, 0x26, 0x52, 0xE8, 0xBA, 0x00, 0x74, 0xCE,
+    0xC2, 0x78, 0x0C, 0xB6, 0xE4, 0x5E, 0x2A, 0x90, 0x8E, 0x34, 0x40, 0xFA, 0xA8, 0x12, 0x66, 0xDC,
+    0x5A, 0xE0, 0x94, 0x2E, 0x7C, 0xC6, 0xB2, 0x08, 0x16, 0xAC, 0xD8, 0x62, 0x30, 0x8A, 0xFE, 0x44,
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bytes(hex: &str) -> Vec<u8> {
+        hex.split_whitespace()
+            .map(|byte| u8::from_str_radix(byte, 16).unwrap())
+            .collect()
+    }
+
+    /// Decodes a captured frame, checks the typed result and encodes it back.
+    fn assert_roundtrip(capture: &str, address: u8, frame: Frame) {
+        let raw = bytes(capture);
+        assert_eq!(Frame::decode(&raw), Some((address, frame.clone())));
+        assert_eq!(frame.encode(address), raw);
+    }
+
+    #[test]
+    fn test_rc_channels_roundtrip() {
+        assert_roundtrip(
+            "c8 18 16 e0 03 1f f8 c0 07 3e f0 81 0f 7c e0 03 1f f8 c0 07 3e f0 81 0f 7c ad",
+            ADDRESS_SYNC,
+            Frame::RcChannels([CHANNEL_VALUE_MID; RC_CHANNELS]),
+        );
+
+        let mut channels = [0; RC_CHANNELS];
+        for (idx, value) in channels.iter_mut().enumerate() {
+            *value = CHANNEL_VALUE_MIN + idx as u16 * 97;
+        }
+        let raw = Frame::RcChannels(channels).encode(ADDRESS_MODULE);
+        assert_eq!(raw.len(), 26);
+        assert_eq!(
+            Frame::decode(&raw),
+            Some((ADDRESS_MODULE, Frame::RcChannels(channels)))
+        );
+    }
+
+    #[test]
+    fn test_telemetry_roundtrip() {
+        assert_roundtrip(
+            "ea 0c 14 3c 48 62 09 00 06 03 37 64 f6 ae",
+            ADDRESS_RADIO,
+            Frame::LinkStatistics(LinkStatistics {
+                uplink_rssi_1: 60,
+                uplink_rssi_2: 72,
+                uplink_link_quality: 98,
+                uplink_snr: 9,
+                active_antenna: 0,
+                rf_mode: 6,
+                uplink_tx_power: 3,
+                downlink_rssi: 55,
+                downlink_link_quality: 100,
+                downlink_snr: -10,
+            }),
+        );
+        assert_roundtrip(
+            "ea 0a 08 00 a8 00 2d 00 04 d2 4b 13",
+            ADDRESS_RADIO,
+            Frame::Battery(Battery {
+                voltage: 168,
+                current: 45,
+                capacity: 1234,
+                remaining: 75,
+            }),
+        );
+        assert_roundtrip(
+            "ea 11 02 1c 40 52 4a 05 17 f4 43 00 7b 69 78 05 aa 09 83",
+            ADDRESS_RADIO,
+            Frame::Gps(Gps {
+                latitude: 473977418,
+                longitude: 85455939,
+                ground_speed: 123,
+                heading: 27000,
+                altitude: 1450,
+                satellites: 9,
+            }),
+        );
+        assert_roundtrip(
+            "ea 08 1e 14 74 eb 8c 00 00 f2",
+            ADDRESS_RADIO,
+            Frame::Attitude(Attitude {
+                pitch: 5236,
+                roll: -5236,
+                yaw: 0,
+            }),
+        );
+        assert_roundtrip(
+            "ea 07 21 41 43 52 4f 00 80",
+            ADDRESS_RADIO,
+            Frame::FlightMode("ACRO".to_string()),
+        );
+        assert_roundtrip(
+            "ea 06 09 27 7a 00 32 59",
+            ADDRESS_RADIO,
+            Frame::BaroAltitude {
+                altitude: 10106,
+                vario: Some(50),
+            },
+        );
+        assert_roundtrip("ea 04 07 ff 9c 31", ADDRESS_RADIO, Frame::Vario(-100));
+    }
+
+    #[test]
+    fn test_device_and_param_roundtrip() {
+        assert_roundtrip(
+            "c8 04 28 00 ea 54",
+            ADDRESS_SYNC,
+            Frame::DevicePing {
+                dest: ADDRESS_BROADCAST,
+                origin: ADDRESS_RADIO,
+            },
+        );
+
+        let info = DeviceInfo {
+            dest: ADDRESS_RADIO,
+            origin: ADDRESS_MODULE,
+            name: "ELRS TX".to_string(),
+            serial_number: 0x454C5253,
+            hardware_id: 0,
+            firmware_id: 0x00030401,
+            parameter_count: 28,
+            parameter_version: 0,
+        };
+        assert_eq!(info.firmware_version(), "3.4.1");
+        assert_roundtrip(
+            "ea 1a 29 ea ee 45 4c 52 53 20 54 58 00 45 4c 52 53 00 00 00 00 00 03 04 01 1c 00 99",
+            ADDRESS_RADIO,
+            Frame::DeviceInfo(info),
+        );
+
+        let mut data = vec![0x00, 0x09];
+        data.extend_from_slice(b"Packet Rate\0");
+        data.extend_from_slice(b"50Hz;150Hz\0");
+        data.extend_from_slice(&[1, 0, 1, 0, 0]);
+        assert_roundtrip(
+            "ea 24 2b ea ee 01 00 00 09 50 61 63 6b 65 74 20 52 61 74 65 00 35 30 48 7a \
+             3b 31 35 30 48 7a 00 01 00 01 00 00 cd",
+            ADDRESS_RADIO,
+            Frame::ParamEntry(ParamEntry {
+                dest: ADDRESS_RADIO,
+                origin: ADDRESS_MODULE,
+                field_id: 1,
+                chunks_remaining: 0,
+                data,
+            }),
+        );
+        assert_roundtrip(
+            "c8 06 2c ee ef 05 00 5a",
+            ADDRESS_SYNC,
+            Frame::ParamRead {
+                dest: ADDRESS_MODULE,
+                origin: ADDRESS_HANDSET,
+                field_id: 5,
+                chunk: 0,
+            },
+        );
+        assert_roundtrip(
+            "c8 0c 2d ee ef 06 6c 69 6e 2d 74 78 00 6f",
+            ADDRESS_SYNC,
+            Frame::ParamWrite {
+                dest: ADDRESS_MODULE,
+                origin: ADDRESS_HANDSET,
+                field_id: 6,
+                value: b"lin-tx\0".to_vec(),
+            },
+        );
+        assert_roundtrip(
+            "ea 09 2e ea ee 03 01 f4 01 00 dd",
+            ADDRESS_RADIO,
+            Frame::ElrsStatus(ElrsStatus {
+                dest: ADDRESS_RADIO,
+                origin: ADDRESS_MODULE,
+                bad_packets: 3,
+                good_packets: 500,
+                flags: 0x01,
+                message: String::new(),
+            }),
+        );
+    }
+
+    #[test]
+    fn test_timing_correction_roundtrip() {
+        // 250 Hz, RC frame 125 us late.
+        assert_roundtrip(
+            "ea 0d 3a ea ee 10 00 00 9c 40 ff ff fb 1e 5c",
+            ADDRESS_RADIO,
+            Frame::TimingCorrection {
+                dest: ADDRESS_RADIO,
+                origin: ADDRESS_MODULE,
+                interval: 40000,
+                offset: -1250,
+            },
+        );
+    }
+
+    #[test]
+    fn test_command_inner_crc() {
+        assert_roundtrip(
+            "c8 07 32 ee ea 10 01 04 b9",
+            ADDRESS_SYNC,
+            Frame::Command(Command {
+                dest: ADDRESS_MODULE,
+                origin: ADDRESS_RADIO,
+                command: COMMAND_CRSF,
+                payload: vec![SUBCOMMAND_BIND],
+            }),
+        );
+
+        assert_roundtrip(
+            "c8 0c 32 ee ea 0a 70 00 00 0e 10 00 8e e9",
+            ADDRESS_SYNC,
+            Frame::Command(Command::speed_proposal(
+                ADDRESS_MODULE,
+                ADDRESS_RADIO,
+                0,
+                921600,
+            )),
+        );
+        let Some((_, Frame::Command(response))) =
+            Frame::decode(&bytes("ea 09 32 ea ee 0a 71 00 01 7e 57"))
+        else {
+            panic!("speed response did not decode");
+        };
+        assert_eq!(response.speed_response(), Some((0, true)));
+
+        // Valid outer CRC but a wrong CRC8_BA is rejected.
+        let raw = encode_raw(ADDRESS_SYNC, FRAME_COMMAND, &[0xEE, 0xEA, 0x10, 0x01, 0x05]);
+        assert_eq!(Frame::decode(&raw), None);
+    }
+
+    #[test]
+    fn test_decode_rejects_bad_crc_and_short_payload() {
+        let mut raw = bytes("c8 04 28 00 ea 54");
+        raw[5] ^= 0xFF;
+        assert_eq!(Frame::decode(&raw), None);
+
+        assert_eq!(
+            Frame::decode(&encode_raw(ADDRESS_RADIO, FRAME_BATTERY, &[0, 1])),
+            None
+        );
+        assert_eq!(
+            Frame::decode(&encode_raw(ADDRESS_RADIO, 0x7F, &[1, 2])),
+            Some((
+                ADDRESS_RADIO,
+                Frame::Other {
+                    frame_type: 0x7F,
+                    payload: vec![1, 2],
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn test_extract_frames_skips_invalid_bytes() {
+        let mut buf = vec![0x00, 0x01];
+        buf.extend_from_slice(&bytes("c8 04 28 00 ea 54"));
+        buf.extend_from_slice(&bytes("ea 0c 14 3c 48"));
+        let frames = extract_frames(&mut buf);
+        assert_eq!(frames, vec![bytes("c8 04 28 00 ea 54")]);
+        // The partial frame waits for the rest of its bytes.
+        assert_eq!(buf, bytes("ea 0c 14 3c 48"));
+    }
+}
@@ -1,61 +1,47 @@
-use crate::{client_process_args, messages::AdcRawMsg};
+use crate::{
+    client_process_args,
+    config::InputSource,
+    crsf::{self, link, Frame},
+    messages::{CrsfFrameMsg, InputFrameMsg},
+};
 use clap::Parser;
-use crsf::{PacketParser, RcChannels};
 use rpos::{msg::get_new_tx_of_message, thread_logln};
 use std::time::Duration;
 
 #[derive(Parser)]
 #[command(name="crsf_rc_in", about = "Read RC data from STM32 via CRSF Protocol", long_about = None)]
 struct Cli {
-    #[arg(short, long, default_value_t = 420000)]
-    // CRSF typically uses 420k, but can be 115200. Defaulting to standard CRSF.
+    /// Fixed baud rate; 0 listens on 420000 and the ELRS rates until RC
+    /// frames arrive
+    #[arg(short, long, default_value_t = 0)]
     baudrate: u32,
 
     dev_name: String,
 }
 
-fn handle_channels(channels: &RcChannels, tx: &rpos::channel::Sender<AdcRawMsg>) {
+fn handle_channels(channels: &[u16; crsf::RC_CHANNELS], tx: &rpos::channel::Sender<InputFrameMsg>) {
     // CRSF channels are 0-1984 (11-bit), typically centered at 992.
-    // LinTx internal AdcRawMsg typically expects values.
-    // Let's assume AdcRawMsg expects raw values similar to what we got before.
-    // Previous code: [CH1_L, CH1_H] u16.
-    // Let's map CRSF channels [0..15] to AdcRawMsg.value array.
-
-    // Note: AdcRawMsg strictly has `value: [i16; 4]` based on previous analysis of stm32_serial.rs and adc.rs
-    // But we should probably expand AdcRawMsg to support more channels later.
-    // For now, we map the first 4 channels to the existing 4 slots to maintain compatibility.
-
-    let mut mapped_values = [0i16; 4];
-
-    // CRSF channel order is typically AETR (Aileron, Elevator, Throttle, Rudder) or TAER.
-    // Standard CRSF is AETR.
-    // LinTx mixer expects:
-    // Index 0: Thrust (Throttle)
-    // Index 1: Direction (Rudder)
-    // Index 2: Aileron
-    // Index 3: Elevator
-
-    // We need to know the channel mapping of the STM32 source.
-    // Assuming STM32 sends AETR (std CRSF):
+    // The STM32 source sends standard AETR order:
     // Ch 0: Aileron
     // Ch 1: Elevator
     // Ch 2: Throttle
     // Ch 3: Rudder
-
-    // Mapping to LinTx AdcRawMsg (based on calibrate.rs/mixer.rs usage):
-    // AdcRawMsg[0] -> used for Thrust
-    // AdcRawMsg[1] -> used for Direction
-    // AdcRawMsg[2] -> used for Aileron
-    // AdcRawMsg[3] -> used for Elevator
-
-    mapped_values[2] = channels.0[0] as i16; // Aileron
-    mapped_values[3] = channels.0[1] as i16; // Elevator
-    mapped_values[0] = channels.0[2] as i16; // Throttle
-    mapped_values[1] = channels.0[3] as i16; // Rudder
-
-    tx.send(AdcRawMsg {
-        value: mapped_values,
-    });
+    //
+    // The first four axes keep the layout existing calibrations were made with
+    // (calibrate.rs/mixer.rs usage):
+    // axes[0] -> Thrust
+    // axes[1] -> Direction
+    // axes[2] -> Aileron
+    // axes[3] -> Elevator
+    // Channels 5..16 follow unchanged as axes[4..16].
+
+    let mut axes: Vec<i16> = channels.iter().map(|value| *value as i16).collect();
+    axes[0] = channels[2] as i16; // Throttle
+    axes[1] = channels[3] as i16; // Rudder
+    axes[2] = channels[0] as i16; // Aileron
+    axes[3] = channels[1] as i16; // Elevator
+
+    tx.send(InputFrameMsg::from_axes(InputSource::Crsf, axes));
 }
 
 pub fn crsf_rc_in_main(argc: u32, argv: *const &str) {
@@ -66,32 +52,34 @@ pub fn crsf_rc_in_main(argc: u32, argv: *const &str) {
 
     let args = arg_ret.unwrap();
 
-    let serial = serialport::new(&args.dev_name, args.baudrate);
-    match serial.timeout(Duration::from_millis(100)).open() {
-        Ok(mut port) => {
-            thread_logln!(
-                "crsf_rc_in start on {} @ {} baud",
-                args.dev_name,
-                args.baudrate
-            );
-
-            let adc_raw_tx = get_new_tx_of_message::<AdcRawMsg>("adc_raw").unwrap();
-            let mut parser = PacketParser::<1024>::new(); // Internal buffer size
+    let opened = if args.baudrate != 0 {
+        link::open_port(&args.dev_name, args.baudrate).map(|port| (port, args.baudrate))
+    } else {
+        // The STM32 streams on its own, so probing only listens.
+        let mut candidates = vec![420000];
+        candidates.extend(link::CRSF_BAUDRATES);
+        link::probe(&args.dev_name, &candidates, false)
+    };
+    match opened {
+        Ok((mut port, baudrate)) => {
+            thread_logln!("crsf_rc_in start on {} @ {} baud", args.dev_name, baudrate);
+
+            let input_tx = get_new_tx_of_message::<InputFrameMsg>("input_frame").unwrap();
+            let frame_tx = get_new_tx_of_message::<CrsfFrameMsg>("crsf_rx").unwrap();
             let mut buf = [0u8; 1024];
+            let mut rx_buf = Vec::with_capacity(256);
 
             loop {
                 match port.read(&mut buf) {
                     Ok(n) if n > 0 => {
-                        parser.push_bytes(&buf[..n]);
-                        while let Some(packet) = parser.next_packet() {
-                            match packet {
-                                Ok((_addr, crsf::Packet::RcChannels(channels))) => {
-                                    handle_channels(&channels, &adc_raw_tx);
-                                }
-                                Ok(_) => {
-                                    // Ignore telemetry or other packets for now
+                        rx_buf.extend_from_slice(&buf[..n]);
+                        for frame in crsf::extract_frames(&mut rx_buf) {
+                            match Frame::decode(&frame) {
+                                Some((_, Frame::RcChannels(channels))) => {
+                                    handle_channels(&channels, &input_tx)
                                 }
-                                Err(_) => {}
+                                // Everything else goes to the `telemetry` module.
+                                _ => frame_tx.send(CrsfFrameMsg { frame }),
                             }
                         }
                     }
@@ -4,43 +4,53 @@ use std::{
 };
 
 use clap::Parser;
-use crc::{Crc, CRC_8_DVB_S2};
 use rpos::{
+    channel::{Receiver, Sender},
     msg::{get_new_rx_of_message, get_new_tx_of_message},
     thread_logln,
 };
 
 use crate::{
     client_process_args,
-    messages::{ElrsCommandMsg, ElrsParamEntry, ElrsStateMsg},
+    config::{store, ElrsParamValue, ModelConfig},
+    crsf::{self, Command, DeviceInfo, ElrsStatus, Frame, ParamEntry},
+    messages::{
+        ActiveModelMsg, CrsfFrameMsg, CrsfRateMsg, ElrsCommandMsg, ElrsParamEntry, ElrsStateMsg,
+    },
 };
 
-const CRSF_CRC: Crc<u8> = Crc::<u8>::new(&CRC_8_DVB_S2);
-const CRSF_SYNC: u8 = 0xC8;
-const CRSF_ADDRESS_BROADCAST: u8 = 0x00;
-const CRSF_ADDRESS_RADIO: u8 = 0xEA;
-const CRSF_ADDRESS_MODULE: u8 = 0xEE;
-const CRSF_ADDRESS_HANDSET: u8 = 0xEF;
-const CRSF_ADDRESS_RECEIVER: u8 = 0xEC;
-const CRSF_FRAME_PING_DEVICES: u8 = 0x28;
-const CRSF_FRAME_DEVICE_INFO: u8 = 0x29;
-const CRSF_FRAME_PARAM_ENTRY: u8 = 0x2B;
-const CRSF_FRAME_PARAM_READ: u8 = 0x2C;
-const CRSF_FRAME_PARAM_WRITE: u8 = 0x2D;
-const CRSF_FRAME_COMMAND: u8 = 0x32;
-const CRSF_FRAME_ELRS_INFO: u8 = 0x2E;
-const CRSF_SUBCMD_CRSF: u8 = 0x10;
-const CRSF_SUBCMD_BIND: u8 = 0x01;
-const CRSF_MAX_FRAME_LEN: usize = 64;
-const CRSF_MAX_PACKET_SIZE: usize = CRSF_MAX_FRAME_LEN + 2;
 const CRSF_PARAM_HIDDEN_MASK: u8 = 0x80;
 const CRSF_PARAM_TYPE_UINT8: u8 = 0x00;
+const CRSF_PARAM_TYPE_INT8: u8 = 0x01;
+const CRSF_PARAM_TYPE_UINT16: u8 = 0x02;
+const CRSF_PARAM_TYPE_INT16: u8 = 0x03;
+const CRSF_PARAM_TYPE_UINT32: u8 = 0x04;
+const CRSF_PARAM_TYPE_INT32: u8 = 0x05;
+const CRSF_PARAM_TYPE_FLOAT: u8 = 0x08;
 const CRSF_PARAM_TYPE_TEXT_SELECTION: u8 = 0x09;
 const CRSF_PARAM_TYPE_STRING: u8 = 0x0A;
 const CRSF_PARAM_TYPE_FOLDER: u8 = 0x0B;
 const CRSF_PARAM_TYPE_INFO: u8 = 0x0C;
 const CRSF_PARAM_TYPE_COMMAND: u8 = 0x0D;
+/// Answer to a read past the device's last field.
+const CRSF_PARAM_TYPE_OUT_OF_RANGE: u8 = 0x7F;
+/// Command field steps, written to drive a command and reported back.
+const CRSF_COMMAND_STEP_IDLE: u8 = 0;
+const CRSF_COMMAND_STEP_CLICK: u8 = 1;
+const CRSF_COMMAND_STEP_EXECUTING: u8 = 2;
+const CRSF_COMMAND_STEP_ASK_CONFIRM: u8 = 3;
+const CRSF_COMMAND_STEP_CONFIRMED: u8 = 4;
+const CRSF_COMMAND_STEP_CANCEL: u8 = 5;
+const CRSF_COMMAND_STEP_QUERY: u8 = 6;
+/// Floor for the device's command timeout, which may be zero.
+const COMMAND_MIN_POLL_INTERVAL: Duration = Duration::from_millis(100);
+/// More decimals than this are shown rounded down, as on EdgeTX.
+const CRSF_FLOAT_MAX_PRECISION: u8 = 3;
 const DISCOVERY_MISS_LIMIT: u8 = 16;
+/// Devices that stop answering pings for this long are dropped.
+const DEVICE_INFO_TIMEOUT: Duration = Duration::from_secs(3);
+/// RC rate reports older than this mean no CRSF output is running.
+const RC_RATE_TIMEOUT: Duration = Duration::from_secs(3);
 const STRING_EDIT_MAX_LEN: usize = 32;
 const STRING_EDIT_CHARSET: &[u8] =
     b" abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789-_.";
@@ -48,6 +58,7 @@ const STRING_EDIT_CHARSET: &[u8] =
 #[derive(Parser)]
 #[command(name = "elrs_agent", about = "ELRS configuration state service")]
 struct Cli {
+    /// mock, crsf (own the UART) or link (through the `output` CRSF driver)
     #[arg(long, default_value = "mock")]
     mode: String,
 
@@ -159,6 +170,24 @@ enum VisibleEntry {
     Refresh,
     FolderUp,
     Field(u8),
+    /// Opens the list of other CRSF devices, after the root fields.
+    OtherDevices,
+    Device(u8),
+    /// Opens the model's saved module settings, after the root fields.
+    ModelSettings,
+    SaveToModel,
+    ApplyToModule,
+    /// A field whose live value differs from the model's.
+    SnapshotDiff(u8),
+}
+
+/// What the ELRS page lists.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MenuView {
+    /// The browsed device's parameter tree.
+    Fields,
+    Devices,
+    ModelSettings,
 }
 
 #[derive(Debug, Clone)]
@@ -273,12 +302,16 @@ impl MockElrsAgent {
     }
 
     fn handle_command(&mut self, cmd: ElrsCommandMsg) {
-        if self.edit_state.is_some() {
+        if self.edit_state.is_some() && cmd != ElrsCommandMsg::Bind {
             self.handle_edit_command(cmd);
             return;
         }
 
         match cmd {
+            ElrsCommandMsg::Bind => {
+                self.bind_until = Some(Instant::now() + Duration::from_secs(2));
+                self.status_text = "Binding request sent".to_string();
+            }
             ElrsCommandMsg::Refresh => {
                 self.status_text = "Refreshed mock ELRS tree".to_string();
             }
@@ -328,7 +361,7 @@ impl MockElrsAgent {
                 self.status_text = format!("Updated `{}` = {}", edit.label, value);
                 self.edit_state = None;
             }
-            ElrsCommandMsg::Refresh => {}
+            ElrsCommandMsg::Refresh | ElrsCommandMsg::Bind => {}
         }
     }
 
@@ -362,6 +395,14 @@ impl MockElrsAgent {
                     value: "Back".to_string(),
                     selectable: true,
                 },
+                VisibleEntry::OtherDevices
+                | VisibleEntry::Device(_)
+                | VisibleEntry::ModelSettings
+                | VisibleEntry::SaveToModel
+                | VisibleEntry::ApplyToModule
+                | VisibleEntry::SnapshotDiff(_) => {
+                    unreachable!("the mock lists no devices or model settings")
+                }
                 VisibleEntry::Field(field_id) => {
                     let field = self.field(field_id).unwrap();
                     ElrsParamEntry {
@@ -388,6 +429,12 @@ impl MockElrsAgent {
             VisibleEntry::FolderUp => {
                 self.handle_command(ElrsCommandMsg::Back);
             }
+            VisibleEntry::OtherDevices
+            | VisibleEntry::Device(_)
+            | VisibleEntry::ModelSettings
+            | VisibleEntry::SaveToModel
+            | VisibleEntry::ApplyToModule
+            | VisibleEntry::SnapshotDiff(_) => {}
             VisibleEntry::Field(field_id) => {
                 let Some(field) = self.field(field_id).cloned() else {
                     return;
@@ -514,6 +561,10 @@ impl MockElrsAgent {
             busy: self.bind_until.is_some(),
             can_leave: self.current_folder == 0 && self.edit_state.is_none(),
             path: self.current_path(),
+            command_active: false,
+            command_label: String::new(),
+            command_info: String::new(),
+            command_confirm: false,
             editor_active: self.edit_state.is_some(),
             editor_label: self
                 .edit_state
@@ -536,6 +587,7 @@ impl MockElrsAgent {
             packet_rate,
             telemetry_ratio,
             tx_power,
+            rc_rate: "--".to_string(),
             status_text: self.status_text.clone(),
             wifi_running: self.wifi_running,
             selected_idx: self.selected_idx,
@@ -546,8 +598,19 @@ impl MockElrsAgent {
 
 #[derive(Debug, Clone)]
 struct CrsfRuntime {
-    dev_name: String,
-    baudrate: u32,
+    /// Where frames go, for status text.
+    link_name: String,
+    /// Everything that answered the broadcast ping, TX module first.
+    devices: Vec<CrsfDevice>,
+    /// Device whose parameters are browsed.
+    target: u8,
+    view: MenuView,
+    /// Active model and its saved module settings.
+    model_id: String,
+    model_name: String,
+    snapshot: Vec<ElrsParamValue>,
+    /// Report the snapshot diff once the module's tree is scanned.
+    snapshot_check_pending: bool,
     selected_idx: usize,
     connected: bool,
     module_name: String,
@@ -561,6 +624,7 @@ struct CrsfRuntime {
     current_folder: u8,
     folder_stack: Vec<u8>,
     edit_state: Option<StringEditState>,
+    command: Option<CommandRun>,
     fields: Vec<CrsfField>,
     pending_reads: Vec<u8>,
     active_read: Option<PendingRead>,
@@ -572,17 +636,24 @@ struct CrsfRuntime {
     last_ping_at: Instant,
     last_info_at: Option<Instant>,
     last_refresh_at: Instant,
+    rc_rate: Option<(CrsfRateMsg, Instant)>,
 }
 
 impl CrsfRuntime {
-    fn new(dev_name: String, baudrate: u32) -> Self {
+    fn new(link_name: String) -> Self {
         Self {
-            dev_name: dev_name.clone(),
-            baudrate,
+            link_name: link_name.clone(),
+            devices: Vec::new(),
+            target: crsf::ADDRESS_MODULE,
+            view: MenuView::Fields,
+            model_id: String::new(),
+            model_name: String::new(),
+            snapshot: Vec::new(),
+            snapshot_check_pending: false,
             selected_idx: 1,
             connected: false,
             module_name: "ExpressLRS TX".to_string(),
-            device_name: dev_name,
+            device_name: link_name,
             version: "--".to_string(),
             packet_rate: "--".to_string(),
             telemetry_ratio: "--".to_string(),
@@ -592,6 +663,7 @@ impl CrsfRuntime {
             current_folder: 0,
             folder_stack: Vec::new(),
             edit_state: None,
+            command: None,
             fields: Vec::new(),
             pending_reads: Vec::new(),
             active_read: None,
@@ -603,6 +675,7 @@ impl CrsfRuntime {
             last_ping_at: Instant::now() - Duration::from_secs(10),
             last_info_at: None,
             last_refresh_at: Instant::now(),
+            rc_rate: None,
         }
     }
 
@@ -616,7 +689,9 @@ impl CrsfRuntime {
         self.highest_field_id_seen = 0;
         self.current_folder = 0;
         self.folder_stack.clear();
+        self.view = MenuView::Fields;
         self.edit_state = None;
+        self.command = None;
         self.selected_idx = 1;
         if clear_fields {
             self.fields.clear();
@@ -628,6 +703,32 @@ impl CrsfRuntime {
 
     fn visible_entries(&self) -> Vec<VisibleEntry> {
         let mut entries = vec![VisibleEntry::Refresh];
+        match self.view {
+            MenuView::Fields => {}
+            MenuView::Devices => {
+                entries.push(VisibleEntry::FolderUp);
+                entries.extend(
+                    self.devices
+                        .iter()
+                        .map(|device| VisibleEntry::Device(device.address)),
+                );
+                return entries;
+            }
+            MenuView::ModelSettings => {
+                entries.push(VisibleEntry::FolderUp);
+                entries.push(VisibleEntry::SaveToModel);
+                let diffs = self.snapshot_diff();
+                if !diffs.is_empty() {
+                    entries.push(VisibleEntry::ApplyToModule);
+                }
+                entries.extend(
+                    diffs
+                        .iter()
+                        .map(|diff| VisibleEntry::SnapshotDiff(diff.field_id)),
+                );
+                return entries;
+            }
+        }
         if self.current_folder != 0 {
             entries.push(VisibleEntry::FolderUp);
         }
@@ -637,9 +738,41 @@ impl CrsfRuntime {
                 .filter(|field| !field.hidden && field.parent == self.current_folder)
                 .map(|field| VisibleEntry::Field(field.field_id)),
         );
+        if self.current_folder == 0 && self.target == crsf::ADDRESS_MODULE && self.connected {
+            entries.push(VisibleEntry::ModelSettings);
+        }
+        if self.current_folder == 0
+            && self
+                .devices
+                .iter()
+                .any(|device| device.address != self.target)
+        {
+            entries.push(VisibleEntry::OtherDevices);
+        }
         entries
     }
 
+    fn device(&self, address: u8) -> Option<&CrsfDevice> {
+        self.devices.iter().find(|device| device.address == address)
+    }
+
+    /// Switches the parameter tree to another device and rescans it.
+    fn select_device(&mut self, address: u8) {
+        let Some(device) = self.device(address).cloned() else {
+            return;
+        };
+        if address != self.target {
+            self.target = address;
+            self.reset_scan(true);
+        }
+        self.view = MenuView::Fields;
+        self.device_name = device.name.clone();
+        self.version = device.version.clone();
+        self.last_info_at = Some(device.last_seen);
+        self.focus_first_field();
+        self.status_text = format!("Browsing {}", device.name);
+    }
+
     fn params(&self) -> Vec<ElrsParamEntry> {
         self.visible_entries()
             .into_iter()
@@ -663,10 +796,170 @@ impl CrsfRuntime {
                 VisibleEntry::Field(field_id) => {
                     self.field(field_id).and_then(CrsfField::to_param_entry)
                 }
+                VisibleEntry::OtherDevices => Some(ElrsParamEntry {
+                    id: "other_devices".to_string(),
+                    label: "Other Devices".to_string(),
+                    value: ">".to_string(),
+                    selectable: true,
+                }),
+                VisibleEntry::Device(address) => {
+                    self.device(address).map(|device| ElrsParamEntry {
+                        id: format!("device_{:02x}", address),
+                        label: device.name.clone(),
+                        value: if address == self.target {
+                            "Current".to_string()
+                        } else {
+                            device.version.clone()
+                        },
+                        selectable: true,
+                    })
+                }
+                VisibleEntry::ModelSettings => Some(ElrsParamEntry {
+                    id: "model_settings".to_string(),
+                    label: "Model Settings".to_string(),
+                    value: if self.snapshot.is_empty() {
+                        "Not saved".to_string()
+                    } else if !self.discovery_complete {
+                        "Scanning".to_string()
+                    } else {
+                        match self.snapshot_diff().len() {
+                            0 => "Match".to_string(),
+                            count => format!("{count} differ"),
+                        }
+                    },
+                    selectable: true,
+                }),
+                VisibleEntry::SaveToModel => Some(ElrsParamEntry {
+                    id: "save_to_model".to_string(),
+                    label: format!("Save to {}", self.model_name),
+                    value: format!("{} saved", self.snapshot.len()),
+                    selectable: true,
+                }),
+                VisibleEntry::ApplyToModule => Some(ElrsParamEntry {
+                    id: "apply_to_module".to_string(),
+                    label: "Apply to module".to_string(),
+                    value: format!("{} changes", self.snapshot_diff().len()),
+                    selectable: true,
+                }),
+                VisibleEntry::SnapshotDiff(field_id) => self
+                    .snapshot_diff()
+                    .into_iter()
+                    .find(|diff| diff.field_id == field_id)
+                    .map(|diff| ElrsParamEntry {
+                        id: format!("diff_{field_id}"),
+                        label: diff.key,
+                        value: format!("{} -> {}", diff.live, diff.stored),
+                        selectable: false,
+                    }),
+            })
+            .collect()
+    }
+
+    /// Folder path and label of a field, the key of its saved value.
+    fn field_key(&self, field: &CrsfField) -> String {
+        let mut parts = vec![field.label.as_str()];
+        let mut parent = field.parent;
+        // Bounded, in case a broken tree loops.
+        while parent != 0 && parts.len() < 8 {
+            let Some(folder) = self.field(parent) else {
+                break;
+            };
+            parts.push(folder.label.as_str());
+            parent = folder.parent;
+        }
+        parts.reverse();
+        parts.join("/")
+    }
+
+    /// Current values of the module's editable numbers and selections.
+    fn snapshot_values(&self) -> Vec<ElrsParamValue> {
+        self.fields
+            .iter()
+            .filter(|field| !field.hidden)
+            .filter_map(|field| {
+                let value = match &field.value {
+                    CrsfFieldValue::Number { current, .. } => *current,
+                    CrsfFieldValue::TextSelection { current, .. } => *current as i64,
+                    _ => return None,
+                };
+                Some(ElrsParamValue {
+                    key: self.field_key(field),
+                    value,
+                    text: field.display_value(),
+                })
+            })
+            .collect()
+    }
+
+    /// Saved values that differ from the live fields. Saved keys the
+    /// module does not show (yet) are left out.
+    fn snapshot_diff(&self) -> Vec<SnapshotDiff> {
+        self.snapshot
+            .iter()
+            .filter_map(|saved| {
+                let field = self
+                    .fields
+                    .iter()
+                    .find(|field| !field.hidden && self.field_key(field) == saved.key)?;
+                let (current, value) = match &field.value {
+                    CrsfFieldValue::Number { current, .. } => (*current, saved.value),
+                    CrsfFieldValue::TextSelection {
+                        current, options, ..
+                    } => (
+                        *current as i64,
+                        options
+                            .iter()
+                            .position(|option| *option == saved.text)
+                            .map_or(saved.value, |index| index as i64),
+                    ),
+                    _ => return None,
+                };
+                (current != value).then(|| SnapshotDiff {
+                    field_id: field.field_id,
+                    key: saved.key.clone(),
+                    live: field.display_value(),
+                    stored: if saved.text.is_empty() {
+                        value.to_string()
+                    } else {
+                        saved.text.clone()
+                    },
+                    value,
+                })
             })
             .collect()
     }
 
+    fn set_model(&mut self, model: &ModelConfig) {
+        // The same model published again keeps the check already reported.
+        if model.id == self.model_id && model.elrs.params == self.snapshot {
+            self.model_name = model.name.clone();
+            return;
+        }
+        self.model_id = model.id.clone();
+        self.model_name = model.name.clone();
+        self.snapshot = model.elrs.params.clone();
+        self.snapshot_check_pending = !self.snapshot.is_empty();
+    }
+
+    /// Reports the diff against a newly selected model once the module's
+    /// tree is complete.
+    fn check_snapshot(&mut self) {
+        if !self.snapshot_check_pending
+            || !self.discovery_complete
+            || self.target != crsf::ADDRESS_MODULE
+        {
+            return;
+        }
+        self.snapshot_check_pending = false;
+        self.status_text = match self.snapshot_diff().len() {
+            0 => format!("ELRS settings match `{}`", self.model_name),
+            count => format!(
+                "{count} ELRS settings differ from `{}`, see Model Settings",
+                self.model_name
+            ),
+        };
+    }
+
     fn field(&self, field_id: u8) -> Option<&CrsfField> {
         self.fields.iter().find(|field| field.field_id == field_id)
     }
@@ -678,7 +971,11 @@ impl CrsfRuntime {
     }
 
     fn focus_first_field(&mut self) {
-        self.selected_idx = if self.current_folder == 0 { 1 } else { 2 };
+        self.selected_idx = if self.current_folder == 0 && self.view == MenuView::Fields {
+            1
+        } else {
+            2
+        };
         self.normalize_selection();
     }
 
@@ -688,6 +985,11 @@ impl CrsfRuntime {
     }
 
     fn current_path(&self) -> String {
+        match self.view {
+            MenuView::Fields => {}
+            MenuView::Devices => return "/Other Devices".to_string(),
+            MenuView::ModelSettings => return "/Model Settings".to_string(),
+        }
         let mut parts = Vec::new();
         for folder_id in self
             .folder_stack
@@ -707,6 +1009,19 @@ impl CrsfRuntime {
         }
     }
 
+    /// Leaves the device or model list, or the current folder.
+    fn go_back(&mut self) {
+        if self.view != MenuView::Fields {
+            self.view = MenuView::Fields;
+        } else if self.current_folder != 0 {
+            self.current_folder = self.folder_stack.pop().unwrap_or(0);
+        } else {
+            return;
+        }
+        self.focus_first_field();
+        self.status_text = format!("Back to {}", self.current_path());
+    }
+
     fn selected_visible_entry(&self) -> VisibleEntry {
         self.visible_entries()
             .get(self.selected_idx)
@@ -717,9 +1032,31 @@ impl CrsfRuntime {
     fn state(&self, busy: bool) -> ElrsStateMsg {
         ElrsStateMsg {
             connected: self.connected,
-            busy,
-            can_leave: self.current_folder == 0 && self.edit_state.is_none(),
+            busy: busy
+                || self
+                    .command
+                    .as_ref()
+                    .is_some_and(|run| !run.awaiting_confirm()),
+            can_leave: self.current_folder == 0
+                && self.view == MenuView::Fields
+                && self.edit_state.is_none()
+                && self.command.is_none(),
             path: self.current_path(),
+            command_active: self.command.is_some(),
+            command_label: self
+                .command
+                .as_ref()
+                .map(|run| run.label.clone())
+                .unwrap_or_default(),
+            command_info: self
+                .command
+                .as_ref()
+                .map(|run| run.info.clone())
+                .unwrap_or_default(),
+            command_confirm: self
+                .command
+                .as_ref()
+                .is_some_and(CommandRun::awaiting_confirm),
             editor_active: self.edit_state.is_some(),
             editor_label: self
                 .edit_state
@@ -742,6 +1079,10 @@ impl CrsfRuntime {
             packet_rate: self.packet_rate.clone(),
             telemetry_ratio: self.telemetry_ratio.clone(),
             tx_power: self.tx_power.clone(),
+            rc_rate: self
+                .rc_rate
+                .filter(|(_, at)| at.elapsed() < RC_RATE_TIMEOUT)
+                .map_or("--".to_string(), |(rate, _)| rc_rate_text(&rate)),
             status_text: self.status_text.clone(),
             wifi_running: self.wifi_running,
             selected_idx: self.selected_idx,
@@ -750,6 +1091,46 @@ impl CrsfRuntime {
     }
 }
 
+/// A command field from START until the device reports IDLE again.
+#[derive(Debug, Clone)]
+struct CommandRun {
+    field_id: u8,
+    parent: u8,
+    label: String,
+    /// Last step written or reported.
+    step: u8,
+    info: String,
+    poll_interval: Duration,
+    /// When to ask the device for progress again.
+    poll_at: Instant,
+}
+
+impl CommandRun {
+    fn awaiting_confirm(&self) -> bool {
+        self.step == CRSF_COMMAND_STEP_ASK_CONFIRM
+    }
+}
+
+/// A saved module setting that differs from the live field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct SnapshotDiff {
+    field_id: u8,
+    key: String,
+    live: String,
+    stored: String,
+    /// Raw value that restores the saved setting.
+    value: i64,
+}
+
+/// A CRSF device that answered a ping.
+#[derive(Debug, Clone)]
+struct CrsfDevice {
+    address: u8,
+    name: String,
+    version: String,
+    last_seen: Instant,
+}
+
 #[derive(Debug, Clone)]
 struct PendingRead {
     field_id: u8,
@@ -764,13 +1145,77 @@ struct PendingChunk {
     bytes: Vec<u8>,
 }
 
+/// Wire format of a numeric field. FLOAT is an INT32 scaled by
+/// `10^precision`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum NumberKind {
+    Uint8,
+    Int8,
+    Uint16,
+    Int16,
+    Uint32,
+    Int32,
+    Float,
+}
+
+impl NumberKind {
+    fn from_type(field_type: u8) -> Option<Self> {
+        match field_type {
+            CRSF_PARAM_TYPE_UINT8 => Some(Self::Uint8),
+            CRSF_PARAM_TYPE_INT8 => Some(Self::Int8),
+            CRSF_PARAM_TYPE_UINT16 => Some(Self::Uint16),
+            CRSF_PARAM_TYPE_INT16 => Some(Self::Int16),
+            CRSF_PARAM_TYPE_UINT32 => Some(Self::Uint32),
+            CRSF_PARAM_TYPE_INT32 => Some(Self::Int32),
+            CRSF_PARAM_TYPE_FLOAT => Some(Self::Float),
+            _ => None,
+        }
+    }
+
+    fn size(self) -> usize {
+        match self {
+            Self::Uint8 | Self::Int8 => 1,
+            Self::Uint16 | Self::Int16 => 2,
+            Self::Uint32 | Self::Int32 | Self::Float => 4,
+        }
+    }
+
+    fn signed(self) -> bool {
+        matches!(self, Self::Int8 | Self::Int16 | Self::Int32 | Self::Float)
+    }
+
+    /// Reads one big-endian value at `idx`.
+    fn read(self, bytes: &[u8], idx: &mut usize) -> Option<i64> {
+        let size = self.size();
+        let raw = bytes.get(*idx..*idx + size)?;
+        *idx += size;
+        let value = raw.iter().fold(0u64, |acc, byte| acc << 8 | *byte as u64);
+        let shift = 64 - size as u32 * 8;
+        Some(if self.signed() {
+            ((value << shift) as i64) >> shift
+        } else {
+            value as i64
+        })
+    }
+
+    /// `0x2D` payload, sized to the field type.
+    fn encode(self, value: i64) -> Vec<u8> {
+        value.to_be_bytes()[8 - self.size()..].to_vec()
+    }
+}
+
 #[derive(Debug, Clone)]
 enum CrsfFieldValue {
-    Uint8 {
-        current: u8,
-        min: u8,
-        max: u8,
-        default: u8,
+    Number {
+        kind: NumberKind,
+        current: i64,
+        min: i64,
+        max: i64,
+        default: i64,
+        /// Decimal places, only non-zero for FLOAT.
+        precision: u8,
+        /// Change per +/- press, in raw units.
+        step: u32,
         unit: String,
     },
     TextSelection {
@@ -793,6 +1238,7 @@ enum CrsfFieldValue {
         timeout_10ms: u8,
         info: String,
     },
+    OutOfRange,
     Unknown {
         raw_type: u8,
         bytes: Vec<u8>,
@@ -824,11 +1270,17 @@ impl CrsfField {
 
     fn display_value(&self) -> String {
         match &self.value {
-            CrsfFieldValue::Uint8 { current, unit, .. } => {
+            CrsfFieldValue::Number {
+                current,
+                precision,
+                unit,
+                ..
+            } => {
+                let value = format_number(*current, *precision);
                 if unit.is_empty() {
-                    current.to_string()
+                    value
                 } else {
-                    format!("{current} {unit}")
+                    format!("{value} {unit}")
                 }
             }
             CrsfFieldValue::TextSelection {
@@ -847,6 +1299,7 @@ impl CrsfField {
                     info.clone()
                 }
             }
+            CrsfFieldValue::OutOfRange => "--".to_string(),
             CrsfFieldValue::Unknown { raw_type, bytes } => {
                 format!("type=0x{raw_type:02x} [{}B]", bytes.len())
             }
@@ -856,7 +1309,7 @@ impl CrsfField {
     fn is_selectable(&self) -> bool {
         matches!(
             self.value,
-            CrsfFieldValue::Uint8 { .. }
+            CrsfFieldValue::Number { .. }
                 | CrsfFieldValue::TextSelection { .. }
                 | CrsfFieldValue::String { .. }
                 | CrsfFieldValue::Folder
@@ -865,22 +1318,36 @@ impl CrsfField {
     }
 }
 
+/// How the agent reaches the module.
+trait CrsfLink {
+    fn write_frame(&mut self, frame: &[u8]) -> std::io::Result<()>;
+
+    /// Frames received since the last poll.
+    fn poll_frames(&mut self) -> std::io::Result<Vec<Vec<u8>>>;
+}
+
+/// The agent owns the UART. Only usable while nothing else streams RC on it.
 struct CrsfPort {
     port: Box<dyn serialport::SerialPort>,
     rx_buf: Vec<u8>,
+    /// Telemetry shares the port; the `telemetry` module decodes it.
+    frame_tx: Sender<CrsfFrameMsg>,
 }
 
 impl CrsfPort {
-    fn open(path: &str, baudrate: u32) -> std::io::Result<Self> {
+    fn open(path: &str, baudrate: u32, frame_tx: Sender<CrsfFrameMsg>) -> std::io::Result<Self> {
         let port = serialport::new(path, baudrate)
             .timeout(Duration::from_millis(20))
             .open()?;
         Ok(Self {
             port,
             rx_buf: Vec::with_capacity(256),
+            frame_tx,
         })
     }
+}
 
+impl CrsfLink for CrsfPort {
     fn write_frame(&mut self, frame: &[u8]) -> std::io::Result<()> {
         elrs_debug_log(&format!("tx {}", hex_bytes(frame)));
         self.port.write_all(frame)?;
@@ -896,7 +1363,47 @@ impl CrsfPort {
             Err(err) => return Err(err),
         }
 
-        Ok(extract_crsf_frames(&mut self.rx_buf))
+        let frames = crsf::extract_frames(&mut self.rx_buf);
+        for frame in &frames {
+            self.frame_tx.send(CrsfFrameMsg {
+                frame: frame.clone(),
+            });
+        }
+        Ok(frames)
+    }
+}
+
+/// The `output` CRSF driver owns the UART: frames queued on `crsf_tx` go out
+/// between RC frames and everything the module answers arrives on `crsf_rx`.
+struct SharedLink {
+    tx: Sender<CrsfFrameMsg>,
+    rx: Receiver<CrsfFrameMsg>,
+}
+
+impl SharedLink {
+    fn new() -> Self {
+        Self {
+            tx: get_new_tx_of_message::<CrsfFrameMsg>("crsf_tx").unwrap(),
+            rx: get_new_rx_of_message::<CrsfFrameMsg>("crsf_rx").unwrap(),
+        }
+    }
+}
+
+impl CrsfLink for SharedLink {
+    fn write_frame(&mut self, frame: &[u8]) -> std::io::Result<()> {
+        elrs_debug_log(&format!("tx {}", hex_bytes(frame)));
+        self.tx.send(CrsfFrameMsg {
+            frame: frame.to_vec(),
+        });
+        Ok(())
+    }
+
+    fn poll_frames(&mut self) -> std::io::Result<Vec<Vec<u8>>> {
+        let mut frames = Vec::new();
+        while let Some(msg) = self.rx.try_read() {
+            frames.push(msg.frame);
+        }
+        Ok(frames)
     }
 }
 
@@ -909,7 +1416,16 @@ fn elrs_agent_main(argc: u32, argv: *const &str) {
     let mut cmd_rx = get_new_rx_of_message::<ElrsCommandMsg>("elrs_cmd").unwrap();
 
     match args.mode.as_str() {
-        "crsf" | "real" => run_crsf_agent(args, state_tx, &mut cmd_rx),
+        "crsf" | "real" => run_uart_agent(args, state_tx, &mut cmd_rx),
+        "link" => {
+            thread_logln!("elrs_agent start on the shared CRSF link");
+            run_crsf_agent(
+                &mut SharedLink::new(),
+                "CRSF output link".to_string(),
+                state_tx,
+                &mut cmd_rx,
+            );
+        }
         "mock" => run_mock_agent(args, state_tx, &mut cmd_rx),
         other => {
             thread_logln!(
@@ -941,12 +1457,13 @@ fn run_mock_agent(
     }
 }
 
-fn run_crsf_agent(
+fn run_uart_agent(
     args: Cli,
     state_tx: rpos::channel::Sender<ElrsStateMsg>,
     cmd_rx: &mut rpos::channel::Receiver<ElrsCommandMsg>,
 ) {
-    let mut port = match CrsfPort::open(&args.dev_name, args.baudrate) {
+    let frame_tx = get_new_tx_of_message::<CrsfFrameMsg>("crsf_rx").unwrap();
+    let mut port = match CrsfPort::open(&args.dev_name, args.baudrate, frame_tx) {
         Ok(port) => port,
         Err(err) => {
             thread_logln!(
@@ -966,18 +1483,39 @@ fn run_crsf_agent(
         }
     };
 
-    let mut runtime = CrsfRuntime::new(args.dev_name, args.baudrate);
-    runtime.status_text = "CRSF port opened, probing module".to_string();
-    state_tx.send(runtime.state(false));
     thread_logln!("elrs_agent start in crsf mode");
+    let link_name = format!("{} @ {}", args.dev_name, args.baudrate);
+    run_crsf_agent(&mut port, link_name, state_tx, cmd_rx);
+}
+
+fn run_crsf_agent(
+    link: &mut dyn CrsfLink,
+    link_name: String,
+    state_tx: rpos::channel::Sender<ElrsStateMsg>,
+    cmd_rx: &mut rpos::channel::Receiver<ElrsCommandMsg>,
+) {
+    let mut rate_rx = get_new_rx_of_message::<CrsfRateMsg>("crsf_rate").unwrap();
+    let mut model_rx = get_new_rx_of_message::<ActiveModelMsg>("active_model").unwrap();
+    let mut runtime = CrsfRuntime::new(link_name);
+    if let Ok(model) = store::load_active_model() {
+        runtime.set_model(&model);
+    }
+    runtime.status_text = "CRSF link ready, probing module".to_string();
+    state_tx.send(runtime.state(false));
 
     let mut bind_busy_until: Option<Instant> = None;
     loop {
         while let Some(cmd) = cmd_rx.try_read() {
-            handle_crsf_command(cmd, &mut runtime, &mut port, &mut bind_busy_until);
+            handle_crsf_command(cmd, &mut runtime, link, &mut bind_busy_until);
+        }
+        while let Some(rate) = rate_rx.try_read() {
+            runtime.rc_rate = Some((rate, Instant::now()));
+        }
+        while let Some(msg) = model_rx.try_read() {
+            runtime.set_model(&msg.model);
         }
 
-        match port.poll_frames() {
+        match link.poll_frames() {
             Ok(frames) => {
                 for frame in frames {
                     elrs_debug_log(&format!("rx {}", hex_bytes(&frame)));
@@ -991,7 +1529,7 @@ fn run_crsf_agent(
         }
 
         if runtime.last_ping_at.elapsed() >= Duration::from_secs(1) {
-            if let Err(err) = port.write_frame(&build_ping_frame()) {
+            if let Err(err) = link.write_frame(&build_ping_frame()) {
                 runtime.connected = false;
                 runtime.status_text = format!("Ping failed: {}", err);
             } else {
@@ -1000,15 +1538,22 @@ fn run_crsf_agent(
         }
 
         if runtime.connected {
-            drive_param_discovery(&mut runtime, &mut port);
+            drive_command_run(&mut runtime, link);
+            drive_param_discovery(&mut runtime, link);
+            runtime.check_snapshot();
         }
 
         if let Some(last_info) = runtime.last_info_at {
-            if last_info.elapsed() > Duration::from_secs(3) {
+            if last_info.elapsed() > DEVICE_INFO_TIMEOUT {
                 runtime.connected = false;
-                runtime.status_text = "ELRS module info timeout".to_string();
+                runtime.status_text = format!("{} info timeout", runtime.device_name);
             }
         }
+        let target = runtime.target;
+        runtime.devices.retain(|device| {
+            device.address == target || device.last_seen.elapsed() < DEVICE_INFO_TIMEOUT
+        });
+        runtime.normalize_selection();
 
         if let Some(until) = bind_busy_until {
             if Instant::now() >= until {
@@ -1025,15 +1570,28 @@ fn run_crsf_agent(
 fn handle_crsf_command(
     cmd: ElrsCommandMsg,
     runtime: &mut CrsfRuntime,
-    port: &mut CrsfPort,
+    port: &mut dyn CrsfLink,
     bind_busy_until: &mut Option<Instant>,
 ) {
-    if runtime.edit_state.is_some() {
+    if runtime.edit_state.is_some() && cmd != ElrsCommandMsg::Bind {
         handle_string_edit_command(cmd, runtime, port);
         return;
     }
+    if runtime.command.is_some() && cmd != ElrsCommandMsg::Bind {
+        handle_command_run_input(cmd, runtime, port);
+        return;
+    }
 
     match cmd {
+        ElrsCommandMsg::Bind => match port.write_frame(&build_bind_frame(false)) {
+            Ok(_) => {
+                *bind_busy_until = Some(Instant::now() + Duration::from_secs(2));
+                runtime.status_text = "Bind frame sent".to_string();
+            }
+            Err(err) => {
+                runtime.status_text = format!("Bind failed: {}", err);
+            }
+        },
         ElrsCommandMsg::SelectPrev => {
             runtime.selected_idx = runtime.selected_idx.saturating_sub(1);
         }
@@ -1041,13 +1599,7 @@ fn handle_crsf_command(
             let max_idx = runtime.visible_entries().len().saturating_sub(1);
             runtime.selected_idx = (runtime.selected_idx + 1).min(max_idx);
         }
-        ElrsCommandMsg::Back => {
-            if runtime.current_folder != 0 {
-                runtime.current_folder = runtime.folder_stack.pop().unwrap_or(0);
-                runtime.focus_first_field();
-                runtime.status_text = format!("Back to {}", runtime.current_path());
-            }
-        }
+        ElrsCommandMsg::Back => runtime.go_back(),
         ElrsCommandMsg::Refresh => {
             runtime.reset_scan(true);
             if let Err(err) = port.write_frame(&build_ping_frame()) {
@@ -1067,13 +1619,21 @@ fn handle_crsf_command(
                     runtime.status_text = "CRSF ping sent".to_string();
                 }
             }
-            VisibleEntry::FolderUp => {
-                if runtime.current_folder != 0 {
-                    runtime.current_folder = runtime.folder_stack.pop().unwrap_or(0);
-                    runtime.focus_first_field();
-                    runtime.status_text = format!("Back to {}", runtime.current_path());
-                }
+            VisibleEntry::FolderUp => runtime.go_back(),
+            VisibleEntry::OtherDevices => {
+                runtime.view = MenuView::Devices;
+                runtime.focus_first_field();
+                runtime.status_text = format!("{} devices found", runtime.devices.len());
             }
+            VisibleEntry::Device(address) => runtime.select_device(address),
+            VisibleEntry::ModelSettings => {
+                runtime.view = MenuView::ModelSettings;
+                runtime.focus_first_field();
+                runtime.status_text = format!("Settings saved with `{}`", runtime.model_name);
+            }
+            VisibleEntry::SaveToModel => save_snapshot(runtime),
+            VisibleEntry::ApplyToModule => apply_snapshot(runtime, port),
+            VisibleEntry::SnapshotDiff(_) => {}
             VisibleEntry::Field(field_id) => {
                 let Some(field) = runtime.field(field_id).cloned() else {
                     return;
@@ -1094,22 +1654,8 @@ fn handle_crsf_command(
                         ));
                         runtime.status_text = format!("Editing `{}`", field.label);
                     }
-                    CrsfFieldValue::Command { step, .. } => {
-                        let next_step = if *step == 3 { 4 } else { 1 };
-                        match port
-                            .write_frame(&build_param_write_u8_frame(field.field_id, next_step))
-                        {
-                            Ok(_) => {
-                                *bind_busy_until =
-                                    Some(Instant::now() + Duration::from_millis(500));
-                                runtime.status_text = format!("Command `{}` sent", field.label);
-                                schedule_sibling_refresh(runtime, field.parent);
-                            }
-                            Err(err) => {
-                                runtime.status_text =
-                                    format!("Command `{}` failed: {}", field.label, err);
-                            }
-                        }
+                    CrsfFieldValue::Command { timeout_10ms, .. } => {
+                        start_command(runtime, port, &field, *timeout_10ms);
                     }
                     _ => {
                         runtime.status_text = format!("Field `{}` is not an action", field.label);
@@ -1130,9 +1676,11 @@ fn handle_crsf_command(
                 return;
             };
             match next_field_value(&field, delta) {
-                Some(next) => match port
-                    .write_frame(&build_param_write_u8_frame(field.field_id, next))
-                {
+                Some((next, value)) => match port.write_frame(&build_param_write_frame(
+                    runtime.target,
+                    field.field_id,
+                    value,
+                )) {
                     Ok(_) => {
                         apply_numeric_update(runtime, field.field_id, next);
                         runtime.status_text =
@@ -1153,7 +1701,11 @@ fn handle_crsf_command(
     runtime.normalize_selection();
 }
 
-fn handle_string_edit_command(cmd: ElrsCommandMsg, runtime: &mut CrsfRuntime, port: &mut CrsfPort) {
+fn handle_string_edit_command(
+    cmd: ElrsCommandMsg,
+    runtime: &mut CrsfRuntime,
+    port: &mut dyn CrsfLink,
+) {
     match cmd {
         ElrsCommandMsg::Back => {
             if let Some(edit) = runtime.edit_state.as_ref() {
@@ -1181,7 +1733,7 @@ fn handle_string_edit_command(cmd: ElrsCommandMsg, runtime: &mut CrsfRuntime, po
                 edit.move_cursor(1);
             }
         }
-        ElrsCommandMsg::Refresh => {}
+        ElrsCommandMsg::Refresh | ElrsCommandMsg::Bind => {}
         ElrsCommandMsg::Activate => {
             let Some((field_id, label, value)) = runtime
                 .edit_state
@@ -1190,7 +1742,11 @@ fn handle_string_edit_command(cmd: ElrsCommandMsg, runtime: &mut CrsfRuntime, po
             else {
                 return;
             };
-            match port.write_frame(&build_param_write_string_frame(field_id, &value)) {
+            match port.write_frame(&build_param_write_string_frame(
+                runtime.target,
+                field_id,
+                &value,
+            )) {
                 Ok(_) => {
                     if let Some(field) = runtime.field_mut(field_id) {
                         field.value = CrsfFieldValue::String {
@@ -1209,91 +1765,219 @@ fn handle_string_edit_command(cmd: ElrsCommandMsg, runtime: &mut CrsfRuntime, po
     }
 }
 
-fn handle_crsf_frame(frame: &[u8], runtime: &mut CrsfRuntime) {
-    if frame.len() < 5 {
+fn command_poll_interval(timeout_10ms: u8) -> Duration {
+    Duration::from_millis(timeout_10ms as u64 * 10).max(COMMAND_MIN_POLL_INTERVAL)
+}
+
+fn write_command_step(
+    runtime: &mut CrsfRuntime,
+    port: &mut dyn CrsfLink,
+    field_id: u8,
+    step: u8,
+) -> bool {
+    match port.write_frame(&build_param_write_frame(
+        runtime.target,
+        field_id,
+        vec![step],
+    )) {
+        Ok(_) => true,
+        Err(err) => {
+            runtime.status_text = format!("Command write failed: {}", err);
+            false
+        }
+    }
+}
+
+/// Clicks a command field; its progress then comes back as field updates.
+fn start_command(
+    runtime: &mut CrsfRuntime,
+    port: &mut dyn CrsfLink,
+    field: &CrsfField,
+    timeout_10ms: u8,
+) {
+    if !write_command_step(runtime, port, field.field_id, CRSF_COMMAND_STEP_CLICK) {
+        return;
+    }
+    let poll_interval = command_poll_interval(timeout_10ms);
+    runtime.command = Some(CommandRun {
+        field_id: field.field_id,
+        parent: field.parent,
+        label: field.label.clone(),
+        step: CRSF_COMMAND_STEP_CLICK,
+        info: String::new(),
+        poll_interval,
+        poll_at: Instant::now() + poll_interval,
+    });
+    runtime.status_text = format!("Command `{}` started", field.label);
+    schedule_field_refresh(runtime, field.field_id);
+}
+
+/// Enter confirms when the device asks, Back cancels; the menu stays
+/// locked while a command runs.
+fn handle_command_run_input(
+    cmd: ElrsCommandMsg,
+    runtime: &mut CrsfRuntime,
+    port: &mut dyn CrsfLink,
+) {
+    let Some(run) = runtime.command.clone() else {
         return;
+    };
+    match cmd {
+        ElrsCommandMsg::Activate if run.awaiting_confirm() => {
+            if write_command_step(runtime, port, run.field_id, CRSF_COMMAND_STEP_CONFIRMED) {
+                if let Some(run) = runtime.command.as_mut() {
+                    run.step = CRSF_COMMAND_STEP_CONFIRMED;
+                    run.poll_at = Instant::now() + run.poll_interval;
+                }
+                runtime.status_text = format!("Confirmed `{}`", run.label);
+                push_pending_read_front(runtime, run.field_id);
+            }
+        }
+        ElrsCommandMsg::Back => {
+            if write_command_step(runtime, port, run.field_id, CRSF_COMMAND_STEP_CANCEL) {
+                runtime.command = None;
+                runtime.status_text = format!("Canceled `{}`", run.label);
+                schedule_field_refresh(runtime, run.field_id);
+            }
+        }
+        _ => {
+            runtime.status_text = format!("`{}` is running, Back to cancel", run.label);
+        }
     }
+}
 
-    match frame[2] {
-        CRSF_FRAME_DEVICE_INFO => parse_device_info(frame, runtime),
-        CRSF_FRAME_ELRS_INFO => parse_elrs_info(frame, runtime),
-        CRSF_FRAME_PARAM_ENTRY => parse_param_entry(frame, runtime),
+/// Applies a re-read of the running command's field.
+fn update_command_run(
+    runtime: &mut CrsfRuntime,
+    field_id: u8,
+    step: u8,
+    timeout_10ms: u8,
+    info: &str,
+) {
+    let Some(run) = runtime
+        .command
+        .as_mut()
+        .filter(|run| run.field_id == field_id)
+    else {
+        return;
+    };
+    run.step = step;
+    run.info = info.to_string();
+    run.poll_interval = command_poll_interval(timeout_10ms);
+    run.poll_at = Instant::now() + run.poll_interval;
+    let label = run.label.clone();
+    let parent = run.parent;
+
+    match step {
+        CRSF_COMMAND_STEP_IDLE => {
+            runtime.command = None;
+            runtime.status_text = if info.is_empty() {
+                format!("Command `{}` done", label)
+            } else {
+                format!("`{}`: {}", label, info)
+            };
+            // Commands like WiFi or bind change other fields.
+            schedule_sibling_refresh(runtime, parent);
+        }
+        CRSF_COMMAND_STEP_ASK_CONFIRM => {
+            runtime.status_text = format!("Confirm `{}`? Enter: yes  Back: no", label);
+        }
+        _ if !info.is_empty() => runtime.status_text = format!("`{}`: {}", label, info),
         _ => {}
     }
 }
 
-fn parse_device_info(frame: &[u8], runtime: &mut CrsfRuntime) {
-    if frame.len() < 8 || frame.get(4).copied() != Some(CRSF_ADDRESS_MODULE) {
+/// Polls the running command at the device's timeout: QUERY while it
+/// executes, a plain re-read while a click or confirm is unanswered.
+fn drive_command_run(runtime: &mut CrsfRuntime, port: &mut dyn CrsfLink) {
+    let now = Instant::now();
+    let Some(run) = runtime.command.as_mut() else {
+        return;
+    };
+    if run.awaiting_confirm() || now < run.poll_at {
         return;
     }
+    run.poll_at = now + run.poll_interval;
+    let (field_id, step) = (run.field_id, run.step);
 
-    let payload_len = frame[1] as usize;
-    if payload_len < 18 || frame.len() < payload_len + 2 {
+    if step == CRSF_COMMAND_STEP_EXECUTING
+        && !write_command_step(runtime, port, field_id, CRSF_COMMAND_STEP_QUERY)
+    {
         return;
     }
+    push_pending_read_front(runtime, field_id);
+}
+
+fn handle_crsf_frame(frame: &[u8], runtime: &mut CrsfRuntime) {
+    match Frame::decode(frame) {
+        Some((_, Frame::DeviceInfo(info))) => parse_device_info(&info, runtime),
+        Some((_, Frame::ElrsStatus(status))) => parse_elrs_info(&status, runtime),
+        Some((_, Frame::ParamEntry(entry))) => parse_param_entry(entry, runtime),
+        _ => {}
+    }
+}
 
-    let name_end = frame[5..frame.len().saturating_sub(4)]
-        .iter()
-        .position(|byte| *byte == 0)
-        .map(|idx| 5 + idx)
-        .unwrap_or(5);
-    let name = String::from_utf8_lossy(&frame[5..name_end])
-        .trim()
-        .to_string();
-    let name_size = payload_len.saturating_sub(18);
-    let version_idx = 5 + name_size + 9;
-    if version_idx + 2 < frame.len() {
-        runtime.version = format!(
-            "{}.{}.{}",
-            frame[version_idx],
-            frame[version_idx + 1],
-            frame[version_idx + 2]
-        );
+fn parse_device_info(info: &DeviceInfo, runtime: &mut CrsfRuntime) {
+    let name = match info.name.trim() {
+        "" => format!("Device 0x{:02X}", info.origin),
+        name => name.to_string(),
+    };
+    let device = CrsfDevice {
+        address: info.origin,
+        name: name.clone(),
+        version: info.firmware_version(),
+        last_seen: Instant::now(),
+    };
+    match runtime
+        .devices
+        .iter_mut()
+        .find(|entry| entry.address == info.origin)
+    {
+        Some(entry) => *entry = device,
+        None => {
+            runtime.devices.push(device);
+            // The TX module leads the list, as in the EdgeTX ELRS script.
+            runtime
+                .devices
+                .sort_by_key(|entry| entry.address != crsf::ADDRESS_MODULE);
+        }
     }
-    if !name.is_empty() {
+    if info.origin == crsf::ADDRESS_MODULE {
         runtime.module_name = name.clone();
-        runtime.device_name = name;
     }
+    if info.origin != runtime.target {
+        return;
+    }
+
+    runtime.version = info.firmware_version();
+    runtime.device_name = name;
     runtime.connected = true;
     runtime.last_info_at = Some(Instant::now());
-    runtime.status_text = format!(
-        "ELRS device detected on {} @ {}",
-        runtime.dev_name, runtime.baudrate
-    );
+    runtime.status_text = format!("ELRS device detected on {}", runtime.link_name);
     if runtime.fields.is_empty() {
         runtime.reset_scan(false);
     }
 }
 
-fn parse_elrs_info(frame: &[u8], runtime: &mut CrsfRuntime) {
-    if frame.len() < 8 {
-        return;
-    }
-
+fn parse_elrs_info(status: &ElrsStatus, runtime: &mut CrsfRuntime) {
     runtime.connected = true;
     runtime.last_info_at = Some(Instant::now());
 
-    let bad_packets = frame.get(4).copied().unwrap_or(0);
-    let good_packets = frame
-        .get(5..7)
-        .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]))
-        .unwrap_or(0);
-    let flags = frame.get(7).copied().unwrap_or(0);
-    runtime.wifi_running = flags & 0x01 != 0;
+    runtime.wifi_running = status.flags & 0x01 != 0;
     runtime.status_text = format!(
         "ELRS info good={} bad={} flags=0x{:02x}",
-        good_packets, bad_packets, flags
+        status.good_packets, status.bad_packets, status.flags
     );
 }
 
-fn parse_param_entry(frame: &[u8], runtime: &mut CrsfRuntime) {
-    if frame.len() < 8 || frame[3] != CRSF_ADDRESS_RADIO {
+fn parse_param_entry(entry: ParamEntry, runtime: &mut CrsfRuntime) {
+    if entry.dest != crsf::ADDRESS_RADIO || entry.origin != runtime.target {
         return;
     }
 
-    let field_id = frame[5];
-    let chunks_remaining = frame[6];
-    let continuation = frame[7..frame.len() - 1].to_vec();
+    let field_id = entry.field_id;
+    let chunks_remaining = entry.chunks_remaining;
+    let continuation = entry.data;
 
     match &mut runtime.pending_chunks {
         Some(pending) if pending.field_id == field_id => {
@@ -1335,6 +2019,14 @@ fn parse_param_entry(frame: &[u8], runtime: &mut CrsfRuntime) {
 
 fn finish_field_parse(runtime: &mut CrsfRuntime, field_id: u8, bytes: &[u8]) {
     match parse_field_bytes(field_id, bytes) {
+        Some(CrsfField {
+            value: CrsfFieldValue::OutOfRange,
+            ..
+        }) => {
+            // Nothing lives at or past this id.
+            runtime.fields.retain(|field| field.field_id < field_id);
+            runtime.discovery_complete = true;
+        }
         Some(field) => {
             runtime.discovery_miss_streak = 0;
             runtime.discovery_complete = false;
@@ -1361,24 +2053,36 @@ fn parse_field_bytes(field_id: u8, bytes: &[u8]) -> Option<CrsfField> {
     let hidden = bytes[1] & CRSF_PARAM_HIDDEN_MASK != 0;
     let field_type = bytes[1] & !CRSF_PARAM_HIDDEN_MASK;
     let mut idx = 2usize;
-    let label = read_c_string(bytes, &mut idx)?;
+    let label = match field_type {
+        // Carries no label on some devices.
+        CRSF_PARAM_TYPE_OUT_OF_RANGE => read_c_string(bytes, &mut idx).unwrap_or_default(),
+        _ => read_c_string(bytes, &mut idx)?,
+    };
 
     let value = match field_type {
-        CRSF_PARAM_TYPE_UINT8 => {
-            if idx + 3 >= bytes.len() {
-                return None;
-            }
-            let current = bytes[idx];
-            let min = bytes[idx + 1];
-            let max = bytes[idx + 2];
-            let default = bytes[idx + 3];
-            idx += 4;
+        CRSF_PARAM_TYPE_UINT8..=CRSF_PARAM_TYPE_INT32 | CRSF_PARAM_TYPE_FLOAT => {
+            let kind = NumberKind::from_type(field_type)?;
+            let current = kind.read(bytes, &mut idx)?;
+            let min = kind.read(bytes, &mut idx)?;
+            let max = kind.read(bytes, &mut idx)?;
+            let default = kind.read(bytes, &mut idx)?;
+            let (precision, step) = if kind == NumberKind::Float {
+                let precision = *bytes.get(idx)?;
+                idx += 1;
+                let step = NumberKind::Uint32.read(bytes, &mut idx)? as u32;
+                (precision.min(CRSF_FLOAT_MAX_PRECISION), step.max(1))
+            } else {
+                (0, 1)
+            };
             let unit = read_c_string(bytes, &mut idx).unwrap_or_default();
-            CrsfFieldValue::Uint8 {
+            CrsfFieldValue::Number {
+                kind,
                 current,
                 min,
                 max,
                 default,
+                precision,
+                step,
                 unit,
             }
         }
@@ -1425,6 +2129,7 @@ fn parse_field_bytes(field_id: u8, bytes: &[u8]) -> Option<CrsfField> {
                 info,
             }
         }
+        CRSF_PARAM_TYPE_OUT_OF_RANGE => CrsfFieldValue::OutOfRange,
         raw_type => CrsfFieldValue::Unknown {
             raw_type,
             bytes: bytes[idx..].to_vec(),
@@ -1448,6 +2153,15 @@ fn upsert_field(runtime: &mut CrsfRuntime, field: CrsfField) {
         "tx power" | "max power" => runtime.tx_power = field.display_value(),
         _ => {}
     }
+    if let CrsfFieldValue::Command {
+        step,
+        timeout_10ms,
+        info,
+    } = &field.value
+    {
+        let (step, timeout_10ms, info) = (*step, *timeout_10ms, info.clone());
+        update_command_run(runtime, field.field_id, step, timeout_10ms, &info);
+    }
 
     if let Some(existing) = runtime
         .fields
@@ -1462,16 +2176,18 @@ fn upsert_field(runtime: &mut CrsfRuntime, field: CrsfField) {
     runtime.normalize_selection();
 }
 
-fn drive_param_discovery(runtime: &mut CrsfRuntime, port: &mut CrsfPort) {
+fn drive_param_discovery(runtime: &mut CrsfRuntime, port: &mut dyn CrsfLink) {
     if let Some(active) = &runtime.active_read {
         if active.requested_at.elapsed() < Duration::from_millis(250) {
             return;
         }
 
         if active.chunk > 0 {
-            if let Err(err) =
-                port.write_frame(&build_param_read_frame(active.field_id, active.chunk))
-            {
+            if let Err(err) = port.write_frame(&build_param_read_frame(
+                runtime.target,
+                active.field_id,
+                active.chunk,
+            )) {
                 runtime.status_text = format!("Chunk read failed: {}", err);
             } else {
                 runtime.active_read = Some(PendingRead {
@@ -1508,7 +2224,7 @@ fn drive_param_discovery(runtime: &mut CrsfRuntime, port: &mut CrsfPort) {
     };
 
     if let Some(field_id) = next_field_id {
-        if let Err(err) = port.write_frame(&build_param_read_frame(field_id, 0)) {
+        if let Err(err) = port.write_frame(&build_param_read_frame(runtime.target, field_id, 0)) {
             runtime.status_text = format!("Parameter read failed: {}", err);
         } else {
             runtime.active_read = Some(PendingRead {
@@ -1571,23 +2287,79 @@ fn push_pending_read_front(runtime: &mut CrsfRuntime, field_id: u8) {
     runtime.pending_reads.insert(0, field_id);
 }
 
-fn next_field_value(field: &CrsfField, delta: i16) -> Option<u8> {
-    match &field.value {
-        CrsfFieldValue::Uint8 {
-            current, min, max, ..
-        } => Some((*current as i16 + delta).clamp(*min as i16, *max as i16) as u8),
+/// Value one step up or down, with the `0x2D` payload that writes it.
+fn next_field_value(field: &CrsfField, delta: i64) -> Option<(i64, Vec<u8>)> {
+    let next = match &field.value {
+        CrsfFieldValue::Number {
+            current,
+            min,
+            max,
+            step,
+            ..
+        } => (current + delta * *step as i64).clamp(*min, *max),
         CrsfFieldValue::TextSelection {
             current, min, max, ..
-        } => Some((*current as i16 + delta).clamp(*min as i16, *max as i16) as u8),
+        } => (*current as i64 + delta).clamp(*min as i64, *max as i64),
+        _ => return None,
+    };
+    Some((next, encode_field_value(field, next)?))
+}
+
+/// The `0x2D` payload that sets `field` to `value`.
+fn encode_field_value(field: &CrsfField, value: i64) -> Option<Vec<u8>> {
+    match &field.value {
+        CrsfFieldValue::Number { kind, .. } => Some(kind.encode(value)),
+        CrsfFieldValue::TextSelection { .. } => Some(vec![value as u8]),
         _ => None,
     }
 }
 
-fn apply_numeric_update(runtime: &mut CrsfRuntime, field_id: u8, next: u8) {
+/// Stores the module's current settings with the active model.
+fn save_snapshot(runtime: &mut CrsfRuntime) {
+    let params = runtime.snapshot_values();
+    let count = params.len();
+    match store::save_elrs_params(&runtime.model_id, params.clone()) {
+        Ok(()) => {
+            runtime.snapshot = params;
+            runtime.status_text = format!("Saved {} settings to `{}`", count, runtime.model_name);
+        }
+        Err(err) => {
+            runtime.status_text = format!("Save to `{}` failed: {}", runtime.model_name, err);
+        }
+    }
+}
+
+/// Writes the model's saved settings that differ back to the module.
+fn apply_snapshot(runtime: &mut CrsfRuntime, port: &mut dyn CrsfLink) {
+    let mut applied = 0;
+    for diff in runtime.snapshot_diff() {
+        let Some(field) = runtime.field(diff.field_id).cloned() else {
+            continue;
+        };
+        let Some(value) = encode_field_value(&field, diff.value) else {
+            continue;
+        };
+        if let Err(err) = port.write_frame(&build_param_write_frame(
+            runtime.target,
+            field.field_id,
+            value,
+        )) {
+            runtime.status_text = format!("Apply `{}` failed: {}", diff.key, err);
+            return;
+        }
+        apply_numeric_update(runtime, field.field_id, diff.value);
+        // Packet rate and friends change what other fields offer.
+        schedule_field_refresh(runtime, field.field_id);
+        applied += 1;
+    }
+    runtime.status_text = format!("Applied {} settings from `{}`", applied, runtime.model_name);
+}
+
+fn apply_numeric_update(runtime: &mut CrsfRuntime, field_id: u8, next: i64) {
     if let Some(field) = runtime.field_mut(field_id) {
         match &mut field.value {
-            CrsfFieldValue::Uint8 { current, .. } => *current = next,
-            CrsfFieldValue::TextSelection { current, .. } => *current = next,
+            CrsfFieldValue::Number { current, .. } => *current = next,
+            CrsfFieldValue::TextSelection { current, .. } => *current = next as u8,
             _ => {}
         }
         let label_lower = field.label.to_ascii_lowercase();
@@ -1600,6 +2372,22 @@ fn apply_numeric_update(runtime: &mut CrsfRuntime, field_id: u8, next: u8) {
     }
 }
 
+/// `value` in units of `10^-precision`, e.g. 1234 at precision 2 is "12.34".
+fn format_number(value: i64, precision: u8) -> String {
+    if precision == 0 {
+        return value.to_string();
+    }
+    let scale = 10u64.pow(precision as u32);
+    let sign = if value < 0 { "-" } else { "" };
+    let abs = value.unsigned_abs();
+    format!(
+        "{sign}{}.{:0width$}",
+        abs / scale,
+        abs % scale,
+        width = precision as usize
+    )
+}
+
 fn read_c_string(bytes: &[u8], idx: &mut usize) -> Option<String> {
     let start = *idx;
     let end = bytes.get(start..)?.iter().position(|byte| *byte == 0)? + start;
@@ -1607,124 +2395,64 @@ fn read_c_string(bytes: &[u8], idx: &mut usize) -> Option<String> {
     Some(String::from_utf8_lossy(&bytes[start..end]).to_string())
 }
 
-fn extract_crsf_frames(buf: &mut Vec<u8>) -> Vec<Vec<u8>> {
-    let mut frames = Vec::new();
-    let mut cursor = 0usize;
-
-    while buf.len().saturating_sub(cursor) >= 3 {
-        if !matches!(
-            buf[cursor],
-            CRSF_ADDRESS_RADIO | CRSF_SYNC | CRSF_ADDRESS_MODULE
-        ) {
-            cursor += 1;
-            continue;
-        }
-
-        let packet_len = buf[cursor + 1] as usize + 2;
-        if packet_len < 5 || packet_len > CRSF_MAX_PACKET_SIZE {
-            cursor += 1;
-            continue;
-        }
-
-        if cursor + packet_len > buf.len() {
-            break;
-        }
-
-        let frame = &buf[cursor..cursor + packet_len];
-        if check_frame_crc(frame) {
-            frames.push(frame.to_vec());
-            cursor += packet_len;
-        } else {
-            cursor += 1;
-        }
-    }
-
-    if cursor > 0 {
-        buf.drain(..cursor);
-    }
-    frames
-}
-
-fn check_frame_crc(frame: &[u8]) -> bool {
-    if frame.len() < 5 {
-        return false;
-    }
-    let len = frame[1] as usize;
-    let expected = *frame.last().unwrap_or(&0);
-    CRSF_CRC.checksum(&frame[2..2 + len - 1]) == expected
-}
-
 fn build_ping_frame() -> Vec<u8> {
-    build_crsf_frame(
-        CRSF_SYNC,
-        CRSF_FRAME_PING_DEVICES,
-        &[CRSF_ADDRESS_BROADCAST, CRSF_ADDRESS_RADIO],
-    )
+    Frame::DevicePing {
+        dest: crsf::ADDRESS_BROADCAST,
+        origin: crsf::ADDRESS_RADIO,
+    }
+    .encode(crsf::ADDRESS_SYNC)
 }
 
 fn build_bind_frame(telemetry_streaming: bool) -> Vec<u8> {
-    let destination = if telemetry_streaming {
-        CRSF_ADDRESS_RECEIVER
+    let dest = if telemetry_streaming {
+        crsf::ADDRESS_RECEIVER
     } else {
-        CRSF_ADDRESS_MODULE
+        crsf::ADDRESS_MODULE
     };
-    build_crsf_command_frame(
-        CRSF_SYNC,
-        &[
-            destination,
-            CRSF_ADDRESS_RADIO,
-            CRSF_SUBCMD_CRSF,
-            CRSF_SUBCMD_BIND,
-        ],
-    )
-}
-
-fn build_param_read_frame(field_id: u8, chunk: u8) -> Vec<u8> {
-    build_crsf_frame(
-        CRSF_SYNC,
-        CRSF_FRAME_PARAM_READ,
-        &[CRSF_ADDRESS_MODULE, CRSF_ADDRESS_HANDSET, field_id, chunk],
-    )
-}
-
-fn build_param_write_u8_frame(field_id: u8, value: u8) -> Vec<u8> {
-    build_crsf_frame(
-        CRSF_SYNC,
-        CRSF_FRAME_PARAM_WRITE,
-        &[CRSF_ADDRESS_MODULE, CRSF_ADDRESS_HANDSET, field_id, value],
-    )
+    Frame::Command(Command {
+        dest,
+        origin: crsf::ADDRESS_RADIO,
+        command: crsf::COMMAND_CRSF,
+        payload: vec![crsf::SUBCOMMAND_BIND],
+    })
+    .encode(crsf::ADDRESS_SYNC)
 }
 
-fn build_param_write_string_frame(field_id: u8, value: &str) -> Vec<u8> {
-    let mut payload = vec![CRSF_ADDRESS_MODULE, CRSF_ADDRESS_HANDSET, field_id];
-    payload.extend_from_slice(value.as_bytes());
-    payload.push(0);
-    build_crsf_frame(CRSF_SYNC, CRSF_FRAME_PARAM_WRITE, &payload)
+fn build_param_read_frame(dest: u8, field_id: u8, chunk: u8) -> Vec<u8> {
+    Frame::ParamRead {
+        dest,
+        origin: crsf::ADDRESS_HANDSET,
+        field_id,
+        chunk,
+    }
+    .encode(crsf::ADDRESS_SYNC)
 }
 
-fn build_crsf_frame(address: u8, frame_type: u8, payload: &[u8]) -> Vec<u8> {
-    let mut out = Vec::with_capacity(payload.len() + 4);
-    out.push(address);
-    out.push((payload.len() + 2) as u8);
-    out.push(frame_type);
-    out.extend_from_slice(payload);
-    out.push(CRSF_CRC.checksum(&out[2..]));
-    out
+fn build_param_write_string_frame(dest: u8, field_id: u8, value: &str) -> Vec<u8> {
+    let mut bytes = value.as_bytes().to_vec();
+    bytes.push(0);
+    build_param_write_frame(dest, field_id, bytes)
 }
 
-fn build_crsf_command_frame(address: u8, payload_without_crc: &[u8]) -> Vec<u8> {
-    let mut payload = Vec::with_capacity(payload_without_crc.len() + 1);
-    payload.extend_from_slice(payload_without_crc);
-    payload.push(crc8_ba(payload_without_crc));
-    build_crsf_frame(address, CRSF_FRAME_COMMAND, &payload)
+fn build_param_write_frame(dest: u8, field_id: u8, value: Vec<u8>) -> Vec<u8> {
+    Frame::ParamWrite {
+        dest,
+        origin: crsf::ADDRESS_HANDSET,
+        field_id,
+        value,
+    }
+    .encode(crsf::ADDRESS_SYNC)
 }
 
-fn crc8_ba(data: &[u8]) -> u8 {
-    let mut crc = 0u8;
-    for byte in data {
-        crc = CRC8_BA_TABLE[(crc ^ byte) as usize];
+fn rc_rate_text(rate: &CrsfRateMsg) -> String {
+    if rate.synced {
+        format!(
+            "{}/{} Hz ±{}us",
+            rate.achieved_hz, rate.target_hz, rate.jitter_us
+        )
+    } else {
+        format!("{} Hz ±{}us, no sync", rate.achieved_hz, rate.jitter_us)
     }
-    crc
 }
 
 fn elrs_debug_enabled() -> bool {
@@ -1745,54 +2473,80 @@ fn hex_bytes(bytes: &[u8]) -> String {
         .join(" ")
 }
 
-const CRC8_BA_TABLE: [u8; 256] = [
-    0x00, 0xBA, 0xCE, 0x74, 0x26, 0x9C, 0xE8, 0x52, 0x4C, 0xF6, 0x82, 0x38, 0x6A, 0xD0, 0xA4, 0x1E,
-    0x98, 0x22, 0x56, 0xEC, 0xBE, 0x04, 0x70, 0xCA, 0xD4, 0x6E, 0x1A, 0xA0, 0xF2, 0x48, 0x3C, 0x86,
-    0x8A, 0x30, 0x44, 0xFE, 0xAC, 0x16, 0x62, 0xD8, 0xC6, 0x7C, 0x08, 0xB2, 0xE0, 0x5A, 0x2E, 0x94,
-    0x12, 0xA8, 0xDC, 0x66, 0x34, 0x8E, 0xFA, 0x40, 0x5E, 0xE4, 0x90, 0x2A, 0x78, 0xC2, 0xB6, 0x0C,
-    0xAE, 0x14, 0x60, 0xDA, 0x88, 0x32, 0x46, 0xFC, 0xE2, 0x58, 0x2C, 0x96, 0xC4, 0x7E, 0x0A, 0xB0,
-    0x36, 0x8C, 0xF8, 0x42, 0x10, 0xAA, 0xDE, 0x64, 0x7A, 0xC0, 0xB4, 0x0E, 0x5C, 0xE6, 0x92, 0x28,
-    0x24, 0x9E, 0xEA, 0x50, 0x02, 0xB8, 0xCC, 0x76, 0x68, 0xD2, 0xA6, 0x1C, 0x4E, 0xF4, 0x80, 0x3A,
-    0xBC, 0x06, 0x72, 0xC8, 0x9A, 0x20, 0x54, 0xEE, 0xF0, 0x4A, 0x3E, 0x84, 0xD6, 0x6C, 0x18, 0xA2,
-    0xE6, 0x5C, 0x28, 0x92, 0xC0, 0x7A, 0x0E, 0xB4, 0xAA, 0x10, 0x64, 0xDE, 0x8C, 0x36, 0x42, 0xF8,
-    0x7E, 0xC4, 0xB0, 0x0A, 0x58, 0xE2, 0x96, 0x2C, 0x32, 0x88, 0xFC, 0x46, 0x14, 0xAE, 0xDA, 0x60,
-    0x6C, 0xD6, 0xA2, 0x18, 0x4A, 0xF0, 0x84, 0x3E, 0x20, 0x9A, 0xEE, 0x54, 0x06, 0xBC, 0xC8, 0x72,
-    0xF4, 0x4E, 0x3A, 0x80, 0xD2, 0x68, 0x1C, 0xA6, 0xB8, 0x02, 0x76, 0xCC, 0x9E, 0x24, 0x50, 0xEA,
-    0x48, 0xF2, 0x86, 0x3C, 0x6E, 0xD4, 0xA0, 0x1A, 0x04, 0xBE, 0xCA, 0x70, 0x22, 0x98, 0xEC, 0x56,
-    0xD0, 0x6A, 0x1E, 0xA4, 0xF6, 0x4C, 0x38, 0x82, 0x9C, 0x26, 0x52, 0xE8, 0xBA, 0x00, 0x74, 0xCE,
-    0xC2, 0x78, 0x0C, 0xB6, 0xE4, 0x5E, 0x2A, 0x90, 0x8E, 0x34, 0x40, 0xFA, 0xA8, 0x12, 0x66, 0xDC,
-    0x5A, 0xE0, 0x94, 0x2E, 0x7C, 0xC6, 0xB2, 0x08, 0x16, 0xAC, 0xD8, 0x62, 0x30, 0x8A, 0xFE, 0x44,
-];
-
 #[cfg(test)]
 mod tests {
+    use std::time::Instant;
+
     use super::{
-        build_bind_frame, build_param_write_string_frame, build_ping_frame, check_frame_crc,
-        extract_crsf_frames, StringEditState, CRSF_FRAME_COMMAND, CRSF_FRAME_PING_DEVICES,
+        build_bind_frame, build_param_write_string_frame, build_ping_frame, drive_command_run,
+        drive_param_discovery, handle_crsf_command, next_field_value, parse_device_info,
+        parse_field_bytes, parse_param_entry, start_command, upsert_field, CrsfField,
+        CrsfFieldValue, CrsfLink, CrsfRuntime, MenuView, StringEditState, VisibleEntry,
+    };
+    use crate::{
+        config::ElrsParamValue,
+        crsf::{
+            check_crc, DeviceInfo, Frame, ParamEntry, ADDRESS_MODULE, ADDRESS_RADIO,
+            ADDRESS_RECEIVER, ADDRESS_SYNC, FRAME_COMMAND, FRAME_PING_DEVICES,
+        },
+        messages::ElrsCommandMsg,
     };
 
+    #[derive(Default)]
+    struct RecordingLink {
+        written: Vec<Vec<u8>>,
+    }
+
+    impl CrsfLink for RecordingLink {
+        fn write_frame(&mut self, frame: &[u8]) -> std::io::Result<()> {
+            self.written.push(frame.to_vec());
+            Ok(())
+        }
+
+        fn poll_frames(&mut self) -> std::io::Result<Vec<Vec<u8>>> {
+            Ok(Vec::new())
+        }
+    }
+
+    impl RecordingLink {
+        /// Command steps written so far, clearing the log.
+        fn take_steps(&mut self) -> Vec<u8> {
+            self.written
+                .drain(..)
+                .filter_map(|frame| match Frame::decode(&frame) {
+                    Some((_, Frame::ParamWrite { value, .. })) => Some(value[0]),
+                    _ => None,
+                })
+                .collect()
+        }
+    }
+
+    fn command_field(step: u8, info: &str) -> CrsfField {
+        parse_field_bytes(3, &field_bytes(0x0D, "Bind", &[step, 20], info)).unwrap()
+    }
+
+    fn field_bytes(field_type: u8, label: &str, values: &[u8], unit: &str) -> Vec<u8> {
+        let mut bytes = vec![0, field_type];
+        bytes.extend_from_slice(label.as_bytes());
+        bytes.push(0);
+        bytes.extend_from_slice(values);
+        bytes.extend_from_slice(unit.as_bytes());
+        bytes.push(0);
+        bytes
+    }
+
     #[test]
     fn test_ping_frame_crc() {
         let frame = build_ping_frame();
-        assert_eq!(frame[2], CRSF_FRAME_PING_DEVICES);
-        assert!(check_frame_crc(&frame));
+        assert_eq!(frame[2], FRAME_PING_DEVICES);
+        assert!(check_crc(&frame));
     }
 
     #[test]
     fn test_bind_frame_crc() {
         let frame = build_bind_frame(false);
-        assert_eq!(frame[2], CRSF_FRAME_COMMAND);
-        assert!(check_frame_crc(&frame));
-    }
-
-    #[test]
-    fn test_extract_crsf_frames_skips_invalid_bytes() {
-        let mut buf = vec![0x00, 0x01];
-        let ping = build_ping_frame();
-        buf.extend_from_slice(&ping);
-        let frames = extract_crsf_frames(&mut buf);
-        assert_eq!(frames.len(), 1);
-        assert!(buf.is_empty());
+        assert_eq!(frame[2], FRAME_COMMAND);
+        assert!(check_crc(&frame));
     }
 
     #[test]
@@ -1803,10 +2557,294 @@ mod tests {
         assert_eq!(edit.buffer_string(), "ac");
     }
 
+    #[test]
+    fn test_signed_int_field_writes_two_bytes() {
+        // INT16: current -5, min -100, max 100, default 0.
+        let bytes = field_bytes(
+            0x03,
+            "Trim",
+            &[0xff, 0xfb, 0xff, 0x9c, 0x00, 0x64, 0x00, 0x00],
+            "us",
+        );
+        let field = parse_field_bytes(4, &bytes).unwrap();
+        assert_eq!(field.display_value(), "-5 us");
+        assert!(field.is_selectable());
+        assert_eq!(next_field_value(&field, 1), Some((-4, vec![0xff, 0xfc])));
+        assert_eq!(
+            next_field_value(&field, -200),
+            Some((-100, vec![0xff, 0x9c]))
+        );
+    }
+
+    #[test]
+    fn test_float_field_uses_precision_and_step() {
+        // 12.50 in 0.25 steps between 0 and 50.
+        let mut values = Vec::new();
+        for value in [1250i32, 0, 5000, 1000] {
+            values.extend_from_slice(&value.to_be_bytes());
+        }
+        values.push(2);
+        values.extend_from_slice(&25u32.to_be_bytes());
+        let field = parse_field_bytes(5, &field_bytes(0x08, "Gain", &values, "x")).unwrap();
+        assert_eq!(field.display_value(), "12.50 x");
+        assert_eq!(
+            next_field_value(&field, -1),
+            Some((1225, 1225i32.to_be_bytes().to_vec()))
+        );
+    }
+
+    #[test]
+    fn test_uint32_and_out_of_range_fields() {
+        let bytes = field_bytes(
+            0x04,
+            "Baud",
+            &[
+                0x00, 0x0e, 0x10, 0x00, 0, 0, 0, 0, 0xff, 0xff, 0xff, 0xff, 0, 0, 0, 0,
+            ],
+            "",
+        );
+        let field = parse_field_bytes(6, &bytes).unwrap();
+        assert_eq!(field.display_value(), "921600");
+        assert_eq!(
+            next_field_value(&field, 1),
+            Some((921601, vec![0x00, 0x0e, 0x10, 0x01]))
+        );
+
+        // Truncated values are rejected rather than read as zero.
+        assert!(parse_field_bytes(6, &bytes[..bytes.len() - 4]).is_none());
+
+        let field = parse_field_bytes(7, &[0, 0x7f]).unwrap();
+        assert!(matches!(field.value, CrsfFieldValue::OutOfRange));
+        assert!(!field.is_selectable());
+    }
+
+    #[test]
+    fn test_command_confirms_and_polls_until_idle() {
+        let mut runtime = CrsfRuntime::new("test".to_string());
+        let mut link = RecordingLink::default();
+        start_command(&mut runtime, &mut link, &command_field(0, ""), 20);
+        assert_eq!(link.take_steps(), vec![1]);
+
+        upsert_field(&mut runtime, command_field(3, "Sure?"));
+        let state = runtime.state(false);
+        assert!(state.command_active && state.command_confirm && !state.busy);
+        assert!(!state.can_leave);
+
+        // Other keys do not reach the menu while the device asks.
+        handle_crsf_command(
+            ElrsCommandMsg::SelectNext,
+            &mut runtime,
+            &mut link,
+            &mut None,
+        );
+        assert!(link.take_steps().is_empty());
+        handle_crsf_command(ElrsCommandMsg::Activate, &mut runtime, &mut link, &mut None);
+        assert_eq!(link.take_steps(), vec![4]);
+
+        upsert_field(&mut runtime, command_field(2, "Binding..."));
+        let state = runtime.state(false);
+        assert!(state.busy && !state.command_confirm);
+        assert_eq!(state.command_info, "Binding...");
+        drive_command_run(&mut runtime, &mut link);
+        assert!(link.take_steps().is_empty());
+        runtime.command.as_mut().unwrap().poll_at = Instant::now();
+        drive_command_run(&mut runtime, &mut link);
+        assert_eq!(link.take_steps(), vec![6]);
+
+        upsert_field(&mut runtime, command_field(0, ""));
+        assert!(runtime.command.is_none());
+        assert!(runtime.state(false).can_leave);
+    }
+
+    #[test]
+    fn test_command_back_cancels() {
+        let mut runtime = CrsfRuntime::new("test".to_string());
+        let mut link = RecordingLink::default();
+        start_command(&mut runtime, &mut link, &command_field(0, ""), 0);
+        upsert_field(&mut runtime, command_field(2, "Working"));
+        handle_crsf_command(ElrsCommandMsg::Back, &mut runtime, &mut link, &mut None);
+        assert_eq!(link.take_steps(), vec![1, 5]);
+        assert!(runtime.command.is_none());
+        assert!(matches!(
+            runtime.field(3).map(|field| &field.value),
+            Some(CrsfFieldValue::Command { step: 2, .. })
+        ));
+    }
+
+    fn device_info(origin: u8, name: &str) -> DeviceInfo {
+        DeviceInfo {
+            dest: ADDRESS_RADIO,
+            origin,
+            name: name.to_string(),
+            ..DeviceInfo::default()
+        }
+    }
+
+    fn param_entry(origin: u8) -> ParamEntry {
+        ParamEntry {
+            dest: ADDRESS_RADIO,
+            origin,
+            field_id: 1,
+            chunks_remaining: 0,
+            data: field_bytes(0x00, "Rate", &[1, 0, 3, 0], ""),
+        }
+    }
+
+    #[test]
+    fn test_other_devices_switch_parameter_target() {
+        let mut runtime = CrsfRuntime::new("test".to_string());
+        let mut link = RecordingLink::default();
+        parse_device_info(&device_info(ADDRESS_MODULE, "TX"), &mut runtime);
+        assert!(!runtime
+            .visible_entries()
+            .contains(&VisibleEntry::OtherDevices));
+
+        parse_device_info(&device_info(ADDRESS_RECEIVER, "RX"), &mut runtime);
+        parse_device_info(&device_info(ADDRESS_SYNC, "Betaflight"), &mut runtime);
+        let entries = runtime.visible_entries();
+        assert_eq!(entries.last(), Some(&VisibleEntry::OtherDevices));
+        runtime.selected_idx = entries.len() - 1;
+        handle_crsf_command(ElrsCommandMsg::Activate, &mut runtime, &mut link, &mut None);
+        assert_eq!(runtime.state(false).path, "/Other Devices");
+        assert_eq!(
+            runtime.visible_entries(),
+            vec![
+                VisibleEntry::Refresh,
+                VisibleEntry::FolderUp,
+                VisibleEntry::Device(ADDRESS_MODULE),
+                VisibleEntry::Device(ADDRESS_RECEIVER),
+                VisibleEntry::Device(ADDRESS_SYNC),
+            ]
+        );
+
+        runtime.selected_idx = 3;
+        handle_crsf_command(ElrsCommandMsg::Activate, &mut runtime, &mut link, &mut None);
+        let state = runtime.state(false);
+        assert_eq!(
+            (state.device_name.as_str(), state.path.as_str()),
+            ("RX", "/")
+        );
+
+        // Only the browsed device's parameters are taken, and reads go to it.
+        parse_param_entry(param_entry(ADDRESS_MODULE), &mut runtime);
+        assert!(runtime.field(1).is_none());
+        parse_param_entry(param_entry(ADDRESS_RECEIVER), &mut runtime);
+        assert!(runtime.field(1).is_some());
+        drive_param_discovery(&mut runtime, &mut link);
+        assert!(matches!(
+            link.written.last().and_then(|frame| Frame::decode(frame)),
+            Some((
+                _,
+                Frame::ParamRead {
+                    dest: ADDRESS_RECEIVER,
+                    ..
+                }
+            ))
+        ));
+    }
+
+    #[test]
+    fn test_model_settings_diff_and_apply() {
+        let mut runtime = CrsfRuntime::new("test".to_string());
+        let mut link = RecordingLink::default();
+        parse_device_info(&device_info(ADDRESS_MODULE, "TX"), &mut runtime);
+        upsert_field(
+            &mut runtime,
+            parse_field_bytes(1, &field_bytes(0x0B, "TX Power", &[], "")).unwrap(),
+        );
+        let mut power = field_bytes(0x09, "Max Power", b"10;25;100\0", "");
+        power[0] = 1;
+        power.splice(power.len() - 1.., [1, 0, 2, 0, 0]);
+        upsert_field(&mut runtime, parse_field_bytes(2, &power).unwrap());
+        upsert_field(
+            &mut runtime,
+            parse_field_bytes(3, &field_bytes(0x00, "Rate", &[1, 0, 3, 0], "")).unwrap(),
+        );
+        runtime.discovery_complete = true;
+
+        let values = runtime.snapshot_values();
+        assert_eq!(
+            values,
+            vec![
+                ElrsParamValue {
+                    key: "TX Power/Max Power".to_string(),
+                    value: 1,
+                    text: "25".to_string(),
+                },
+                ElrsParamValue {
+                    key: "Rate".to_string(),
+                    value: 1,
+                    text: "1".to_string(),
+                },
+            ]
+        );
+
+        // Selections match by text, so the stale index is ignored; keys the
+        // module does not show are skipped.
+        runtime.snapshot = vec![
+            ElrsParamValue {
+                key: "TX Power/Max Power".to_string(),
+                value: 0,
+                text: "100".to_string(),
+            },
+            ElrsParamValue {
+                key: "Rate".to_string(),
+                value: 3,
+                text: "3".to_string(),
+            },
+            ElrsParamValue {
+                key: "Model Match".to_string(),
+                value: 1,
+                text: "On".to_string(),
+            },
+        ];
+        runtime.snapshot_check_pending = true;
+        runtime.check_snapshot();
+        assert!(runtime.status_text.starts_with("2 ELRS settings differ"));
+
+        let entries = runtime.visible_entries();
+        runtime.selected_idx = entries
+            .iter()
+            .position(|entry| *entry == VisibleEntry::ModelSettings)
+            .unwrap();
+        handle_crsf_command(ElrsCommandMsg::Activate, &mut runtime, &mut link, &mut None);
+        assert_eq!(runtime.view, MenuView::ModelSettings);
+        assert_eq!(
+            runtime.visible_entries(),
+            vec![
+                VisibleEntry::Refresh,
+                VisibleEntry::FolderUp,
+                VisibleEntry::SaveToModel,
+                VisibleEntry::ApplyToModule,
+                VisibleEntry::SnapshotDiff(2),
+                VisibleEntry::SnapshotDiff(3),
+            ]
+        );
+
+        runtime.selected_idx = 3;
+        handle_crsf_command(ElrsCommandMsg::Activate, &mut runtime, &mut link, &mut None);
+        let writes = link
+            .written
+            .iter()
+            .filter_map(|frame| match Frame::decode(frame) {
+                Some((
+                    _,
+                    Frame::ParamWrite {
+                        field_id, value, ..
+                    },
+                )) => Some((field_id, value)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(writes, vec![(2, vec![2]), (3, vec![3])]);
+        assert!(runtime.snapshot_diff().is_empty());
+        assert_eq!(runtime.state(false).path, "/Model Settings");
+    }
+
     #[test]
     fn test_string_write_frame_crc() {
-        let frame = build_param_write_string_frame(6, "lin-tx");
-        assert!(check_frame_crc(&frame));
+        let frame = build_param_write_string_frame(crate::crsf::ADDRESS_MODULE, 6, "lin-tx");
+        assert!(check_crc(&frame));
     }
 }
 
@@ -0,0 +1,167 @@
+use std::time::{Duration, Instant};
+
+use crate::{
+    config::{ControlRole, FlightModeConfig},
+    logical_switch::switch_active,
+};
+
+/// Weight of a mode that is fully faded in.
+pub const FADE_FULL: i32 = 1000;
+
+/// Index of the mode that should be active: the first mode whose switch is
+/// on, else the first mode without a switch, else 0.
+pub fn select_mode(
+    modes: &[FlightModeConfig],
+    inputs: &[(ControlRole, i32)],
+    logical_states: &[bool],
+) -> usize {
+    modes
+        .iter()
+        .position(|mode| {
+            mode.switch
+                .is_some_and(|switch| switch_active(switch, inputs, logical_states))
+        })
+        .or_else(|| modes.iter().position(|mode| mode.switch.is_none()))
+        .unwrap_or(0)
+}
+
+fn ramp(elapsed: Duration, fade_ms: u32) -> i32 {
+    if fade_ms == 0 {
+        return FADE_FULL;
+    }
+    (elapsed.as_millis().min(fade_ms as u128) * FADE_FULL as u128 / fade_ms as u128) as i32
+}
+
+/// Weighted average of two values; falls back to `active` when both weights are 0.
+pub fn blend(active: i32, active_weight: i32, previous: i32, previous_weight: i32) -> i32 {
+    let total = active_weight + previous_weight;
+    if total == 0 {
+        return active;
+    }
+    (active * active_weight + previous * previous_weight) / total
+}
+
+/// Tracks the active mode and the one it is fading away from.
+#[derive(Debug, Default)]
+pub struct FlightModeTracker {
+    active: usize,
+    previous: Option<usize>,
+    changed_at: Option<Instant>,
+}
+
+impl FlightModeTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn active(&self) -> usize {
+        self.active
+    }
+
+    /// Makes `selected` the active mode; the old one starts fading out.
+    pub fn update(&mut self, selected: usize, now: Instant) {
+        if selected != self.active {
+            self.previous = Some(self.active);
+            self.active = selected;
+            self.changed_at = Some(now);
+        }
+    }
+
+    /// Blend weights (0..=`FADE_FULL`) of the active mode and, while it is
+    /// still fading out, the previous one.
+    pub fn weights(
+        &mut self,
+        modes: &[FlightModeConfig],
+        now: Instant,
+    ) -> (i32, Option<(usize, i32)>) {
+        let (Some(previous), Some(changed_at)) = (self.previous, self.changed_at) else {
+            return (FADE_FULL, None);
+        };
+
+        let elapsed = now.saturating_duration_since(changed_at);
+        let fade_in = modes.get(self.active).map_or(0, |mode| mode.fade_in_ms);
+        let fade_out = modes.get(previous).map_or(0, |mode| mode.fade_out_ms);
+        let previous_weight = FADE_FULL - ramp(elapsed, fade_out);
+        if previous_weight == 0 {
+            self.previous = None;
+            return (FADE_FULL, None);
+        }
+        (ramp(elapsed, fade_in), Some((previous, previous_weight)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SwitchRef;
+
+    fn mode(name: &str, switch: Option<SwitchRef>) -> FlightModeConfig {
+        FlightModeConfig {
+            name: name.to_string(),
+            switch,
+            fade_in_ms: 0,
+            fade_out_ms: 0,
+            profile: String::new(),
+            trims: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_select_mode_prefers_first_active_switch() {
+        let modes = [
+            mode("normal", None),
+            mode("landing", Some(SwitchRef::Input(ControlRole::Aux1))),
+            mode("launch", Some(SwitchRef::Logical(0))),
+        ];
+
+        assert_eq!(
+            select_mode(&modes, &[(ControlRole::Aux1, -5000)], &[false]),
+            0
+        );
+        assert_eq!(
+            select_mode(&modes, &[(ControlRole::Aux1, 5000)], &[true]),
+            1
+        );
+        assert_eq!(
+            select_mode(&modes, &[(ControlRole::Aux1, -5000)], &[true]),
+            2
+        );
+        assert_eq!(select_mode(&[], &[], &[]), 0);
+    }
+
+    #[test]
+    fn test_fade_weights_ramp_between_modes() {
+        let start = Instant::now();
+        let modes = [
+            FlightModeConfig {
+                fade_out_ms: 400,
+                ..mode("normal", None)
+            },
+            FlightModeConfig {
+                fade_in_ms: 200,
+                ..mode("landing", Some(SwitchRef::Input(ControlRole::Aux1)))
+            },
+        ];
+        let mut tracker = FlightModeTracker::new();
+        assert_eq!(tracker.weights(&modes, start), (FADE_FULL, None));
+
+        tracker.update(1, start);
+        assert_eq!(tracker.active(), 1);
+        assert_eq!(tracker.weights(&modes, start), (0, Some((0, 1000))));
+        assert_eq!(
+            tracker.weights(&modes, start + Duration::from_millis(100)),
+            (500, Some((0, 750)))
+        );
+        assert_eq!(
+            tracker.weights(&modes, start + Duration::from_millis(300)),
+            (1000, Some((0, 250)))
+        );
+        assert_eq!(
+            tracker.weights(&modes, start + Duration::from_millis(400)),
+            (FADE_FULL, None)
+        );
+
+        assert_eq!(blend(100, 500, 200, 500), 150);
+        assert_eq!(blend(100, 0, 200, 0), 100);
+    }
+}
@@ -0,0 +1,273 @@
+use std::time::{Duration, Instant};
+
+use crate::{
+    config::{ControlRole, InputChannel, InputKind, InputMapping, InputSource},
+    messages::InputFrameMsg,
+};
+
+/// A producer that has not published for this long is skipped in favour of
+/// the next source in `source_priority`.
+pub const INPUT_STALE_TIMEOUT: Duration = Duration::from_millis(300);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RoutedInput {
+    pub role: ControlRole,
+    pub source: InputSource,
+    pub kind: InputKind,
+    /// Axis/trim value in source units, switch position (0/1/2) or button state (0/1).
+    pub raw: i16,
+    pub reversed: bool,
+    /// `false` when the mapped source was stale and a fallback source was used.
+    pub primary: bool,
+}
+
+/// Keeps the latest frame of every input producer and resolves
+/// `ModelConfig::input_mapping` against them.
+pub struct InputRouter {
+    latest: Vec<(InputFrameMsg, Instant)>,
+    stale_after: Duration,
+}
+
+impl InputRouter {
+    pub fn new(stale_after: Duration) -> Self {
+        Self {
+            latest: Vec::new(),
+            stale_after,
+        }
+    }
+
+    pub fn update(&mut self, frame: InputFrameMsg, now: Instant) {
+        match self
+            .latest
+            .iter_mut()
+            .find(|(latest, _)| latest.source == frame.source)
+        {
+            Some(slot) => *slot = (frame, now),
+            None => self.latest.push((frame, now)),
+        }
+    }
+
+    fn fresh(&self, source: InputSource, now: Instant) -> Option<&InputFrameMsg> {
+        self.latest
+            .iter()
+            .find(|(frame, _)| frame.source == source)
+            .filter(|(_, seen)| now.saturating_duration_since(*seen) <= self.stale_after)
+            .map(|(frame, _)| frame)
+    }
+
+    /// Resolves every mapped role. `stick_axis` gives the axis a source's
+    /// calibration found a stick on, which wins over the mapped index. Roles
+    /// whose source and all fallbacks are stale are left out; the mixer pulls
+    /// a lost throttle low and holds the rest at center.
+    pub fn route(
+        &self,
+        mapping: &InputMapping,
+        source_priority: &[InputSource],
+        stick_axis: impl Fn(InputSource, ControlRole) -> Option<usize>,
+        now: Instant,
+    ) -> Vec<RoutedInput> {
+        mapping
+            .channels
+            .iter()
+            .filter_map(|channel| self.route_channel(channel, source_priority, &stick_axis, now))
+            .collect()
+    }
+
+    fn route_channel(
+        &self,
+        channel: &InputChannel,
+        source_priority: &[InputSource],
+        stick_axis: &impl Fn(InputSource, ControlRole) -> Option<usize>,
+        now: Instant,
+    ) -> Option<RoutedInput> {
+        let mapped = (channel.source != InputSource::Unknown).then_some(channel.source);
+        mapped
+            .into_iter()
+            .chain(
+                source_priority
+                    .iter()
+                    .copied()
+                    .filter(|source| Some(*source) != mapped),
+            )
+            .find_map(|source| {
+                let frame = self.fresh(source, now)?;
+                let index = match channel.kind {
+                    InputKind::Axis => stick_axis(source, channel.role),
+                    _ => None,
+                };
+                let raw =
+                    read_element(frame, channel.kind, index.unwrap_or(channel.index as usize))?;
+                Some(RoutedInput {
+                    role: channel.role,
+                    source,
+                    kind: channel.kind,
+                    raw,
+                    reversed: channel.reversed,
+                    primary: mapped.is_none_or(|mapped| mapped == source),
+                })
+            })
+    }
+}
+
+fn read_element(frame: &InputFrameMsg, kind: InputKind, index: usize) -> Option<i16> {
+    match kind {
+        InputKind::Axis => frame.axes.get(index).copied(),
+        InputKind::Trim => frame.trims.get(index).copied(),
+        InputKind::Switch => frame.switches.get(index).map(|state| *state as i16),
+        InputKind::Button => frame.buttons.get(index).map(|pressed| *pressed as i16),
+    }
+}
+
+/// Raw range a producer reports before calibration; used for fallback
+/// sources and for roles that have no calibration entry.
+pub fn nominal_range(source: InputSource) -> (i16, i16) {
+    match source {
+        InputSource::Adc => (0, 2047),
+        InputSource::Stm32Serial => (0, 4095),
+        InputSource::Crsf => (172, 1811),
+        InputSource::Joydev => (-32767, 32767),
+        InputSource::Mock => (0, 1984),
+        InputSource::Unknown => (i16::MIN, i16::MAX),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::messages::SwitchState;
+
+    fn mapping(source: InputSource) -> InputMapping {
+        InputMapping {
+            channels: vec![
+                InputChannel {
+                    role: ControlRole::Thrust,
+                    source,
+                    kind: InputKind::Axis,
+                    index: 1,
+                    reversed: false,
+                },
+                InputChannel {
+                    role: ControlRole::Arm,
+                    source,
+                    kind: InputKind::Switch,
+                    index: 0,
+                    reversed: false,
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_route_uses_mapped_source_and_index() {
+        let now = Instant::now();
+        let mut router = InputRouter::new(INPUT_STALE_TIMEOUT);
+        router.update(
+            InputFrameMsg {
+                switches: vec![SwitchState::Down],
+                ..InputFrameMsg::from_axes(InputSource::Stm32Serial, vec![10, 20])
+            },
+            now,
+        );
+        router.update(
+            InputFrameMsg::from_axes(InputSource::Adc, vec![30, 40]),
+            now,
+        );
+
+        let routed = router.route(
+            &mapping(InputSource::Stm32Serial),
+            &[InputSource::Adc, InputSource::Stm32Serial],
+            |_, _| None,
+            now,
+        );
+        assert_eq!(routed.len(), 2);
+        assert_eq!(routed[0].source, InputSource::Stm32Serial);
+        assert_eq!(routed[0].raw, 20);
+        assert!(routed[0].primary);
+        assert_eq!(routed[1].raw, SwitchState::Down as i16);
+    }
+
+    #[test]
+    fn test_route_fails_over_when_source_goes_stale() {
+        let start = Instant::now();
+        let mut router = InputRouter::new(INPUT_STALE_TIMEOUT);
+        router.update(
+            InputFrameMsg::from_axes(InputSource::Stm32Serial, vec![10, 20]),
+            start,
+        );
+        let later = start + INPUT_STALE_TIMEOUT * 2;
+        router.update(
+            InputFrameMsg::from_axes(InputSource::Adc, vec![30, 40]),
+            later,
+        );
+
+        let priority = [InputSource::Stm32Serial, InputSource::Adc];
+        let routed = router.route(
+            &mapping(InputSource::Stm32Serial),
+            &priority,
+            |_, _| None,
+            later,
+        );
+        assert_eq!(
+            routed.len(),
+            1,
+            "adc frame has no switches for the arm role"
+        );
+        assert_eq!(routed[0].source, InputSource::Adc);
+        assert_eq!(routed[0].raw, 40);
+        assert!(!routed[0].primary);
+
+        router.update(
+            InputFrameMsg::from_axes(InputSource::Stm32Serial, vec![11, 21]),
+            later,
+        );
+        let routed = router.route(
+            &mapping(InputSource::Stm32Serial),
+            &priority,
+            |_, _| None,
+            later,
+        );
+        assert_eq!(routed[0].source, InputSource::Stm32Serial);
+        assert_eq!(routed[0].raw, 21);
+    }
+
+    #[test]
+    fn test_unknown_source_follows_priority() {
+        let now = Instant::now();
+        let mut router = InputRouter::new(INPUT_STALE_TIMEOUT);
+        router.update(InputFrameMsg::from_axes(InputSource::Mock, vec![1, 2]), now);
+        router.update(InputFrameMsg::from_axes(InputSource::Crsf, vec![3, 4]), now);
+
+        let routed = router.route(
+            &mapping(InputSource::Unknown),
+            &[InputSource::Adc, InputSource::Crsf, InputSource::Mock],
+            |_, _| None,
+            now,
+        );
+        assert_eq!(routed[0].source, InputSource::Crsf);
+        assert!(routed[0].primary);
+    }
+
+    #[test]
+    fn test_calibrated_stick_axis_wins_over_mapped_index() {
+        let now = Instant::now();
+        let mut router = InputRouter::new(INPUT_STALE_TIMEOUT);
+        router.update(
+            InputFrameMsg {
+                switches: vec![SwitchState::Mid],
+                ..InputFrameMsg::from_axes(InputSource::Adc, vec![10, 20, 30])
+            },
+            now,
+        );
+
+        let stick_axis =
+            |source, role| (source == InputSource::Adc && role == ControlRole::Thrust).then_some(2);
+        let routed = router.route(
+            &mapping(InputSource::Adc),
+            &[InputSource::Adc],
+            stick_axis,
+            now,
+        );
+        assert_eq!(routed[0].raw, 30);
+        assert_eq!(routed[1].raw, SwitchState::Mid as i16);
+    }
+}
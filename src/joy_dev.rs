@@ -4,7 +4,7 @@ use clap::Parser;
 use joydev::{event_codes::AbsoluteAxis, GenericEvent};
 use rpos::{msg::get_new_tx_of_message, thread_logln};
 
-use crate::{client_process_args, messages::AdcRawMsg};
+use crate::{client_process_args, config::InputSource, messages::InputFrameMsg};
 
 #[derive(Parser)]
 #[command(name="joy_dev", about = "used for machine with joysticks(/dev/input/js*)", long_about = None)]
@@ -25,7 +25,7 @@ fn joy_dev_main(argc: u32, argv: *const &str) {
         .unwrap();
     let dev = joydev::Device::new(file).unwrap();
 
-    let adc_raw_tx = rpos::msg::get_new_tx_of_message::<AdcRawMsg>("adc_raw").unwrap();
+    let input_tx = rpos::msg::get_new_tx_of_message::<InputFrameMsg>("input_frame").unwrap();
     let chn_map: HashMap<AbsoluteAxis, usize> = [
         (AbsoluteAxis::LeftX, 0),
         (AbsoluteAxis::LeftY, 1),
@@ -34,15 +34,15 @@ fn joy_dev_main(argc: u32, argv: *const &str) {
     ]
     .into_iter()
     .collect();
-    let mut chn_value: [i16; 4] = [0; 4];
+    let mut frame = InputFrameMsg::from_axes(InputSource::Joydev, vec![0; chn_map.len()]);
 
     loop {
         let s = dev.get_event().unwrap();
         match s {
             joydev::DeviceEvent::Axis(x) => {
                 if let Some(index) = chn_map.get(&x.axis()) {
-                    chn_value[*index] = x.value();
-                    adc_raw_tx.send(AdcRawMsg { value: chn_value });
+                    frame.axes[*index] = x.value();
+                    input_tx.send(frame.clone());
                 }
             }
             _ => {}
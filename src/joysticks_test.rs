@@ -3,11 +3,15 @@ use rpos::{msg::get_new_rx_of_message, thread_log};
 use crate::mixer::MixerOutMsg;
 
 fn channel_out(mixout: &MixerOutMsg) {
-    thread_log!("\x1b[2KThrust:{}\n", mixout.thrust);
-    thread_log!("\x1b[2Kdirection:{}\n", mixout.direction);
-    thread_log!("\x1b[2Kaileron:{}\n", mixout.aileron);
-    thread_log!("\x1b[2Kelevator:{}\n", mixout.elevator);
-    thread_log!("\x1b[4A");
+    for (index, value) in mixout.active_channels().iter().enumerate() {
+        thread_log!(
+            "\x1b[2KCH{} {}:{}\n",
+            index + 1,
+            mixout.roles[index].short_name(),
+            value
+        );
+    }
+    thread_log!("\x1b[{}A", mixout.count);
 }
 fn joysticks_test_main(_argc: u32, _argv: *const &str) {
     let mut rx = get_new_rx_of_message::<MixerOutMsg>("mixer_out").unwrap();
@@ -25,15 +29,19 @@ fn register() {
 mod tests {
 
     use super::*;
+    use crate::config::ControlRole;
     #[test]
     fn test_channel_out() {
         for i in 0..100 as u16 {
-            let mixout = MixerOutMsg {
-                thrust: i * 100,
-                direction: i * 100,
-                elevator: i * 100,
-                aileron: i * 100,
-            };
+            let mixout = MixerOutMsg::from_roles(
+                &[
+                    ControlRole::Aileron,
+                    ControlRole::Elevator,
+                    ControlRole::Thrust,
+                    ControlRole::Direction,
+                ],
+                |_| i * 100,
+            );
             channel_out(&mixout);
             std::thread::sleep(std::time::Duration::from_secs(1));
         }
@@ -0,0 +1,406 @@
+use std::time::{Duration, Instant};
+
+use crate::config::{
+    ControlRole, LogicalFunction, LogicalSource, LogicalSwitchConfig, ModelConfig, SwitchRef,
+};
+
+/// Values the switches can look at during one mixer cycle. Inputs and
+/// channels are centered (-5000..5000); telemetry is in raw sensor units.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SwitchContext<'a> {
+    pub inputs: &'a [(ControlRole, i32)],
+    pub channels: &'a [i32],
+    pub telemetry: &'a [(String, i32)],
+}
+
+impl SwitchContext<'_> {
+    fn source_value(&self, source: &LogicalSource) -> Option<i32> {
+        match source {
+            LogicalSource::Input(role) => self
+                .inputs
+                .iter()
+                .find(|(input_role, _)| input_role == role)
+                .map(|(_, value)| *value),
+            LogicalSource::Channel(slot) => self.channels.get(*slot as usize).copied(),
+            LogicalSource::Telemetry(key) => self
+                .telemetry
+                .iter()
+                .find(|(sensor, _)| sensor == key)
+                .map(|(_, value)| *value),
+        }
+    }
+}
+
+fn threshold(source: &LogicalSource, x: i32) -> i32 {
+    match source {
+        LogicalSource::Input(_) | LogicalSource::Channel(_) => x * 5,
+        LogicalSource::Telemetry(_) => x,
+    }
+}
+
+/// Shared by logical switches, mix conditions and anything else that takes a
+/// `SwitchRef`. Missing switches read as off.
+pub fn switch_active(
+    switch: SwitchRef,
+    inputs: &[(ControlRole, i32)],
+    logical_states: &[bool],
+) -> bool {
+    match switch {
+        SwitchRef::Logical(index) => logical_states.get(index as usize).copied().unwrap_or(false),
+        SwitchRef::Input(role) => inputs
+            .iter()
+            .find(|(input_role, _)| *input_role == role)
+            .is_some_and(|(_, value)| *value > 0),
+    }
+}
+
+fn elapsed(since: Option<Instant>, now: Instant) -> Duration {
+    since.map_or(Duration::ZERO, |since| now.saturating_duration_since(since))
+}
+
+#[derive(Debug, Clone, Default)]
+struct SwitchRuntime {
+    /// When the function (and AND switch) last turned true.
+    true_since: Option<Instant>,
+    /// When the switch itself last turned on; used for `duration_ms`.
+    on_since: Option<Instant>,
+    /// Set once `duration_ms` ran out, cleared when the function drops.
+    expired: bool,
+    latched: bool,
+    edge_source_since: Option<Instant>,
+    timer_start: Option<Instant>,
+}
+
+/// Keeps per-switch timing state between mixer cycles.
+#[derive(Debug, Default)]
+pub struct LogicalSwitchEngine {
+    model_id: String,
+    switches: Vec<LogicalSwitchConfig>,
+    runtime: Vec<SwitchRuntime>,
+    states: Vec<bool>,
+}
+
+impl LogicalSwitchEngine {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Drops latches, delays and timers when another model becomes active or
+    /// the active model's switches are edited.
+    pub fn sync_model(&mut self, model: &ModelConfig) {
+        if self.model_id == model.id && self.switches == model.logical_switches {
+            return;
+        }
+        self.model_id = model.id.clone();
+        self.switches = model.logical_switches.clone();
+        self.runtime.clear();
+        self.states.clear();
+    }
+
+    /// Runs one cycle. State is reset when the number of switches changes.
+    pub fn evaluate(
+        &mut self,
+        switches: &[LogicalSwitchConfig],
+        context: &SwitchContext,
+        now: Instant,
+    ) -> &[bool] {
+        if self.states.len() != switches.len() {
+            self.runtime = vec![SwitchRuntime::default(); switches.len()];
+            self.states = vec![false; switches.len()];
+        }
+
+        for (index, switch) in switches.iter().enumerate() {
+            let function_on = self.evaluate_function(index, &switch.function, context, now);
+            let and_on = switch
+                .and_switch
+                .is_none_or(|and_switch| switch_active(and_switch, context.inputs, &self.states));
+            self.states[index] = self.apply_timing(index, switch, function_on && and_on, now);
+        }
+
+        &self.states
+    }
+
+    fn evaluate_function(
+        &mut self,
+        index: usize,
+        function: &LogicalFunction,
+        context: &SwitchContext,
+        now: Instant,
+    ) -> bool {
+        let switch_on = |switch: &SwitchRef| switch_active(*switch, context.inputs, &self.states);
+        let compare = |a: &LogicalSource, test: &dyn Fn(i32) -> bool| {
+            context.source_value(a).is_some_and(test)
+        };
+
+        match function {
+            LogicalFunction::AGreaterX { a, x } => compare(a, &|v| v > threshold(a, *x)),
+            LogicalFunction::ALessX { a, x } => compare(a, &|v| v < threshold(a, *x)),
+            LogicalFunction::AbsGreaterX { a, x } => {
+                compare(a, &|v| v.abs() > threshold(a, *x).abs())
+            }
+            LogicalFunction::AbsLessX { a, x } => compare(a, &|v| v.abs() < threshold(a, *x).abs()),
+            LogicalFunction::AGreaterB { a, b } => context
+                .source_value(b)
+                .is_some_and(|b| compare(a, &|a| a > b)),
+            LogicalFunction::ALessB { a, b } => context
+                .source_value(b)
+                .is_some_and(|b| compare(a, &|a| a < b)),
+            LogicalFunction::And { a, b } => switch_on(a) && switch_on(b),
+            LogicalFunction::Or { a, b } => switch_on(a) || switch_on(b),
+            LogicalFunction::Xor { a, b } => switch_on(a) != switch_on(b),
+            LogicalFunction::Edge { a, min_ms, max_ms } => {
+                let source_on = switch_on(a);
+                let runtime = &mut self.runtime[index];
+                match (source_on, runtime.edge_source_since) {
+                    (true, None) => {
+                        runtime.edge_source_since = Some(now);
+                        false
+                    }
+                    (false, Some(since)) => {
+                        runtime.edge_source_since = None;
+                        let held = now.saturating_duration_since(since);
+                        held >= Duration::from_millis(*min_ms as u64)
+                            && (*max_ms == 0 || held <= Duration::from_millis(*max_ms as u64))
+                    }
+                    _ => false,
+                }
+            }
+            LogicalFunction::Sticky { set, reset } => {
+                let (set_on, reset_on) = (switch_on(set), switch_on(reset));
+                let runtime = &mut self.runtime[index];
+                if reset_on {
+                    runtime.latched = false;
+                } else if set_on {
+                    runtime.latched = true;
+                }
+                runtime.latched
+            }
+            LogicalFunction::Timer { on_ms, off_ms } => {
+                let period = *on_ms as u128 + *off_ms as u128;
+                let runtime = &mut self.runtime[index];
+                let start = *runtime.timer_start.get_or_insert(now);
+                period != 0
+                    && now.saturating_duration_since(start).as_millis() % period < *on_ms as u128
+            }
+        }
+    }
+
+    fn apply_timing(
+        &mut self,
+        index: usize,
+        switch: &LogicalSwitchConfig,
+        function_on: bool,
+        now: Instant,
+    ) -> bool {
+        let runtime = &mut self.runtime[index];
+        if !function_on {
+            runtime.true_since = None;
+            runtime.on_since = None;
+            runtime.expired = false;
+            return false;
+        }
+
+        let true_since = *runtime.true_since.get_or_insert(now);
+        if runtime.expired
+            || now.saturating_duration_since(true_since)
+                < Duration::from_millis(switch.delay_ms as u64)
+        {
+            return false;
+        }
+
+        runtime.on_since.get_or_insert(now);
+        if switch.duration_ms != 0
+            && elapsed(runtime.on_since, now) >= Duration::from_millis(switch.duration_ms as u64)
+        {
+            runtime.expired = true;
+            runtime.on_since = None;
+            return false;
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn switch(function: LogicalFunction) -> LogicalSwitchConfig {
+        LogicalSwitchConfig {
+            name: String::new(),
+            function,
+            and_switch: None,
+            delay_ms: 0,
+            duration_ms: 0,
+        }
+    }
+
+    fn ms(value: u64) -> Duration {
+        Duration::from_millis(value)
+    }
+
+    #[test]
+    fn test_comparisons_over_inputs_channels_and_telemetry() {
+        let inputs = [(ControlRole::Thrust, -3000), (ControlRole::Aileron, 1000)];
+        let channels = [0, 2500];
+        let telemetry = [("rssi".to_string(), 40)];
+        let context = SwitchContext {
+            inputs: &inputs,
+            channels: &channels,
+            telemetry: &telemetry,
+        };
+        let switches = [
+            switch(LogicalFunction::AGreaterX {
+                a: LogicalSource::Input(ControlRole::Thrust),
+                x: -700,
+            }),
+            switch(LogicalFunction::AbsGreaterX {
+                a: LogicalSource::Input(ControlRole::Thrust),
+                x: 500,
+            }),
+            switch(LogicalFunction::ALessX {
+                a: LogicalSource::Telemetry("rssi".to_string()),
+                x: 50,
+            }),
+            switch(LogicalFunction::AGreaterB {
+                a: LogicalSource::Channel(1),
+                b: LogicalSource::Input(ControlRole::Aileron),
+            }),
+            switch(LogicalFunction::ALessX {
+                a: LogicalSource::Telemetry("link_quality".to_string()),
+                x: 50,
+            }),
+            switch(LogicalFunction::Xor {
+                a: SwitchRef::Logical(0),
+                b: SwitchRef::Logical(1),
+            }),
+        ];
+
+        let mut engine = LogicalSwitchEngine::new();
+        assert_eq!(
+            engine.evaluate(&switches, &context, Instant::now()),
+            &[true, true, true, true, false, false]
+        );
+    }
+
+    #[test]
+    fn test_delay_duration_and_and_switch() {
+        let start = Instant::now();
+        let mut inputs = vec![(ControlRole::Arm, 5000), (ControlRole::Mode, 5000)];
+        let switches = [LogicalSwitchConfig {
+            and_switch: Some(SwitchRef::Input(ControlRole::Mode)),
+            delay_ms: 100,
+            duration_ms: 200,
+            ..switch(LogicalFunction::AGreaterX {
+                a: LogicalSource::Input(ControlRole::Arm),
+                x: 0,
+            })
+        }];
+        let mut engine = LogicalSwitchEngine::new();
+        let mut step = |inputs: &[(ControlRole, i32)], at: u64| {
+            let context = SwitchContext {
+                inputs,
+                ..SwitchContext::default()
+            };
+            engine.evaluate(&switches, &context, start + ms(at))[0]
+        };
+
+        assert!(!step(&inputs, 0));
+        assert!(!step(&inputs, 50));
+        assert!(step(&inputs, 100));
+        assert!(step(&inputs, 250));
+        assert!(!step(&inputs, 300), "duration expired");
+        assert!(!step(&inputs, 1000), "stays off until the function drops");
+
+        inputs[1].1 = -5000;
+        assert!(!step(&inputs, 1100), "and switch is off");
+        inputs[1].1 = 5000;
+        assert!(!step(&inputs, 1200));
+        assert!(step(&inputs, 1300));
+    }
+
+    #[test]
+    fn test_sticky_edge_and_timer() {
+        let start = Instant::now();
+        let switches = [
+            switch(LogicalFunction::Sticky {
+                set: SwitchRef::Input(ControlRole::Aux1),
+                reset: SwitchRef::Input(ControlRole::Aux2),
+            }),
+            switch(LogicalFunction::Edge {
+                a: SwitchRef::Input(ControlRole::Aux1),
+                min_ms: 100,
+                max_ms: 500,
+            }),
+            switch(LogicalFunction::Timer {
+                on_ms: 100,
+                off_ms: 300,
+            }),
+        ];
+        let mut engine = LogicalSwitchEngine::new();
+        let mut step = |aux1: i32, aux2: i32, at: u64| {
+            let inputs = [(ControlRole::Aux1, aux1), (ControlRole::Aux2, aux2)];
+            let context = SwitchContext {
+                inputs: &inputs,
+                ..SwitchContext::default()
+            };
+            engine
+                .evaluate(&switches, &context, start + ms(at))
+                .to_vec()
+        };
+
+        assert_eq!(step(5000, -5000, 0), [true, false, true]);
+        assert_eq!(step(5000, -5000, 150), [true, false, false]);
+        assert_eq!(
+            step(-5000, -5000, 200),
+            [true, true, false],
+            "edge fires on release"
+        );
+        assert_eq!(step(-5000, -5000, 250), [true, false, false]);
+        assert_eq!(step(-5000, 5000, 400), [false, false, true]);
+
+        assert!(!step(5000, -5000, 500)[1]);
+        assert!(!step(-5000, -5000, 520)[1], "released too early");
+    }
+
+    #[test]
+    fn test_state_resets_on_model_switch() {
+        let sticky = switch(LogicalFunction::Sticky {
+            set: SwitchRef::Input(ControlRole::Aux1),
+            reset: SwitchRef::Input(ControlRole::Aux2),
+        });
+        let quad = ModelConfig {
+            id: "quad".to_string(),
+            logical_switches: vec![sticky],
+            ..ModelConfig::default()
+        };
+        let plane = ModelConfig {
+            id: "plane".to_string(),
+            ..quad.clone()
+        };
+        let mut engine = LogicalSwitchEngine::new();
+        let step = |engine: &mut LogicalSwitchEngine, model: &ModelConfig, aux1: i32| {
+            let inputs = [(ControlRole::Aux1, aux1), (ControlRole::Aux2, -5000)];
+            let context = SwitchContext {
+                inputs: &inputs,
+                ..SwitchContext::default()
+            };
+            engine.sync_model(model);
+            engine.evaluate(&model.logical_switches, &context, Instant::now())[0]
+        };
+
+        assert!(step(&mut engine, &quad, 5000));
+        assert!(step(&mut engine, &quad, -5000), "latched");
+        assert!(
+            !step(&mut engine, &plane, -5000),
+            "new model starts unlatched"
+        );
+
+        assert!(step(&mut engine, &plane, 5000));
+        let mut edited = plane.clone();
+        edited.logical_switches[0].delay_ms = 100;
+        assert!(
+            !step(&mut engine, &edited, -5000),
+            "edited switches start over"
+        );
+    }
+}
@@ -5,17 +5,16 @@ use std::{
 };
 
 use clap::Parser;
-use crc::{Crc, CRC_8_DVB_S2};
-use crsf::{PacketAddress, RawPacket};
 use mlua::{
     Error as LuaError, Lua, Result as LuaResult, String as LuaString, Table, UserData,
     UserDataMethods,
 };
 use rpos::thread_logln;
 
-use crate::client_process_args;
-
-const CRC8_DVB_S2: Crc<u8> = Crc::<u8>::new(&CRC_8_DVB_S2);
+use crate::{
+    client_process_args,
+    crsf::{self, Frame},
+};
 
 #[derive(Parser)]
 #[command(name = "lua_run", about = "Run a Lua script with UART/CRSF helpers")]
@@ -185,7 +184,7 @@ fn install_lua_api(lua: &Lua, script_path: &str, args: &[String]) -> LuaResult<(
     crsf.set(
         "encode",
         lua.create_function(|lua, (dest, frame_type, payload): (u8, u8, LuaString)| {
-            let packet = encode_crsf_packet(dest, frame_type, payload.as_bytes().as_ref());
+            let packet = crsf::encode_raw(dest, frame_type, payload.as_bytes().as_ref());
             lua.create_string(&packet)
         })?,
     )?;
@@ -193,7 +192,7 @@ fn install_lua_api(lua: &Lua, script_path: &str, args: &[String]) -> LuaResult<(
         "rc_channels",
         lua.create_function(|lua, channels: Table| {
             let packet = encode_crsf_rc_channels(channels)?;
-            lua.create_string(packet.data())
+            lua.create_string(&packet)
         })?,
     )?;
     globals.set("crsf", crsf)?;
@@ -201,27 +200,14 @@ fn install_lua_api(lua: &Lua, script_path: &str, args: &[String]) -> LuaResult<(
     Ok(())
 }
 
-fn encode_crsf_packet(dest: u8, frame_type: u8, payload: &[u8]) -> Vec<u8> {
-    let mut out = Vec::with_capacity(payload.len() + 4);
-    out.push(dest);
-    out.push((payload.len() + 2) as u8);
-    out.push(frame_type);
-    out.extend_from_slice(payload);
-    out.push(CRC8_DVB_S2.checksum(&out[2..]));
-    out
-}
-
-fn encode_crsf_rc_channels(channels: Table) -> LuaResult<RawPacket> {
-    let mut values = [crsf::RcChannels::CHANNEL_VALUE_MID; 16];
-    for idx in 1..=16 {
+fn encode_crsf_rc_channels(channels: Table) -> LuaResult<Vec<u8>> {
+    let mut values = [crsf::CHANNEL_VALUE_MID; crsf::RC_CHANNELS];
+    for idx in 1..=crsf::RC_CHANNELS {
         if let Ok(Some(value)) = channels.get::<Option<u16>>(idx) {
-            values[idx - 1] = value.clamp(
-                crsf::RcChannels::CHANNEL_VALUE_MIN,
-                crsf::RcChannels::CHANNEL_VALUE_MAX,
-            );
+            values[idx - 1] = value.clamp(crsf::CHANNEL_VALUE_MIN, crsf::CHANNEL_VALUE_MAX);
         }
     }
-    Ok(crsf::Packet::RcChannels(crsf::RcChannels(values)).into_raw(PacketAddress::Transmitter))
+    Ok(Frame::RcChannels(values).encode(crsf::ADDRESS_MODULE))
 }
 
 fn decode_hex(hex: &str) -> LuaResult<Vec<u8>> {
@@ -259,7 +245,8 @@ fn nibble_to_hex(nibble: u8) -> char {
 
 #[cfg(test)]
 mod tests {
-    use super::{decode_hex, encode_crsf_packet, encode_hex};
+    use super::{decode_hex, encode_hex};
+    use crate::crsf::encode_raw;
 
     #[test]
     fn test_hex_roundtrip() {
@@ -269,7 +256,7 @@ mod tests {
 
     #[test]
     fn test_crsf_packet_layout() {
-        let packet = encode_crsf_packet(0xee, 0x2d, &[0xee, 0xea, 0x01, 0x00]);
+        let packet = encode_raw(0xee, 0x2d, &[0xee, 0xea, 0x01, 0x00]);
         assert_eq!(packet[0], 0xee);
         assert_eq!(packet[1], 6);
         assert_eq!(packet[2], 0x2d);
@@ -7,30 +7,43 @@ use rpos::server_client::{server_init, Client};
 use std::io::ErrorKind;
 #[cfg(target_os = "linux")]
 mod adc;
+#[cfg(target_os = "linux")]
+mod audio;
 mod calibrate;
 mod config;
+mod config_watch;
+mod crsf;
 mod crsf_rc_in;
 #[cfg(target_os = "linux")]
 mod elrs_agent;
 #[cfg(target_os = "linux")]
 mod elrs_tx;
-#[cfg(target_os = "linux")]
-mod gampad;
+mod flight_mode;
+mod input_router;
 #[cfg(all(target_os = "linux", feature = "joydev_input"))]
 mod joy_dev;
 mod joysticks_test;
+mod logical_switch;
 #[cfg(all(target_os = "linux", feature = "lua"))]
 mod lua_run;
 mod messages;
 mod mixer;
 mod mock_joystick;
+#[cfg(target_os = "linux")]
+mod output;
+mod shutdown;
+mod special_function;
 mod stm32_serial;
 mod system_state_mock;
+mod telemetry;
+mod timer;
 mod ui;
 mod ui_demo;
 #[cfg(target_os = "linux")]
 mod usb_gamepad;
 
+/// Calibration file of older releases, copied into the radio's calibration
+/// profile on start; see `config::store::CALIBRATION_DIR`.
 pub const CALIBRATE_FILENAME: &str = "joystick.toml";
 
 #[derive(Parser)]
@@ -126,6 +139,9 @@ fn run_unix_client_server() {
         );
         println!("Server socket path: {socket_path}");
 
+        #[cfg(target_os = "linux")]
+        shutdown::install();
+
         if let Err(err) = server_init(&socket_path) {
             eprintln!(
                 "[lintx] failed to start server on socket `{}`: {}",
@@ -1,8 +1,128 @@
-use crate::config::ModelConfig;
+use crate::{
+    calibrate::ChannelInfo,
+    config::{InputConfig, InputSource, ModelConfig, UiPageRef},
+};
 
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
-pub struct AdcRawMsg {
-    pub value: [i16; 4],
+pub enum SwitchState {
+    #[default]
+    Up,
+    Mid,
+    Down,
+}
+
+impl SwitchState {
+    /// 0 = up, 1 = mid, anything else = down.
+    pub fn from_position(position: u8) -> Self {
+        match position {
+            0 => Self::Up,
+            1 => Self::Mid,
+            _ => Self::Down,
+        }
+    }
+}
+
+/// One snapshot from a single input producer. Axes and trims are in the
+/// producer's raw units (ADC counts, CRSF ticks, joydev values); calibration
+/// happens downstream. Two-position switches only ever report `Up`/`Down`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InputFrameMsg {
+    pub source: InputSource,
+    pub axes: Vec<i16>,
+    pub switches: Vec<SwitchState>,
+    pub buttons: Vec<bool>,
+    pub trims: Vec<i16>,
+}
+
+impl InputFrameMsg {
+    pub fn from_axes(source: InputSource, axes: Vec<i16>) -> Self {
+        Self {
+            source,
+            axes,
+            ..Self::default()
+        }
+    }
+
+    pub fn axis(&self, index: usize) -> i16 {
+        self.axes.get(index).copied().unwrap_or(0)
+    }
+}
+
+/// Logical switch states in `ModelConfig::logical_switches` order,
+/// published by the mixer every cycle.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LogicalSwitchMsg {
+    pub states: Vec<bool>,
+}
+
+/// Active flight mode, published by the mixer every cycle. `name` is empty
+/// when the model has no flight modes.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FlightModeMsg {
+    pub index: u8,
+    pub name: String,
+    /// `true` while the previous mode is still fading out.
+    pub fading: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TimerState {
+    pub name: String,
+    /// Displayed value: elapsed for up timers, remaining for down timers
+    /// (negative once a countdown has run out).
+    pub value_secs: i64,
+    pub running: bool,
+}
+
+/// Timers of the active model, published by the mixer every cycle.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TimerStateMsg {
+    pub timers: Vec<TimerState>,
+}
+
+/// A CRC-checked CRSF frame `[addr, len, type, payload.., crc]`, read back
+/// from a CRSF link (`crsf_rx`) or queued for the link owner (`crsf_tx`).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CrsfFrameMsg {
+    pub frame: Vec<u8>,
+}
+
+/// RC frame pacing of the CRSF output, published about once a second.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CrsfRateMsg {
+    /// The module sent timing corrections recently.
+    pub synced: bool,
+    pub target_hz: u16,
+    pub achieved_hz: u16,
+    /// Mean deviation of the frame spacing from the target period.
+    pub jitter_us: u32,
+}
+
+/// One enabled model sensor, scaled per its `TelemetrySensorConfig`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SensorReading {
+    pub key: String,
+    pub unit: String,
+    pub precision: u8,
+    /// `None` while the sensor is lost.
+    pub value: Option<i32>,
+    /// Extremes since the last reset.
+    pub min: Option<i32>,
+    pub max: Option<i32>,
+}
+
+/// Decoded telemetry of the active model, published by the `telemetry` module.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TelemetryMsg {
+    pub sensors: Vec<SensorReading>,
+    pub flight_mode: String,
+    /// Uplink link quality in percent; `None` while the link is lost.
+    pub link_quality: Option<u8>,
+    /// Best uplink antenna RSSI in dBm.
+    pub rssi: Option<i16>,
+    /// Aircraft pack voltage in 0.1 V.
+    pub battery_voltage: Option<u16>,
+    pub battery_remaining: Option<u8>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -62,12 +182,21 @@ pub struct ElrsStateMsg {
     pub editor_label: String,
     pub editor_buffer: String,
     pub editor_cursor: usize,
+    /// A command field is running on the device.
+    pub command_active: bool,
+    pub command_label: String,
+    /// Progress text the device reports for the running command.
+    pub command_info: String,
+    /// The device waits for confirm (`Activate`) or cancel (`Back`).
+    pub command_confirm: bool,
     pub module_name: String,
     pub device_name: String,
     pub version: String,
     pub packet_rate: String,
     pub telemetry_ratio: String,
     pub tx_power: String,
+    /// RC frame rate the CRSF output achieves, see `CrsfRateMsg`.
+    pub rc_rate: String,
     pub status_text: String,
     pub wifi_running: bool,
     pub selected_idx: usize,
@@ -85,12 +214,17 @@ impl Default for ElrsStateMsg {
             editor_label: String::new(),
             editor_buffer: String::new(),
             editor_cursor: 0,
+            command_active: false,
+            command_label: String::new(),
+            command_info: String::new(),
+            command_confirm: false,
             module_name: "ELRS".to_string(),
             device_name: "Not Connected".to_string(),
             version: "--".to_string(),
             packet_rate: "--".to_string(),
             telemetry_ratio: "--".to_string(),
             tx_power: "--".to_string(),
+            rc_rate: "--".to_string(),
             status_text: "Idle".to_string(),
             wifi_running: false,
             selected_idx: 0,
@@ -101,6 +235,8 @@ impl Default for ElrsStateMsg {
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ElrsCommandMsg {
+    /// Puts the module into bind mode, regardless of the menu state.
+    Bind,
     Refresh,
     Back,
     SelectPrev,
@@ -110,12 +246,116 @@ pub enum ElrsCommandMsg {
     Activate,
 }
 
+/// Where the `calibrate` service is in its guided sequence.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CalibrationStep {
+    #[default]
+    Idle,
+    /// Sticks at rest; `Next` captures center and deadband.
+    Center,
+    /// Waiting for the stick of `channel` to be pushed low or left.
+    DetectAxis,
+    /// An axis was found for `channel`; `Next` keeps it, `Back` retries.
+    ConfirmAxis,
+    /// Sticks are swept to their ends; `Next` checks and saves.
+    MinMax,
+    Done,
+}
+
+/// Progress of the `calibrate` service, published every cycle.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CalibrationStateMsg {
+    pub step: CalibrationStep,
+    /// Source the calibration locked onto with its first frame.
+    pub source: InputSource,
+    /// Profile the result is saved to, resolved for `source`.
+    pub profile: String,
+    /// Channel being detected or confirmed, e.g. `Thrust`.
+    pub channel: String,
+    /// Raw axis found for `channel`, while it waits for confirmation.
+    pub detected_axis: Option<u8>,
+    pub instruction: String,
+    /// Last problem, e.g. a stick that was not moved to both ends.
+    pub status_text: String,
+    /// Latest raw axes, for live bars.
+    pub axes: Vec<i16>,
+    /// Channels calibrated so far, in `JoystickChannel` order.
+    pub channels: Vec<ChannelInfo>,
+}
+
+/// A calibration profile was saved or the radio switched profiles; the
+/// mixer reloads its calibrations.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CalibrationUpdatedMsg {
+    pub profile: String,
+}
+
+/// The `[input]` section of an edited `radio.toml`, published by
+/// `config_watch`; the mixer reroutes and reloads its calibrations.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RadioInputMsg {
+    pub input: InputConfig,
+}
+
+/// A config file changed on disk, published by `config_watch`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConfigChangeMsg {
+    pub path: String,
+    /// Why the file was rejected; empty when it was accepted.
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CalibrationCommandMsg {
+    /// Starts over from the center step.
+    Start,
+    Next,
+    /// Retries the current axis, or returns to the previous one.
+    Back,
+    Cancel,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimerCommandMsg {
+    Reset(u8),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TelemetryCommandMsg {
+    /// Clears min/max and derived sensors to start a new flight.
+    ResetStats,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AudioCueMsg {
+    Sound(String),
+    Haptic { duration_ms: u32 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UiCommandMsg {
+    OpenPage(UiPageRef),
+}
+
 #[rpos::ctor::ctor]
 fn register() {
-    rpos::msg::add_message::<AdcRawMsg>("adc_raw");
+    rpos::msg::add_message::<InputFrameMsg>("input_frame");
     rpos::msg::add_message::<SystemStatusMsg>("system_status");
     rpos::msg::add_message::<SystemConfigMsg>("system_config");
     rpos::msg::add_message::<ActiveModelMsg>("active_model");
     rpos::msg::add_message::<ElrsStateMsg>("elrs_state");
     rpos::msg::add_message::<ElrsCommandMsg>("elrs_cmd");
+    rpos::msg::add_message::<TimerCommandMsg>("timer_cmd");
+    rpos::msg::add_message::<AudioCueMsg>("audio_cue");
+    rpos::msg::add_message::<UiCommandMsg>("ui_cmd");
+    rpos::msg::add_message::<CrsfFrameMsg>("crsf_rx");
+    rpos::msg::add_message::<CrsfFrameMsg>("crsf_tx");
+    rpos::msg::add_message::<CrsfRateMsg>("crsf_rate");
+    rpos::msg::add_message::<TelemetryMsg>("telemetry");
+    rpos::msg::add_message::<TelemetryCommandMsg>("telemetry_cmd");
+    rpos::msg::add_message::<CalibrationStateMsg>("calibration_state");
+    rpos::msg::add_message::<CalibrationCommandMsg>("calibration_cmd");
+    rpos::msg::add_message::<CalibrationUpdatedMsg>("calibration_updated");
+    rpos::msg::add_message::<RadioInputMsg>("radio_input");
+    rpos::msg::add_message::<ConfigChangeMsg>("config_change");
 }
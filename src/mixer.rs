@@ -1,43 +1,472 @@
 use std::{
-    fs,
-    io::Read,
-    sync::{Arc, Mutex},
+    sync::{mpsc, Arc, Mutex},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
-use rpos::thread_logln;
+use rpos::{
+    channel::{Receiver, Sender},
+    thread_logln,
+};
 
 use crate::{
     calibrate::{
-        CalibrationData,
+        CalibrationData, CalibrationSet,
         JoystickChannel::{self, *},
     },
-    config::{store, ControlRole, ModelConfig, OutputLimits},
-    messages::{ActiveModelMsg, AdcRawMsg},
-    CALIBRATE_FILENAME,
+    config::{
+        store, ControlRole, CurveRef, FlightModeConfig, InputKind, InputSource, MixCondition,
+        MixLine, ModelConfig, OutputLimits, RateProfile, SpecialAction,
+    },
+    flight_mode::{self, select_mode, FlightModeTracker},
+    input_router::{nominal_range, InputRouter, RoutedInput, INPUT_STALE_TIMEOUT},
+    logical_switch::{switch_active, LogicalSwitchEngine, SwitchContext},
+    messages::{
+        ActiveModelMsg, AudioCueMsg, CalibrationUpdatedMsg, ElrsCommandMsg, FlightModeMsg,
+        InputFrameMsg, LogicalSwitchMsg, RadioInputMsg, SystemStatusMsg, TelemetryCommandMsg,
+        TelemetryMsg, TimerCommandMsg, TimerStateMsg, UiCommandMsg,
+    },
+    shutdown,
+    special_function::{ChannelLogger, SpecialFunctionEngine},
+    timer::TimerEngine,
 };
 
+/// How long shutdown waits for queued saves to reach the disk.
+const SHUTDOWN_FLUSH_TIMEOUT: Duration = Duration::from_secs(2);
+
+const MIX_CENTER: i32 = 5000;
+const MIX_HALF_RANGE: i32 = 5000;
+
+pub const MIXER_OUT_CHANNELS: usize = 16;
+
+/// One output frame in `OutputConfig::channel_order`. Values are 0..10000;
+/// slots past `count` are unmapped and held at center.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct MixerOutMsg {
-    pub thrust: u16,
-    pub direction: u16,
-    pub aileron: u16,
-    pub elevator: u16,
+    pub channels: [u16; MIXER_OUT_CHANNELS],
+    pub roles: [ControlRole; MIXER_OUT_CHANNELS],
+    pub count: u8,
 }
 
-fn cal_mixout(channel: JoystickChannel, raw: &AdcRawMsg, cal_data: &CalibrationData) -> u16 {
-    let channel_cal_info = &cal_data.channel_infos[channel as usize];
+impl Default for MixerOutMsg {
+    fn default() -> Self {
+        Self {
+            channels: [MIX_CENTER as u16; MIXER_OUT_CHANNELS],
+            roles: [ControlRole::Unknown; MIXER_OUT_CHANNELS],
+            count: 0,
+        }
+    }
+}
 
-    let raw_val = raw.value[channel_cal_info.index as usize]
-        .clamp(channel_cal_info.min, channel_cal_info.max) as i32;
+impl MixerOutMsg {
+    pub fn from_roles(order: &[ControlRole], mut value_of: impl FnMut(ControlRole) -> u16) -> Self {
+        let mut msg = Self::default();
+        for (slot, role) in order.iter().take(MIXER_OUT_CHANNELS).enumerate() {
+            msg.roles[slot] = *role;
+            msg.channels[slot] = value_of(*role);
+            msg.count = slot as u8 + 1;
+        }
+        msg
+    }
+
+    pub fn value_of(&self, role: ControlRole) -> Option<u16> {
+        self.roles[..self.count as usize]
+            .iter()
+            .position(|slot_role| *slot_role == role)
+            .map(|slot| self.channels[slot])
+    }
+
+    pub fn active_channels(&self) -> &[u16] {
+        &self.channels[..self.count as usize]
+    }
+}
 
-    let mut ret = (raw_val - channel_cal_info.min as i32) as u32 * 10000
-        / (channel_cal_info.max as i32 - channel_cal_info.min as i32) as u32;
+fn scale_axis(raw: i16, min: i16, max: i16) -> u16 {
+    let (low, high) = (min.min(max) as i32, min.max(max) as i32);
+    if high == low {
+        return MIX_CENTER as u16;
+    }
+    let raw_val = (raw as i32).clamp(low, high);
+    ((raw_val - low) as u32 * 10000 / (high - low) as u32) as u16
+}
+
+/// Piecewise linear through `points` (raw, 0..10000), ascending by raw and
+/// clamped to the first and last point.
+fn interpolate(points: &[(i16, u16)], raw: i16) -> u16 {
+    let (first, last) = (points[0], points[points.len() - 1]);
+    if raw <= first.0 {
+        return first.1;
+    }
+    if raw >= last.0 {
+        return last.1;
+    }
+    let segment = points
+        .windows(2)
+        .find(|segment| raw <= segment[1].0)
+        .unwrap();
+    let ((x0, y0), (x1, y1)) = (
+        (segment[0].0 as i32, segment[0].1 as i32),
+        (segment[1].0 as i32, segment[1].1 as i32),
+    );
+    if x1 == x0 {
+        return y1 as u16;
+    }
+    (y0 + (raw as i32 - x0) * (y1 - y0) / (x1 - x0)).clamp(0, 10000) as u16
+}
+
+/// Maps a raw axis through its lookup table when it has one, else through
+/// min/center/max so rest reads exactly center. Throttle, which does not
+/// spring back, and calibrations without a center stay linear over min..max.
+fn cal_mixout(channel: JoystickChannel, raw: i16, cal_data: &CalibrationData) -> u16 {
+    let self_centering = !matches!(channel, Thrust);
+    let channel_cal_info = &cal_data.channel_infos[channel as usize];
+    let (min, center, max) = (
+        channel_cal_info.min,
+        channel_cal_info.center,
+        channel_cal_info.max,
+    );
+
+    let mut ret = if channel_cal_info.points.len() >= 2 {
+        interpolate(&channel_cal_info.points, raw)
+    } else if self_centering && min < center && center < max {
+        // The deadband is cut out of both halves, so leaving it does not jump.
+        let low = center.saturating_sub(channel_cal_info.deadband).max(min);
+        let high = center.saturating_add(channel_cal_info.deadband).min(max);
+        interpolate(
+            &[
+                (min, 0),
+                (low, MIX_CENTER as u16),
+                (high, MIX_CENTER as u16),
+                (max, 10000),
+            ],
+            raw,
+        )
+    } else {
+        scale_axis(raw, min, max)
+    };
 
     if channel_cal_info.rev {
         ret = 10000 - ret;
     }
 
-    ret as u16
+    ret
+}
+
+fn calibration_channel(role: ControlRole) -> Option<JoystickChannel> {
+    match role {
+        ControlRole::Thrust => Some(Thrust),
+        ControlRole::Direction => Some(Direction),
+        ControlRole::Aileron => Some(Aileron),
+        ControlRole::Elevator => Some(Elevator),
+        _ => None,
+    }
+}
+
+/// Turns a routed input into 0..10000 through the calibration profile of the
+/// source it was read from, fallback sources included. Roles without a
+/// calibration use the source's nominal range.
+fn normalize_input(input: &RoutedInput, cal_data: Option<&CalibrationData>) -> u16 {
+    let value = match input.kind {
+        InputKind::Axis | InputKind::Trim => match (calibration_channel(input.role), cal_data) {
+            (Some(channel), Some(cal_data))
+                if cal_data.channel_infos.len() >= JoystickChannel::ITER.len() =>
+            {
+                cal_mixout(channel, input.raw, cal_data)
+            }
+            _ => {
+                let (min, max) = nominal_range(input.source);
+                scale_axis(input.raw, min, max)
+            }
+        },
+        InputKind::Switch => (input.raw.clamp(0, 2) * 5000) as u16,
+        InputKind::Button => (input.raw.clamp(0, 1) * 10000) as u16,
+    };
+
+    if input.reversed {
+        10000 - value
+    } else {
+        value
+    }
+}
+
+/// Axis the calibration of `source` found `role`'s stick on.
+fn calibrated_axis(
+    calibration: &CalibrationSet,
+    source: InputSource,
+    role: ControlRole,
+) -> Option<usize> {
+    let channel = calibration_channel(role)?;
+    let cal_data = calibration
+        .for_source(source)
+        .filter(|cal_data| cal_data.channel_infos.len() >= JoystickChannel::ITER.len())?;
+    Some(cal_data.channel_infos[channel as usize].index as usize)
+}
+
+/// Routes the model's inputs and brings them to 0..10000. Sticks are read
+/// from the axes their source's calibration found them on, failing over in
+/// the radio's `source_priority`. A mapped throttle with no fresh source
+/// reads as pulled low rather than centered.
+pub(crate) fn read_inputs(
+    router: &InputRouter,
+    model: &ModelConfig,
+    calibration: &CalibrationSet,
+    now: Instant,
+) -> Vec<(ControlRole, u16)> {
+    let routed = router.route(
+        &model.input_mapping,
+        &calibration.input().source_priority,
+        |source, role| calibrated_axis(calibration, source, role),
+        now,
+    );
+    let mut raw: Vec<(ControlRole, u16)> = routed
+        .iter()
+        .map(|input| {
+            let cal_data = calibration.for_source(input.source);
+            (input.role, normalize_input(input, cal_data))
+        })
+        .collect();
+    let thrust_mapped = model
+        .input_mapping
+        .channels
+        .iter()
+        .any(|channel| channel.role == ControlRole::Thrust);
+    if thrust_mapped && !raw.iter().any(|(role, _)| *role == ControlRole::Thrust) {
+        raw.push((ControlRole::Thrust, 0));
+    }
+    raw
+}
+
+fn input_of(inputs: &[(ControlRole, i32)], role: ControlRole) -> i32 {
+    inputs
+        .iter()
+        .find(|(input_role, _)| *input_role == role)
+        .map(|(_, value)| *value)
+        .unwrap_or(0)
+}
+
+fn apply_expo(value: i32, expo: i16) -> i32 {
+    let k = expo.clamp(-100, 100) as i64;
+    if k == 0 {
+        return value;
+    }
+
+    let half = MIX_HALF_RANGE as i64;
+    let cubic = |x: i64, k: i64| (x * (100 - k) + k * x * x * x / (half * half)) / 100;
+    let x = (value as i64).abs().min(half);
+    let y = if k > 0 {
+        cubic(x, k)
+    } else {
+        half - cubic(half - x, -k)
+    };
+    (y as i32) * value.signum()
+}
+
+fn interpolate_curve(value: i32, points: &[i16]) -> i32 {
+    if points.len() < 2 {
+        return value;
+    }
+
+    let segments = (points.len() - 1) as i64;
+    let span = 2 * MIX_HALF_RANGE as i64;
+    let pos =
+        (value.clamp(-MIX_HALF_RANGE, MIX_HALF_RANGE) as i64 + MIX_HALF_RANGE as i64) * segments;
+    let idx = (pos / span).min(segments - 1) as usize;
+    let frac = pos - idx as i64 * span;
+    let y0 = points[idx] as i64 * 5;
+    let y1 = points[idx + 1] as i64 * 5;
+    (y0 + (y1 - y0) * frac / span) as i32
+}
+
+fn apply_curve(
+    value: i32,
+    curve: CurveRef,
+    expo: i16,
+    curve_name: &str,
+    model: &ModelConfig,
+) -> i32 {
+    match curve {
+        CurveRef::Linear => value,
+        CurveRef::Expo => apply_expo(value, expo),
+        CurveRef::Custom => model
+            .mixer
+            .curves
+            .iter()
+            .find(|custom| custom.name == curve_name)
+            .map(|custom| interpolate_curve(value, &custom.points))
+            .unwrap_or(value),
+    }
+}
+
+/// Centers calibrated 0..10000 values and runs them through the model's input stage.
+fn apply_inputs(raw: &[(ControlRole, u16)], model: &ModelConfig) -> Vec<(ControlRole, i32)> {
+    raw.iter()
+        .map(|(role, value)| {
+            let centered = *value as i32 - MIX_CENTER;
+            let Some(input) = model.mixer.inputs.iter().find(|input| input.role == *role) else {
+                return (*role, centered);
+            };
+            let mut shaped = apply_expo(centered, input.expo);
+            shaped = apply_curve(shaped, input.curve, 0, &input.curve_name, model);
+            (*role, shaped * input.weight as i32 / 100)
+        })
+        .collect()
+}
+
+fn mode_profile<'a>(
+    model: &'a ModelConfig,
+    mode: Option<&FlightModeConfig>,
+) -> Option<&'a RateProfile> {
+    let name = mode.map(|mode| mode.profile.as_str())?;
+    model
+        .profiles
+        .iter()
+        .find(|profile| !name.is_empty() && profile.name == name)
+}
+
+/// Applies the flight mode's rate profile and trims on top of the input stage.
+/// Rates only change when the active flight mode names a profile.
+fn apply_flight_mode(
+    inputs: &[(ControlRole, i32)],
+    model: &ModelConfig,
+    mode: Option<&FlightModeConfig>,
+) -> Vec<(ControlRole, i32)> {
+    let profile = mode_profile(model, mode);
+    inputs
+        .iter()
+        .map(|(role, value)| {
+            let mut shaped = *value;
+            if let Some(profile) = profile {
+                let rate = match role {
+                    ControlRole::Aileron => Some(profile.roll_rate),
+                    ControlRole::Elevator => Some(profile.pitch_rate),
+                    ControlRole::Direction => Some(profile.yaw_rate),
+                    _ => None,
+                };
+                if let Some(rate) = rate {
+                    shaped = apply_expo(shaped, profile.expo_percent as i16) * rate as i32 / 100;
+                }
+            }
+            let trim: i32 = mode
+                .into_iter()
+                .flat_map(|mode| &mode.trims)
+                .filter(|trim| trim.role == *role)
+                .map(|trim| trim.value as i32 * 5)
+                .sum();
+            (*role, shaped + trim)
+        })
+        .collect()
+}
+
+fn line_in_mode(line: &MixLine, mode: Option<&FlightModeConfig>) -> bool {
+    line.flight_modes.is_empty() || mode.is_some_and(|mode| line.flight_modes.contains(&mode.name))
+}
+
+fn condition_active(
+    condition: &MixCondition,
+    inputs: &[(ControlRole, i32)],
+    switches: &[bool],
+) -> bool {
+    match condition {
+        MixCondition::SourceAbove { source, value } => {
+            input_of(inputs, *source) > *value as i32 * 5
+        }
+        MixCondition::SourceBelow { source, value } => {
+            input_of(inputs, *source) < *value as i32 * 5
+        }
+        MixCondition::Switch { switch } => switch_active(*switch, inputs, switches),
+    }
+}
+
+fn mix_lines(
+    role: ControlRole,
+    model: &ModelConfig,
+    inputs: &[(ControlRole, i32)],
+    switches: &[bool],
+    mode: Option<&FlightModeConfig>,
+) -> i32 {
+    let mut lines = model
+        .mixer
+        .mixes
+        .iter()
+        .filter(|line| line.output == role)
+        .peekable();
+    if lines.peek().is_none() {
+        return input_of(inputs, role);
+    }
+
+    lines
+        .filter(|line| {
+            line_in_mode(line, mode)
+                && line
+                    .condition
+                    .as_ref()
+                    .is_none_or(|condition| condition_active(condition, inputs, switches))
+        })
+        .map(|line| {
+            let source = input_of(inputs, line.source);
+            let shaped = apply_curve(source, line.curve, line.expo, &line.curve_name, model);
+            shaped * line.weight as i32 / 100 + line.offset as i32 * 5
+        })
+        .sum()
+}
+
+/// Full chain for one output role: input stage -> mix lines -> output curve/limits.
+fn mix_output(
+    role: ControlRole,
+    model: &ModelConfig,
+    inputs: &[(ControlRole, i32)],
+    switches: &[bool],
+    mode: Option<&FlightModeConfig>,
+) -> u16 {
+    let mixed = (MIX_CENTER + mix_lines(role, model, inputs, switches, mode)).clamp(0, 10000);
+    apply_output_profile(mixed as u16, model, role)
+}
+
+/// Output frame as it would be with only flight mode `mode_index` active.
+fn mix_frame(
+    model: &ModelConfig,
+    inputs: &[(ControlRole, i32)],
+    switches: &[bool],
+    mode_index: usize,
+) -> MixerOutMsg {
+    let mode = model.flight_modes.get(mode_index);
+    let inputs = apply_flight_mode(inputs, model, mode);
+    MixerOutMsg::from_roles(channel_order(model), |role| {
+        mix_output(role, model, &inputs, switches, mode)
+    })
+}
+
+fn blend_frames(
+    active: MixerOutMsg,
+    active_weight: i32,
+    previous: &MixerOutMsg,
+    previous_weight: i32,
+) -> MixerOutMsg {
+    let mut blended = active;
+    for (slot, value) in blended.channels[..active.count as usize]
+        .iter_mut()
+        .enumerate()
+    {
+        *value = flight_mode::blend(
+            *value as i32,
+            active_weight,
+            previous.channels[slot] as i32,
+            previous_weight,
+        ) as u16;
+    }
+    blended
+}
+
+pub(crate) fn channel_order(model: &ModelConfig) -> &[ControlRole] {
+    if model.output.channel_order.is_empty() {
+        &[
+            ControlRole::Aileron,
+            ControlRole::Elevator,
+            ControlRole::Thrust,
+            ControlRole::Direction,
+        ]
+    } else {
+        &model.output.channel_order
+    }
 }
 
 fn apply_output_profile(value: u16, model: &ModelConfig, role: ControlRole) -> u16 {
@@ -50,10 +479,16 @@ fn apply_output_profile(value: u16, model: &ModelConfig, role: ControlRole) -> u
         return value;
     };
 
-    let centered = value as i32 - 5000;
+    let centered = apply_curve(
+        value as i32 - MIX_CENTER,
+        output.curve,
+        output.expo,
+        &output.curve_name,
+        model,
+    );
     let weighted = centered * output.weight as i32 / 100;
     let offset = output.offset as i32 * 5;
-    let mut adjusted = 5000 + weighted + offset;
+    let mut adjusted = MIX_CENTER + weighted + offset;
     adjusted = apply_limits(adjusted, &output.limits);
     adjusted.clamp(0, 10000) as u16
 }
@@ -70,16 +505,250 @@ fn apply_limits(value: i32, limits: &OutputLimits) -> i32 {
     adjusted.clamp(low.min(high), low.max(high))
 }
 
-fn load_calibration() -> Option<CalibrationData> {
-    let mut toml_str = String::new();
-    if let Ok(mut file) = fs::File::open(CALIBRATE_FILENAME) {
-        file.read_to_string(&mut toml_str).unwrap();
-    } else {
-        thread_logln!("no joystick.toml found. please calibrate joysticks first!");
-        return None;
+/// Radio-side values logical switches can compare against next to the
+/// decoded sensors from the `telemetry` topic.
+fn status_values(status: &SystemStatusMsg) -> Vec<(String, i32)> {
+    vec![
+        (
+            "remote_battery".to_string(),
+            status.remote_battery_percent as i32,
+        ),
+        (
+            "aircraft_battery".to_string(),
+            status.aircraft_battery_percent as i32,
+        ),
+    ]
+}
+
+/// File work handed from the mixer callback to `run_jobs`, so the RC path
+/// never waits on storage.
+enum MixerJob {
+    SelectModel(String),
+    StartLog {
+        model_id: String,
+        channels: Vec<ControlRole>,
+    },
+    LogRow {
+        time_ms: u128,
+        values: Vec<u16>,
+    },
+    StopLog,
+    SaveTimers {
+        model_id: String,
+        values_ms: Vec<u64>,
+    },
+    /// Answers once every job queued before it is done.
+    Flush(mpsc::Sender<()>),
+}
+
+fn run_jobs(jobs: mpsc::Receiver<MixerJob>, active_model: Sender<ActiveModelMsg>) {
+    let mut logger: Option<ChannelLogger> = None;
+    for job in jobs {
+        match job {
+            MixerJob::SelectModel(id) => match store::set_active_model(&id) {
+                Ok(model) => active_model.send(ActiveModelMsg { model }),
+                Err(err) => {
+                    thread_logln!("special function: select model `{}` failed: {}", id, err)
+                }
+            },
+            MixerJob::StartLog { model_id, channels } => {
+                logger = ChannelLogger::create(&model_id, &channels)
+                    .inspect_err(|err| thread_logln!("channel log start failed: {}", err))
+                    .ok();
+            }
+            MixerJob::LogRow { time_ms, values } => {
+                if let Some(open) = logger.as_mut() {
+                    if let Err(err) = open.log(time_ms, &values) {
+                        thread_logln!("channel log write failed: {}", err);
+                        logger = None;
+                    }
+                }
+            }
+            MixerJob::StopLog => logger = None,
+            MixerJob::SaveTimers {
+                model_id,
+                values_ms,
+            } => {
+                if let Err(err) = store::save_timer_values(&model_id, &values_ms) {
+                    thread_logln!("timer save for `{}` failed: {}", model_id, err);
+                }
+            }
+            MixerJob::Flush(done) => {
+                let _ = done.send(());
+            }
+        }
     }
+}
 
-    Some(toml::from_str::<CalibrationData>(toml_str.as_str()).unwrap())
+/// Where one-shot special functions publish their effects.
+struct ActionSenders {
+    jobs: mpsc::Sender<MixerJob>,
+    elrs_cmd: Sender<ElrsCommandMsg>,
+    timer_cmd: Sender<TimerCommandMsg>,
+    telemetry_cmd: Sender<TelemetryCommandMsg>,
+    audio: Sender<AudioCueMsg>,
+    ui_cmd: Sender<UiCommandMsg>,
+}
+
+impl ActionSenders {
+    /// Queues `job` for the worker; jobs are dropped if it has stopped.
+    fn job(&self, job: MixerJob) {
+        let _ = self.jobs.send(job);
+    }
+
+    fn dispatch(&self, action: &SpecialAction) {
+        match action {
+            SpecialAction::ResetTimer { timer } => {
+                self.timer_cmd.send(TimerCommandMsg::Reset(*timer))
+            }
+            SpecialAction::SelectModel { model } => self.job(MixerJob::SelectModel(model.clone())),
+            SpecialAction::PlaySound { sound } => {
+                self.audio.send(AudioCueMsg::Sound(sound.clone()))
+            }
+            SpecialAction::Haptic { duration_ms } => self.audio.send(AudioCueMsg::Haptic {
+                duration_ms: *duration_ms,
+            }),
+            SpecialAction::ElrsBind => self.elrs_cmd.send(ElrsCommandMsg::Bind),
+            SpecialAction::ResetTelemetry => {
+                self.telemetry_cmd.send(TelemetryCommandMsg::ResetStats)
+            }
+            SpecialAction::OpenPage { page } => self.ui_cmd.send(UiCommandMsg::OpenPage(*page)),
+            SpecialAction::OverrideChannel { .. } | SpecialAction::Logging { .. } => {}
+        }
+    }
+}
+
+/// State carried between mixer cycles.
+struct MixerRuntime {
+    router: InputRouter,
+    switches: LogicalSwitchEngine,
+    flight_mode: FlightModeTracker,
+    special_functions: SpecialFunctionEngine,
+    logging: bool,
+    last_log_row: Option<Instant>,
+    timers: TimerEngine,
+    timer_cmd_rx: Receiver<TimerCommandMsg>,
+    last_out: MixerOutMsg,
+}
+
+impl MixerRuntime {
+    /// Applies channel overrides to `frame`, keeps the channel log going and
+    /// dispatches one-shot actions.
+    fn apply_special_functions(
+        &mut self,
+        model: &ModelConfig,
+        inputs: &[(ControlRole, i32)],
+        switches: &[bool],
+        frame: &mut MixerOutMsg,
+        senders: &ActionSenders,
+        now: Instant,
+    ) {
+        let effects = self
+            .special_functions
+            .evaluate(&model.special_functions, inputs, switches);
+
+        for (slot, value) in effects.overrides {
+            if slot < frame.count as usize {
+                frame.channels[slot] = value;
+            }
+        }
+
+        match effects.logging {
+            Some(interval) => {
+                if !self.logging {
+                    self.logging = true;
+                    self.last_log_row = None;
+                    senders.job(MixerJob::StartLog {
+                        model_id: model.id.clone(),
+                        channels: channel_order(model).to_vec(),
+                    });
+                }
+                if self
+                    .last_log_row
+                    .is_none_or(|last| now.saturating_duration_since(last) >= interval)
+                {
+                    self.last_log_row = Some(now);
+                    senders.job(MixerJob::LogRow {
+                        time_ms: SystemTime::now()
+                            .duration_since(UNIX_EPOCH)
+                            .unwrap_or_default()
+                            .as_millis(),
+                        values: frame.active_channels().to_vec(),
+                    });
+                }
+            }
+            None if self.logging => {
+                self.logging = false;
+                senders.job(MixerJob::StopLog);
+            }
+            None => {}
+        }
+
+        for action in effects.triggered {
+            senders.dispatch(action);
+        }
+    }
+
+    /// Advances the model timers, handles reset commands, announces alerts
+    /// and queues persistent timer values for saving when they stop.
+    fn run_timers(
+        &mut self,
+        model: &ModelConfig,
+        stored_timers: &[u64],
+        inputs: &[(ControlRole, i32)],
+        switches: &[bool],
+        senders: &ActionSenders,
+        now: Instant,
+    ) -> TimerStateMsg {
+        if let Some((model_id, values_ms)) = self.timers.sync_model(model, stored_timers) {
+            senders.job(MixerJob::SaveTimers {
+                model_id,
+                values_ms,
+            });
+        }
+        while let Some(cmd) = self.timer_cmd_rx.try_read() {
+            match cmd {
+                TimerCommandMsg::Reset(index) => self.timers.reset(&model.timers, index as usize),
+            }
+        }
+
+        for alert in self.timers.tick(&model.timers, inputs, switches, now) {
+            senders.audio.send(AudioCueMsg::Sound(alert.sound_name()));
+        }
+        if let Some(values_ms) = self.timers.take_persist() {
+            senders.job(MixerJob::SaveTimers {
+                model_id: model.id.clone(),
+                values_ms,
+            });
+        }
+        self.timers.state(&model.timers)
+    }
+}
+
+/// The active model and the timer values saved for it.
+struct ActiveModel {
+    config: ModelConfig,
+    timer_values_ms: Vec<u64>,
+}
+
+impl ActiveModel {
+    fn load(config: ModelConfig) -> Self {
+        let timer_values_ms = store::load_model_state(&config.id)
+            .inspect_err(|err| thread_logln!("state of `{}` not loaded: {}", config.id, err))
+            .unwrap_or_default()
+            .timer_values_ms;
+        Self {
+            config,
+            timer_values_ms,
+        }
+    }
+}
+
+/// Calibration profiles the radio and `model` select. Sticks without a
+/// loadable profile run over their source's nominal range.
+fn load_calibration_set(model: &ModelConfig) -> CalibrationSet {
+    let radio = store::load_radio_config().unwrap_or_default();
+    CalibrationSet::load(radio.input, model.calibration.clone())
 }
 
 fn load_initial_model() -> ModelConfig {
@@ -90,49 +759,209 @@ fn load_initial_model() -> ModelConfig {
 }
 
 fn mixer_main(_argc: u32, _argv: *const &str) {
-    let Some(cal_data) = load_calibration() else {
-        return;
+    let runtime = Arc::new(Mutex::new(MixerRuntime {
+        router: InputRouter::new(INPUT_STALE_TIMEOUT),
+        switches: LogicalSwitchEngine::new(),
+        flight_mode: FlightModeTracker::new(),
+        special_functions: SpecialFunctionEngine::new(),
+        logging: false,
+        last_log_row: None,
+        timers: TimerEngine::new(),
+        timer_cmd_rx: rpos::msg::get_new_rx_of_message("timer_cmd").unwrap(),
+        last_out: MixerOutMsg::default(),
+    }));
+    let (jobs, job_rx) = mpsc::channel();
+    let active_model_tx = rpos::msg::get_new_tx_of_message("active_model").unwrap();
+    std::thread::spawn(move || run_jobs(job_rx, active_model_tx));
+    {
+        let runtime = runtime.clone();
+        let jobs = jobs.clone();
+        shutdown::on_shutdown(move || {
+            let dirty = runtime
+                .lock()
+                .ok()
+                .and_then(|mut runtime| runtime.timers.take_dirty());
+            if let Some((model_id, values_ms)) = dirty {
+                let _ = jobs.send(MixerJob::SaveTimers {
+                    model_id,
+                    values_ms,
+                });
+            }
+            let (done_tx, done_rx) = mpsc::channel();
+            if jobs.send(MixerJob::Flush(done_tx)).is_ok() {
+                let _ = done_rx.recv_timeout(SHUTDOWN_FLUSH_TIMEOUT);
+            }
+        });
+    }
+    let senders = ActionSenders {
+        jobs,
+        elrs_cmd: rpos::msg::get_new_tx_of_message("elrs_cmd").unwrap(),
+        timer_cmd: rpos::msg::get_new_tx_of_message("timer_cmd").unwrap(),
+        telemetry_cmd: rpos::msg::get_new_tx_of_message("telemetry_cmd").unwrap(),
+        audio: rpos::msg::get_new_tx_of_message("audio_cue").unwrap(),
+        ui_cmd: rpos::msg::get_new_tx_of_message("ui_cmd").unwrap(),
     };
 
-    let rx = rpos::msg::get_new_rx_of_message::<AdcRawMsg>("adc_raw").unwrap();
+    let rx = rpos::msg::get_new_rx_of_message::<InputFrameMsg>("input_frame").unwrap();
     let tx = rpos::msg::get_new_tx_of_message::<MixerOutMsg>("mixer_out").unwrap();
-    let active_model = Arc::new(Mutex::new(load_initial_model()));
+    let switches_tx =
+        rpos::msg::get_new_tx_of_message::<LogicalSwitchMsg>("logical_switches").unwrap();
+    let flight_mode_tx = rpos::msg::get_new_tx_of_message::<FlightModeMsg>("flight_mode").unwrap();
+    let timers_tx = rpos::msg::get_new_tx_of_message::<TimerStateMsg>("timers").unwrap();
+    let status = Arc::new(Mutex::new(Vec::new()));
+    let sensors = Arc::new(Mutex::new(Vec::new()));
+
+    if let Some(status_rx) = rpos::msg::get_new_rx_of_message::<SystemStatusMsg>("system_status") {
+        let status_for_updates = status.clone();
+        status_rx.register_callback("mixer_status", move |msg| {
+            if let Ok(mut values) = status_for_updates.lock() {
+                *values = status_values(msg);
+            }
+        });
+    }
+    if let Some(telemetry_rx) = rpos::msg::get_new_rx_of_message::<TelemetryMsg>("telemetry") {
+        let sensors_for_updates = sensors.clone();
+        telemetry_rx.register_callback("mixer_telemetry", move |msg| {
+            if let Ok(mut values) = sensors_for_updates.lock() {
+                *values = msg
+                    .sensors
+                    .iter()
+                    .filter_map(|sensor| sensor.value.map(|value| (sensor.key.clone(), value)))
+                    .collect();
+            }
+        });
+    }
+    let active_model = Arc::new(Mutex::new(ActiveModel::load(load_initial_model())));
+    let calibration = Arc::new(Mutex::new(load_calibration_set(
+        &active_model.lock().unwrap().config,
+    )));
 
     if let Some(active_model_rx) =
         rpos::msg::get_new_rx_of_message::<ActiveModelMsg>("active_model")
     {
         let active_model_for_updates = active_model.clone();
+        let calibration_for_updates = calibration.clone();
         active_model_rx.register_callback("mixer_active_model", move |msg| {
-            if let Ok(mut current_model) = active_model_for_updates.lock() {
-                *current_model = msg.model.clone();
+            let loaded = ActiveModel::load(msg.model.clone());
+            let calibration_changed = match active_model_for_updates.lock() {
+                Ok(mut current_model) => {
+                    let changed = current_model.config.calibration != loaded.config.calibration;
+                    *current_model = loaded;
+                    changed
+                }
+                Err(_) => true,
+            };
+            // The model may select other calibration profiles; the files
+            // themselves are only read again on `calibration_updated`.
+            if calibration_changed {
+                let reloaded = load_calibration_set(&msg.model);
+                if let Ok(mut current) = calibration_for_updates.lock() {
+                    *current = reloaded;
+                }
+            }
+        });
+    }
+    if let Some(updated_rx) =
+        rpos::msg::get_new_rx_of_message::<CalibrationUpdatedMsg>("calibration_updated")
+    {
+        let active_model_for_updates = active_model.clone();
+        let calibration_for_updates = calibration.clone();
+        updated_rx.register_callback("mixer_calibration", move |msg| {
+            thread_logln!("reloading calibration after `{}` changed", msg.profile);
+            let model = active_model_for_updates.lock().unwrap().config.clone();
+            let reloaded = load_calibration_set(&model);
+            if let Ok(mut current) = calibration_for_updates.lock() {
+                *current = reloaded;
+            }
+        });
+    }
+    if let Some(input_rx) = rpos::msg::get_new_rx_of_message::<RadioInputMsg>("radio_input") {
+        let active_model_for_updates = active_model.clone();
+        let calibration_for_updates = calibration.clone();
+        input_rx.register_callback("mixer_radio_input", move |msg| {
+            thread_logln!("radio input settings changed, rerouting");
+            let model = active_model_for_updates.lock().unwrap().config.clone();
+            let reloaded = CalibrationSet::load(msg.input.clone(), model.calibration);
+            if let Ok(mut current) = calibration_for_updates.lock() {
+                *current = reloaded;
             }
         });
     }
 
     rx.register_callback("mixer_callback", move |x| {
-        let current_model = active_model.lock().unwrap().clone();
-        let mixer_out = MixerOutMsg {
-            thrust: apply_output_profile(
-                cal_mixout(Thrust, x, &cal_data),
-                &current_model,
-                ControlRole::Thrust,
-            ),
-            direction: apply_output_profile(
-                cal_mixout(Direction, x, &cal_data),
-                &current_model,
-                ControlRole::Direction,
-            ),
-            aileron: apply_output_profile(
-                cal_mixout(Aileron, x, &cal_data),
-                &current_model,
-                ControlRole::Aileron,
-            ),
-            elevator: apply_output_profile(
-                cal_mixout(Elevator, x, &cal_data),
-                &current_model,
-                ControlRole::Elevator,
-            ),
+        let now = Instant::now();
+        let (current_model, stored_timers) = {
+            let active = active_model.lock().unwrap();
+            (active.config.clone(), active.timer_values_ms.clone())
         };
+        let mut runtime = runtime.lock().unwrap();
+        runtime.router.update(x.clone(), now);
+        let raw = read_inputs(
+            &runtime.router,
+            &current_model,
+            &calibration.lock().unwrap(),
+            now,
+        );
+        let inputs = apply_inputs(&raw, &current_model);
+
+        let channels: Vec<i32> = runtime
+            .last_out
+            .active_channels()
+            .iter()
+            .map(|value| *value as i32 - MIX_CENTER)
+            .collect();
+        let mut telemetry = sensors.lock().unwrap().clone();
+        telemetry.extend(status.lock().unwrap().iter().cloned());
+        let context = SwitchContext {
+            inputs: &inputs,
+            channels: &channels,
+            telemetry: &telemetry,
+        };
+        runtime.switches.sync_model(&current_model);
+        let switches = runtime
+            .switches
+            .evaluate(&current_model.logical_switches, &context, now)
+            .to_vec();
+
+        let modes = &current_model.flight_modes;
+        runtime
+            .flight_mode
+            .update(select_mode(modes, &inputs, &switches), now);
+        let active_mode = runtime.flight_mode.active();
+        let (active_weight, fading) = runtime.flight_mode.weights(modes, now);
+        let mut mixer_out = mix_frame(&current_model, &inputs, &switches, active_mode);
+        if let Some((previous_mode, previous_weight)) = fading {
+            let previous_out = mix_frame(&current_model, &inputs, &switches, previous_mode);
+            mixer_out = blend_frames(mixer_out, active_weight, &previous_out, previous_weight);
+        }
+        runtime.apply_special_functions(
+            &current_model,
+            &inputs,
+            &switches,
+            &mut mixer_out,
+            &senders,
+            now,
+        );
+        let timers = runtime.run_timers(
+            &current_model,
+            &stored_timers,
+            &inputs,
+            &switches,
+            &senders,
+            now,
+        );
+
+        runtime.last_out = mixer_out;
+        switches_tx.send(LogicalSwitchMsg { states: switches });
+        flight_mode_tx.send(FlightModeMsg {
+            index: active_mode as u8,
+            name: modes
+                .get(active_mode)
+                .map(|mode| mode.name.clone())
+                .unwrap_or_default(),
+            fading: fading.is_some(),
+        });
+        timers_tx.send(timers);
         tx.send(mixer_out);
     });
 }
@@ -140,12 +969,18 @@ fn mixer_main(_argc: u32, _argv: *const &str) {
 #[rpos::ctor::ctor]
 fn register() {
     rpos::msg::add_message::<MixerOutMsg>("mixer_out");
+    rpos::msg::add_message::<LogicalSwitchMsg>("logical_switches");
+    rpos::msg::add_message::<FlightModeMsg>("flight_mode");
+    rpos::msg::add_message::<TimerStateMsg>("timers");
     rpos::module::Module::register("mixer", mixer_main);
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::calibrate::ChannelInfo;
+    use crate::{
+        calibrate::ChannelInfo,
+        config::{FlightModeTrim, InputConfig, MixerInput, ModelCalibration, SwitchRef},
+    };
 
     use super::*;
     use rand::prelude::*;
@@ -154,9 +989,10 @@ mod tests {
     fn test_cal_mixout() {
         let mut rng = thread_rng();
         let mut get_random_channel_value = || rng.gen_range(300..1400) as i16;
-        let mut adc_raw = AdcRawMsg {
-            value: [500, 100, 1600, get_random_channel_value()],
-        };
+        let mut adc_raw = InputFrameMsg::from_axes(
+            InputSource::Adc,
+            vec![500, 100, 1600, get_random_channel_value()],
+        );
         let mut cal_data = CalibrationData {
             channel_infos: [
                 ChannelInfo {
@@ -164,28 +1000,40 @@ mod tests {
                     index: 0,
                     min: 200,
                     max: 1500,
+                    center: 850,
+                    deadband: 0,
                     rev: false,
+                    points: Vec::new(),
                 },
                 ChannelInfo {
                     name: "direction".to_string(),
                     index: 1,
                     min: 200,
                     max: 1500,
+                    center: 850,
+                    deadband: 0,
                     rev: false,
+                    points: Vec::new(),
                 },
                 ChannelInfo {
                     name: "aliron".to_string(),
                     index: 2,
                     min: 200,
                     max: 1500,
+                    center: 850,
+                    deadband: 0,
                     rev: false,
+                    points: Vec::new(),
                 },
                 ChannelInfo {
                     name: "ele".to_string(),
                     index: 3,
                     min: 200,
                     max: 1500,
+                    center: 850,
+                    deadband: 0,
                     rev: false,
+                    points: Vec::new(),
                 },
             ]
             .to_vec(),
@@ -193,28 +1041,80 @@ mod tests {
         };
 
         assert_eq!(
-            cal_mixout(JoystickChannel::Thrust, &adc_raw, &cal_data),
+            cal_mixout(JoystickChannel::Thrust, adc_raw.axis(0), &cal_data),
             ((500 - 200) as u32 * 10000 / (1500 - 200)) as u16
         );
         assert_eq!(
-            cal_mixout(JoystickChannel::Direction, &adc_raw, &cal_data),
+            cal_mixout(JoystickChannel::Direction, adc_raw.axis(1), &cal_data),
             ((200 - 200) as u32 * 10000 / (1500 - 200)) as u16
         );
         assert_eq!(
-            cal_mixout(JoystickChannel::Aileron, &adc_raw, &cal_data),
+            cal_mixout(JoystickChannel::Aileron, adc_raw.axis(2), &cal_data),
             ((1500 - 200) as u32 * 10000 / (1500 - 200)) as u16
         );
 
         for _ in 0..1000 {
-            assert!(cal_mixout(JoystickChannel::Elevator, &adc_raw, &cal_data) <= 10000);
-            adc_raw.value[3] = get_random_channel_value();
+            assert!(cal_mixout(JoystickChannel::Elevator, adc_raw.axis(3), &cal_data) <= 10000);
+            adc_raw.axes[3] = get_random_channel_value();
         }
 
         cal_data.channel_infos[0].rev = true;
         assert_eq!(
-            cal_mixout(JoystickChannel::Thrust, &adc_raw, &cal_data),
+            cal_mixout(JoystickChannel::Thrust, adc_raw.axis(0), &cal_data),
             10000 - ((500 - 200) as u32 * 10000 / (1500 - 200)) as u16
         );
+
+        cal_data.channel_infos[1].deadband = 20;
+        assert_eq!(cal_mixout(JoystickChannel::Direction, 868, &cal_data), 5000);
+        assert_eq!(cal_mixout(JoystickChannel::Direction, 871, &cal_data), 5007);
+    }
+
+    #[test]
+    fn test_cal_mixout_three_point_and_table() {
+        let info = ChannelInfo {
+            name: "Thrust".to_string(),
+            index: 0,
+            min: 100,
+            max: 2000,
+            center: 1200,
+            deadband: 0,
+            rev: false,
+            points: Vec::new(),
+        };
+        let mut cal_data = CalibrationData {
+            channel_infos: vec![info.clone(), info],
+            channel_indexs: vec![0, 1],
+        };
+        // Off-center gimbal: rest is exact, each half scales on its own.
+        assert_eq!(
+            cal_mixout(JoystickChannel::Direction, 1200, &cal_data),
+            5000
+        );
+        assert_eq!(cal_mixout(JoystickChannel::Direction, 650, &cal_data), 2500);
+        assert_eq!(
+            cal_mixout(JoystickChannel::Direction, 1600, &cal_data),
+            7500
+        );
+        assert_eq!(
+            cal_mixout(JoystickChannel::Direction, 2100, &cal_data),
+            10000
+        );
+
+        // Throttle has no rest point, so a stored center is ignored.
+        assert_eq!(cal_mixout(JoystickChannel::Thrust, 1200, &cal_data), 5789);
+        assert_eq!(cal_mixout(JoystickChannel::Thrust, 650, &cal_data), 2894);
+
+        // Without a center the old linear mapping applies.
+        cal_data.channel_infos[1].center = 0;
+        assert_eq!(
+            cal_mixout(JoystickChannel::Direction, 1200, &cal_data),
+            5789
+        );
+
+        cal_data.channel_infos[0].points = vec![(100, 0), (300, 2500), (1200, 5000), (2000, 10000)];
+        assert_eq!(cal_mixout(JoystickChannel::Thrust, 200, &cal_data), 1250);
+        assert_eq!(cal_mixout(JoystickChannel::Thrust, 750, &cal_data), 3750);
+        assert_eq!(cal_mixout(JoystickChannel::Thrust, 0, &cal_data), 0);
     }
 
     #[test]
@@ -235,4 +1135,413 @@ mod tests {
         assert!(value <= 10000);
         assert_ne!(value, 7000);
     }
+
+    #[test]
+    fn test_apply_expo_keeps_endpoints_and_softens_center() {
+        assert_eq!(apply_expo(0, 40), 0);
+        assert_eq!(apply_expo(5000, 40), 5000);
+        assert_eq!(apply_expo(-5000, 40), -5000);
+        assert!(apply_expo(1000, 40) < 1000);
+        assert!(apply_expo(1000, -40) > 1000);
+        assert_eq!(apply_expo(-1000, 40), -apply_expo(1000, 40));
+    }
+
+    #[test]
+    fn test_interpolate_custom_curve() {
+        let points = [-1000, 0, 1000];
+        assert_eq!(interpolate_curve(-5000, &points), -5000);
+        assert_eq!(interpolate_curve(0, &points), 0);
+        assert_eq!(interpolate_curve(2500, &points), 2500);
+        assert_eq!(interpolate_curve(5000, &points), 5000);
+
+        let flat_top = [0, 500, 500];
+        assert_eq!(interpolate_curve(5000, &flat_top), 2500);
+        assert_eq!(interpolate_curve(-2500, &flat_top), 1250);
+    }
+
+    #[test]
+    fn test_mix_lines_sum_into_one_output() {
+        let mut model = ModelConfig::default();
+        model.mixer.mixes = vec![
+            MixLine::new(ControlRole::Direction, ControlRole::Direction),
+            MixLine {
+                weight: 30,
+                ..MixLine::new(ControlRole::Direction, ControlRole::Aileron)
+            },
+        ];
+
+        let inputs = apply_inputs(
+            &[
+                (ControlRole::Direction, 6000),
+                (ControlRole::Aileron, 10000),
+                (ControlRole::Elevator, 7000),
+            ],
+            &model,
+        );
+        assert_eq!(
+            mix_output(ControlRole::Direction, &model, &inputs, &[], None),
+            5000 + 1000 + 1500
+        );
+        assert_eq!(
+            mix_output(ControlRole::Elevator, &model, &inputs, &[], None),
+            7000
+        );
+        assert_eq!(
+            mix_output(ControlRole::Aileron, &model, &inputs, &[], None),
+            10000
+        );
+    }
+
+    #[test]
+    fn test_mix_line_condition_gates_contribution() {
+        let mut model = ModelConfig::default();
+        model.mixer.mixes = vec![MixLine {
+            condition: Some(MixCondition::SourceAbove {
+                source: ControlRole::Thrust,
+                value: 0,
+            }),
+            ..MixLine::new(ControlRole::Elevator, ControlRole::Elevator)
+        }];
+
+        let low = apply_inputs(
+            &[(ControlRole::Thrust, 2000), (ControlRole::Elevator, 8000)],
+            &model,
+        );
+        let high = apply_inputs(
+            &[(ControlRole::Thrust, 8000), (ControlRole::Elevator, 8000)],
+            &model,
+        );
+        assert_eq!(
+            mix_output(ControlRole::Elevator, &model, &low, &[], None),
+            5000
+        );
+        assert_eq!(
+            mix_output(ControlRole::Elevator, &model, &high, &[], None),
+            8000
+        );
+    }
+
+    #[test]
+    fn test_mix_line_gated_by_logical_switch() {
+        let mut model = ModelConfig::default();
+        model.mixer.mixes = vec![MixLine {
+            condition: Some(MixCondition::Switch {
+                switch: SwitchRef::Logical(1),
+            }),
+            ..MixLine::new(ControlRole::Elevator, ControlRole::Elevator)
+        }];
+        let inputs = apply_inputs(&[(ControlRole::Elevator, 8000)], &model);
+
+        assert_eq!(
+            mix_output(ControlRole::Elevator, &model, &inputs, &[true, false], None),
+            5000
+        );
+        assert_eq!(
+            mix_output(ControlRole::Elevator, &model, &inputs, &[false, true], None),
+            8000
+        );
+    }
+
+    #[test]
+    fn test_input_stage_applies_weight_and_expo() {
+        let mut model = ModelConfig::default();
+        model.mixer.inputs = vec![MixerInput {
+            role: ControlRole::Aileron,
+            weight: 50,
+            expo: 30,
+            curve: CurveRef::Linear,
+            curve_name: String::new(),
+        }];
+
+        let inputs = apply_inputs(&[(ControlRole::Aileron, 10000)], &model);
+        assert_eq!(input_of(&inputs, ControlRole::Aileron), 2500);
+        let inputs = apply_inputs(&[(ControlRole::Aileron, 6000)], &model);
+        assert!(input_of(&inputs, ControlRole::Aileron) < 500);
+    }
+
+    #[test]
+    fn test_mixer_out_follows_channel_order() {
+        let mut model = ModelConfig::default();
+        model.output.channel_order =
+            vec![ControlRole::Thrust, ControlRole::Aileron, ControlRole::Arm];
+        model.mixer.mixes = vec![MixLine {
+            offset: 1000,
+            ..MixLine::new(ControlRole::Arm, ControlRole::Unknown)
+        }];
+
+        let inputs = apply_inputs(
+            &[(ControlRole::Thrust, 1000), (ControlRole::Aileron, 9000)],
+            &model,
+        );
+        let msg = MixerOutMsg::from_roles(channel_order(&model), |role| {
+            mix_output(role, &model, &inputs, &[], None)
+        });
+
+        assert_eq!(msg.count, 3);
+        assert_eq!(msg.active_channels(), &[1000, 9000, 10000]);
+        assert_eq!(msg.value_of(ControlRole::Arm), Some(10000));
+        assert_eq!(msg.value_of(ControlRole::Elevator), None);
+        assert_eq!(msg.channels[3], 5000);
+    }
+
+    #[test]
+    fn test_flight_mode_selects_rates_trims_and_mix_lines() {
+        let mut model = ModelConfig::default();
+        model.output.channel_order = vec![ControlRole::Aileron, ControlRole::Elevator];
+        model.profiles = vec![
+            RateProfile {
+                name: "normal".to_string(),
+                ..RateProfile::default()
+            },
+            RateProfile {
+                name: "landing".to_string(),
+                roll_rate: 50,
+                ..RateProfile::default()
+            },
+        ];
+        model.flight_modes = vec![
+            FlightModeConfig {
+                name: "normal".to_string(),
+                switch: None,
+                fade_in_ms: 0,
+                fade_out_ms: 0,
+                profile: String::new(),
+                trims: Vec::new(),
+            },
+            FlightModeConfig {
+                name: "landing".to_string(),
+                switch: Some(SwitchRef::Input(ControlRole::Aux1)),
+                fade_in_ms: 0,
+                fade_out_ms: 0,
+                profile: "landing".to_string(),
+                trims: vec![FlightModeTrim {
+                    role: ControlRole::Elevator,
+                    value: 100,
+                }],
+            },
+        ];
+        model.mixer.mixes = vec![
+            MixLine::new(ControlRole::Elevator, ControlRole::Elevator),
+            MixLine {
+                weight: 50,
+                flight_modes: vec!["landing".to_string()],
+                ..MixLine::new(ControlRole::Elevator, ControlRole::Aileron)
+            },
+        ];
+
+        let inputs = apply_inputs(
+            &[(ControlRole::Aileron, 9000), (ControlRole::Elevator, 5000)],
+            &model,
+        );
+        let normal = mix_frame(&model, &inputs, &[], 0);
+        assert_eq!(normal.active_channels(), &[9000, 5000]);
+
+        let landing = mix_frame(&model, &inputs, &[], 1);
+        assert_eq!(landing.active_channels(), &[7000, 5000 + 500 + 1000]);
+
+        let halfway = blend_frames(landing, 500, &normal, 500);
+        assert_eq!(halfway.active_channels(), &[8000, 5750]);
+
+        model.flight_modes.clear();
+        let without_modes = mix_frame(&model, &inputs, &[], 0);
+        assert_eq!(
+            without_modes.active_channels(),
+            &[9000, 5000],
+            "profiles need a flight mode to select them"
+        );
+    }
+
+    #[test]
+    fn test_normalize_input_uses_source_calibration() {
+        let cal_data = CalibrationData {
+            channel_infos: JoystickChannel::STRS
+                .iter()
+                .enumerate()
+                .map(|(index, name)| ChannelInfo {
+                    name: name.to_string(),
+                    index: index as u8,
+                    min: 100,
+                    max: 300,
+                    center: 200,
+                    deadband: 0,
+                    rev: false,
+                    points: Vec::new(),
+                })
+                .collect(),
+            channel_indexs: vec![0, 1, 2, 3],
+        };
+        let mut input = RoutedInput {
+            role: ControlRole::Aileron,
+            source: InputSource::Crsf,
+            kind: InputKind::Axis,
+            raw: 200,
+            reversed: false,
+            primary: true,
+        };
+        assert_eq!(normalize_input(&input, Some(&cal_data)), 5000);
+
+        // A fallback source is calibrated too; without a profile it runs
+        // over its nominal range.
+        input.primary = false;
+        input.raw = 250;
+        assert_eq!(normalize_input(&input, Some(&cal_data)), 7500);
+        input.raw = 992;
+        assert_eq!(normalize_input(&input, None), 5003);
+
+        input.kind = InputKind::Switch;
+        input.raw = 2;
+        input.reversed = true;
+        assert_eq!(normalize_input(&input, Some(&cal_data)), 0);
+    }
+
+    fn bundled_quad() -> ModelConfig {
+        toml::from_str(include_str!("../models/quad_x.toml")).unwrap()
+    }
+
+    /// The bundled `joystick.toml` as the radio's default profile.
+    fn bundled_calibration() -> CalibrationSet {
+        let cal_data = toml::from_str(include_str!("../joystick.toml")).unwrap();
+        CalibrationSet::with_profiles(
+            InputConfig::default(),
+            ModelCalibration::default(),
+            vec![("joystick.toml".to_string(), cal_data)],
+        )
+    }
+
+    #[test]
+    fn test_bundled_calibration_picks_stick_axes() {
+        let model = bundled_quad();
+        let calibration = bundled_calibration();
+        let now = Instant::now();
+        let mut router = InputRouter::new(INPUT_STALE_TIMEOUT);
+        router.update(
+            InputFrameMsg::from_axes(InputSource::Adc, vec![1000, 4095, 2047, 0]),
+            now,
+        );
+
+        // Thrust is on axis 1 and Direction, reversed, on axis 0, whatever
+        // the model maps them to.
+        let raw = read_inputs(&router, &model, &calibration, now);
+        assert_eq!(
+            raw,
+            vec![
+                (ControlRole::Thrust, 10000),
+                (ControlRole::Direction, 7558),
+                (ControlRole::Aileron, 5002),
+                (ControlRole::Elevator, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lost_throttle_reads_low() {
+        let model = bundled_quad();
+        let calibration = bundled_calibration();
+        let start = Instant::now();
+        let mut router = InputRouter::new(INPUT_STALE_TIMEOUT);
+        router.update(
+            InputFrameMsg::from_axes(InputSource::Adc, vec![2047; 4]),
+            start,
+        );
+
+        let later = start + INPUT_STALE_TIMEOUT * 2;
+        let raw = read_inputs(&router, &model, &calibration, later);
+        assert_eq!(raw, vec![(ControlRole::Thrust, 0)]);
+        let inputs = apply_inputs(&raw, &model);
+        let frame = mix_frame(&model, &inputs, &[], 0);
+        assert_eq!(frame.value_of(ControlRole::Thrust), Some(0));
+        assert_eq!(frame.value_of(ControlRole::Aileron), Some(5000));
+    }
+
+    #[test]
+    fn test_reloaded_radio_input_reroutes() {
+        let _guard = store::TestCwdGuard::enter();
+        let mut model = bundled_quad();
+        for channel in &mut model.input_mapping.channels {
+            channel.source = InputSource::Unknown;
+        }
+        let now = Instant::now();
+        let mut router = InputRouter::new(INPUT_STALE_TIMEOUT);
+        router.update(InputFrameMsg::from_axes(InputSource::Adc, vec![0; 4]), now);
+        router.update(
+            InputFrameMsg::from_axes(InputSource::Crsf, vec![1811; 4]),
+            now,
+        );
+
+        let calibration = CalibrationSet::load(InputConfig::default(), model.calibration.clone());
+        let raw = read_inputs(&router, &model, &calibration, now);
+        assert_eq!(raw[0], (ControlRole::Thrust, 0));
+
+        // As the `radio_input` callback rebuilds it after an edited radio.toml.
+        let input = InputConfig {
+            source_priority: vec![InputSource::Crsf, InputSource::Adc],
+            ..InputConfig::default()
+        };
+        let calibration = CalibrationSet::load(input, model.calibration.clone());
+        let raw = read_inputs(&router, &model, &calibration, now);
+        assert_eq!(raw[0], (ControlRole::Thrust, 10000));
+    }
+
+    #[test]
+    fn test_failover_uses_fallback_source_calibration() {
+        let mut model = bundled_quad();
+        for channel in &mut model.input_mapping.channels {
+            channel.source = InputSource::Stm32Serial;
+        }
+        let ads = CalibrationData {
+            channel_infos: JoystickChannel::STRS
+                .iter()
+                .enumerate()
+                .map(|(index, name)| ChannelInfo {
+                    name: name.to_string(),
+                    index: index as u8,
+                    min: 10_000,
+                    max: 20_000,
+                    center: 15_000,
+                    deadband: 0,
+                    rev: false,
+                    points: Vec::new(),
+                })
+                .collect(),
+            channel_indexs: vec![0, 1, 2, 3],
+        };
+        let mut input = InputConfig {
+            source_priority: vec![InputSource::Stm32Serial, InputSource::Adc],
+            ..InputConfig::default()
+        };
+        input
+            .calibration_sources
+            .push(crate::config::SourceCalibration {
+                source: InputSource::Adc,
+                profile: "ads1115".to_string(),
+            });
+        let calibration = CalibrationSet::with_profiles(
+            input,
+            ModelCalibration::default(),
+            vec![("ads1115".to_string(), ads)],
+        );
+
+        let start = Instant::now();
+        let mut router = InputRouter::new(INPUT_STALE_TIMEOUT);
+        router.update(
+            InputFrameMsg::from_axes(InputSource::Stm32Serial, vec![2047; 4]),
+            start,
+        );
+        let later = start + INPUT_STALE_TIMEOUT * 2;
+        router.update(
+            InputFrameMsg::from_axes(InputSource::Adc, vec![20_000, 15_000, 12_500, 10_000]),
+            later,
+        );
+
+        let raw = read_inputs(&router, &model, &calibration, later);
+        assert_eq!(
+            raw,
+            vec![
+                (ControlRole::Thrust, 10000),
+                (ControlRole::Direction, 5000),
+                (ControlRole::Aileron, 2500),
+                (ControlRole::Elevator, 0),
+            ]
+        );
+    }
 }
@@ -1,4 +1,8 @@
-use crate::{client_process_args, messages::AdcRawMsg};
+use crate::{
+    client_process_args,
+    config::InputSource,
+    messages::{InputFrameMsg, SwitchState},
+};
 use clap::Parser;
 use rpos::{msg::get_new_tx_of_message, thread_logln};
 use serde::Deserialize;
@@ -35,6 +39,13 @@ struct MockConfig {
 struct StaticConfig {
     #[serde(default = "default_static_channels")]
     channels: Vec<i16>,
+
+    /// Switch positions: 0 = up, 1 = mid, 2 = down.
+    #[serde(default)]
+    switches: Vec<u8>,
+
+    #[serde(default)]
+    buttons: Vec<bool>,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -92,6 +103,8 @@ impl Default for StaticConfig {
     fn default() -> Self {
         Self {
             channels: default_static_channels(),
+            switches: Vec::new(),
+            buttons: Vec::new(),
         }
     }
 }
@@ -153,7 +166,7 @@ pub fn mock_joystick_main(argc: u32, argv: *const &str) {
         }
     };
 
-    let adc_raw_tx = get_new_tx_of_message::<AdcRawMsg>("adc_raw").unwrap();
+    let input_tx = get_new_tx_of_message::<InputFrameMsg>("input_frame").unwrap();
     let update_interval = Duration::from_millis(1000 / config.update_rate_hz as u64);
 
     thread_logln!(
@@ -163,35 +176,49 @@ pub fn mock_joystick_main(argc: u32, argv: *const &str) {
     );
 
     match config.mode.as_str() {
-        "static" => run_static_mode(&config.static_config, &adc_raw_tx, update_interval),
-        "sine" => run_sine_mode(&config.sine_config, &adc_raw_tx, update_interval),
-        "step" => run_step_mode(&config.step_config, &adc_raw_tx, update_interval),
+        "static" => run_static_mode(&config.static_config, &input_tx, update_interval),
+        "sine" => run_sine_mode(&config.sine_config, &input_tx, update_interval),
+        "step" => run_step_mode(&config.step_config, &input_tx, update_interval),
         _ => {
             thread_logln!("Unknown mode: {}, falling back to static", config.mode);
-            run_static_mode(&config.static_config, &adc_raw_tx, update_interval);
+            run_static_mode(&config.static_config, &input_tx, update_interval);
         }
     }
 }
 
 fn run_static_mode(
     config: &StaticConfig,
-    tx: &rpos::channel::Sender<AdcRawMsg>,
+    tx: &rpos::channel::Sender<InputFrameMsg>,
     interval: Duration,
 ) {
-    let mut channels = [0i16; 4];
-    for (i, &val) in config.channels.iter().enumerate().take(4) {
-        channels[i] = val;
-    }
+    let frame = InputFrameMsg {
+        switches: config
+            .switches
+            .iter()
+            .map(|position| SwitchState::from_position(*position))
+            .collect(),
+        buttons: config.buttons.clone(),
+        ..InputFrameMsg::from_axes(InputSource::Mock, config.channels.clone())
+    };
 
-    thread_logln!("Static mode: channels = {:?}", channels);
+    thread_logln!(
+        "Static mode: channels = {:?}, switches = {:?}, buttons = {:?}",
+        frame.axes,
+        frame.switches,
+        frame.buttons
+    );
 
     loop {
-        tx.send(AdcRawMsg { value: channels });
+        tx.send(frame.clone());
         std::thread::sleep(interval);
     }
 }
 
-fn run_sine_mode(config: &SineConfig, tx: &rpos::channel::Sender<AdcRawMsg>, interval: Duration) {
+fn run_sine_mode(
+    config: &SineConfig,
+    tx: &rpos::channel::Sender<InputFrameMsg>,
+    interval: Duration,
+) {
     let mut time = 0.0f32;
     let dt = interval.as_secs_f32();
 
@@ -203,9 +230,9 @@ fn run_sine_mode(config: &SineConfig, tx: &rpos::channel::Sender<AdcRawMsg>, int
     );
 
     loop {
-        let mut channels = [0i16; 4];
+        let mut channels = vec![0i16; config.base.len()];
 
-        for i in 0..4 {
+        for i in 0..channels.len() {
             let base = config.base.get(i).copied().unwrap_or(0);
             let amplitude = config.amplitude.get(i).copied().unwrap_or(0);
             let frequency = config.frequency_hz.get(i).copied().unwrap_or(0.0);
@@ -216,7 +243,7 @@ fn run_sine_mode(config: &SineConfig, tx: &rpos::channel::Sender<AdcRawMsg>, int
             channels[i] = value.clamp(-2048, 2047);
         }
 
-        tx.send(AdcRawMsg { value: channels });
+        tx.send(InputFrameMsg::from_axes(InputSource::Mock, channels));
 
         time += dt;
         if time > 1000.0 {
@@ -227,7 +254,11 @@ fn run_sine_mode(config: &SineConfig, tx: &rpos::channel::Sender<AdcRawMsg>, int
     }
 }
 
-fn run_step_mode(config: &StepConfig, tx: &rpos::channel::Sender<AdcRawMsg>, interval: Duration) {
+fn run_step_mode(
+    config: &StepConfig,
+    tx: &rpos::channel::Sender<InputFrameMsg>,
+    interval: Duration,
+) {
     if config.values.is_empty() {
         thread_logln!("Step mode: no values configured, exiting");
         return;
@@ -245,16 +276,14 @@ fn run_step_mode(config: &StepConfig, tx: &rpos::channel::Sender<AdcRawMsg>, int
     let mut step_index = 0;
 
     loop {
-        let step_values = &config.values[step_index];
-        let mut channels = [0i16; 4];
-
-        for (i, &val) in step_values.iter().enumerate().take(4) {
-            channels[i] = val;
-        }
+        let channels = &config.values[step_index];
 
         // Send the same value multiple times for the step duration
         for _ in 0..steps_per_duration {
-            tx.send(AdcRawMsg { value: channels });
+            tx.send(InputFrameMsg::from_axes(
+                InputSource::Mock,
+                channels.clone(),
+            ));
             std::thread::sleep(interval);
         }
 
@@ -0,0 +1,423 @@
+use std::{
+    io::{self, Read, Write},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use rpos::{
+    channel::{Receiver, Sender},
+    thread_logln,
+};
+use serialport::SerialPort;
+
+use super::{OutputDriver, OutputFrame};
+use crate::{
+    config::{store, ModelConfig},
+    crsf::{self, link, Frame},
+    messages::{CrsfFrameMsg, CrsfRateMsg},
+};
+
+/// RC period until the module sends timing corrections.
+const CRSF_FRAME_INTERVAL: Duration = Duration::from_millis(10);
+/// Without a timing correction for this long the module is treated as unsynced.
+const RATE_SYNC_TIMEOUT: Duration = Duration::from_millis(1000);
+/// ELRS packet rates run from 50 Hz to 1000 Hz.
+const MIN_SYNC_PERIOD_US: u64 = 1000;
+const MAX_SYNC_PERIOD_US: u64 = 20000;
+const RATE_REPORT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Parameter write of field 1 that wakes the module up for RC input.
+fn gen_magic_packet() -> Vec<u8> {
+    Frame::ParamWrite {
+        dest: crsf::ADDRESS_MODULE,
+        origin: crsf::ADDRESS_RADIO,
+        field_id: 1,
+        value: vec![0],
+    }
+    .encode(crsf::ADDRESS_MODULE)
+}
+
+#[inline]
+fn mxier_out_2_crsf(val: u16) -> u16 {
+    (val as u32 * (crsf::CHANNEL_VALUE_MAX - crsf::CHANNEL_VALUE_MIN) as u32 / 10000
+        + crsf::CHANNEL_VALUE_MIN as u32) as u16
+}
+
+/// Paces RC frames to the module's timing corrections (frame 0x3A) and
+/// measures the rate actually achieved.
+#[derive(Debug, Default)]
+pub struct RateSync {
+    /// Period the module asked for and when.
+    synced: Option<(Duration, Instant)>,
+    /// Correction for the next frame in us; positive delays it.
+    pending_offset_us: i64,
+    last_deadline: Option<Instant>,
+    last_sent: Option<Instant>,
+    window_start: Option<Instant>,
+    window_frames: u32,
+    window_deviation_us: u64,
+}
+
+impl RateSync {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Takes a timing correction, values in 0.1 us as the module sends them.
+    pub fn correct(&mut self, interval: u32, offset: i32, now: Instant) {
+        let period_us = (interval as u64 / 10).clamp(MIN_SYNC_PERIOD_US, MAX_SYNC_PERIOD_US);
+        self.synced = Some((Duration::from_micros(period_us), now));
+        self.pending_offset_us = offset as i64 / 10;
+    }
+
+    fn is_synced(&self, now: Instant) -> bool {
+        self.synced
+            .is_some_and(|(_, at)| now.saturating_duration_since(at) < RATE_SYNC_TIMEOUT)
+    }
+
+    pub fn period(&self, now: Instant) -> Duration {
+        match self.synced {
+            Some((period, _)) if self.is_synced(now) => period,
+            _ => CRSF_FRAME_INTERVAL,
+        }
+    }
+
+    /// When the next RC frame is due. A pending correction moves that one
+    /// frame by up to half a period; falling a whole period behind restarts
+    /// the schedule at `now`.
+    pub fn next_deadline(&mut self, now: Instant) -> Instant {
+        let period = self.period(now);
+        let Some(last) = self.last_deadline else {
+            return now;
+        };
+        let limit = period.as_micros() as i64 / 2;
+        let shift = std::mem::take(&mut self.pending_offset_us).clamp(-limit, limit);
+        let next = if shift >= 0 {
+            last + period + Duration::from_micros(shift as u64)
+        } else {
+            last + period - Duration::from_micros(shift.unsigned_abs())
+        };
+        if next + period < now {
+            now
+        } else {
+            next
+        }
+    }
+
+    /// Records the frame due at `deadline` as written at `at`. Returns a
+    /// report once every `RATE_REPORT_INTERVAL`.
+    pub fn sent(&mut self, deadline: Instant, at: Instant) -> Option<CrsfRateMsg> {
+        let period = self.period(at);
+        self.last_deadline = Some(deadline);
+        if let Some(last) = self.last_sent.replace(at) {
+            let spacing = at.saturating_duration_since(last).as_micros() as i64;
+            self.window_deviation_us += (spacing - period.as_micros() as i64).unsigned_abs();
+            self.window_frames += 1;
+        }
+
+        let start = *self.window_start.get_or_insert(at);
+        let elapsed = at.saturating_duration_since(start);
+        if elapsed < RATE_REPORT_INTERVAL || self.window_frames == 0 {
+            return None;
+        }
+        let report = CrsfRateMsg {
+            synced: self.is_synced(at),
+            target_hz: (1_000_000 / period.as_micros()) as u16,
+            achieved_hz: (self.window_frames as f64 / elapsed.as_secs_f64()).round() as u16,
+            jitter_us: (self.window_deviation_us / self.window_frames as u64) as u32,
+        };
+        self.window_start = Some(at);
+        self.window_frames = 0;
+        self.window_deviation_us = 0;
+        Some(report)
+    }
+}
+
+/// CRSF RC channels to an ELRS transmitter module. This is the only owner of
+/// the port: frames other modules queue on `crsf_tx` go out right after an RC
+/// frame, and everything the module answers is forwarded to `crsf_rx`. RC
+/// frames follow the module's packet rate once it sends timing corrections.
+pub struct CrsfOutput {
+    dev: Box<dyn Write + Send>,
+    queued: Receiver<CrsfFrameMsg>,
+    sync: Arc<Mutex<RateSync>>,
+    rate_tx: Sender<CrsfRateMsg>,
+    /// Whether the last rate report was synced, to log changes.
+    synced: bool,
+    /// Stops the telemetry reader thread on drop.
+    stop: Arc<AtomicBool>,
+}
+
+/// Opens the module port. A non-zero `baudrate` is used as is; otherwise
+/// the rate is negotiated per `model.output.crsf` and the result saved to
+/// the model's state.
+pub fn open_module_port(
+    dev_name: &str,
+    baudrate: u32,
+    model: &ModelConfig,
+) -> io::Result<Box<dyn SerialPort>> {
+    if baudrate != 0 {
+        return link::open_port(dev_name, baudrate);
+    }
+    let last_baudrate = store::load_model_state(&model.id)
+        .map(|state| state.crsf_baudrate)
+        .unwrap_or(0);
+    let (dev, baudrate) = link::connect(dev_name, model.output.crsf.baudrate, last_baudrate)?;
+    thread_logln!("crsf module on {} @ {} baud", dev_name, baudrate);
+    if baudrate != last_baudrate {
+        if let Err(err) = store::save_crsf_baudrate(&model.id, baudrate) {
+            thread_logln!("crsf baud rate not saved to `{}`: {}", model.id, err);
+        }
+    }
+    Ok(dev)
+}
+
+impl CrsfOutput {
+    /// Takes over a port opened by `open_module_port`.
+    pub fn open(
+        mut dev: Box<dyn SerialPort>,
+        frame_tx: Sender<CrsfFrameMsg>,
+        queued: Receiver<CrsfFrameMsg>,
+        rate_tx: Sender<CrsfRateMsg>,
+    ) -> io::Result<Self> {
+        dev.set_timeout(Duration::from_millis(1000))?;
+
+        let magic_cmd = gen_magic_packet();
+        for _ in 0..10 {
+            dev.write_all(&magic_cmd)?;
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let sync = Arc::new(Mutex::new(RateSync::new()));
+        match dev.try_clone() {
+            Ok(reader) => {
+                let stop = stop.clone();
+                let sync = sync.clone();
+                std::thread::spawn(move || forward_telemetry(reader, frame_tx, sync, stop));
+            }
+            Err(e) => thread_logln!("crsf telemetry disabled, port clone failed: {}", e),
+        }
+
+        Ok(Self {
+            dev: Box::new(dev),
+            queued,
+            sync,
+            rate_tx,
+            synced: false,
+            stop,
+        })
+    }
+}
+
+fn forward_telemetry(
+    mut reader: Box<dyn SerialPort>,
+    frame_tx: Sender<CrsfFrameMsg>,
+    sync: Arc<Mutex<RateSync>>,
+    stop: Arc<AtomicBool>,
+) {
+    let mut buf = Vec::with_capacity(256);
+    let mut scratch = [0u8; 128];
+    while !stop.load(Ordering::Relaxed) {
+        match reader.read(&mut scratch) {
+            Ok(n) if n > 0 => buf.extend_from_slice(&scratch[..n]),
+            Ok(_) => std::thread::sleep(Duration::from_millis(5)),
+            Err(ref e) if e.kind() == io::ErrorKind::TimedOut => {}
+            Err(e) => {
+                thread_logln!("crsf telemetry read error: {}", e);
+                std::thread::sleep(Duration::from_millis(100));
+            }
+        }
+        for frame in crsf::extract_frames(&mut buf) {
+            if let Some((
+                _,
+                Frame::TimingCorrection {
+                    interval, offset, ..
+                },
+            )) = Frame::decode(&frame)
+            {
+                sync.lock()
+                    .unwrap()
+                    .correct(interval, offset, Instant::now());
+            }
+            frame_tx.send(CrsfFrameMsg { frame });
+        }
+    }
+}
+
+impl Drop for CrsfOutput {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+impl OutputDriver for CrsfOutput {
+    /// `send` waits for the next RC slot itself.
+    fn frame_interval(&self) -> Duration {
+        Duration::ZERO
+    }
+
+    fn send(&mut self, frame: &OutputFrame) -> io::Result<()> {
+        let deadline = self.sync.lock().unwrap().next_deadline(Instant::now());
+        std::thread::sleep(deadline.saturating_duration_since(Instant::now()));
+
+        let mut crsf_chn_values = [0; crsf::RC_CHANNELS];
+        for (crsf_value, value) in crsf_chn_values.iter_mut().zip(frame.channels.channels) {
+            *crsf_value = mxier_out_2_crsf(value);
+        }
+        self.dev
+            .write_all(&Frame::RcChannels(crsf_chn_values).encode(crsf::ADDRESS_MODULE))?;
+
+        // One queued frame per RC slot keeps the RC rate steady while the
+        // ELRS menu reads or writes parameters.
+        if let Some(queued) = self.queued.try_read() {
+            self.dev.write_all(&queued.frame)?;
+        }
+
+        let report = self.sync.lock().unwrap().sent(deadline, Instant::now());
+        if let Some(rate) = report {
+            if rate.synced != self.synced {
+                self.synced = rate.synced;
+                if rate.synced {
+                    thread_logln!("crsf rc synced to {} Hz", rate.target_hz);
+                } else {
+                    thread_logln!("crsf rc sync lost, back to {} Hz", rate.target_hz);
+                }
+            }
+            self.rate_tx.send(rate);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rpos::msg::{get_new_rx_of_message, get_new_tx_of_message};
+
+    use super::*;
+    use crate::mixer::MixerOutMsg;
+
+    #[derive(Clone, Default)]
+    struct Wire(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Wire {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_rate_sync_follows_timing_corrections() {
+        let start = Instant::now();
+        let mut sync = RateSync::new();
+        assert_eq!(sync.next_deadline(start), start);
+        sync.sent(start, start);
+        assert_eq!(sync.next_deadline(start), start + CRSF_FRAME_INTERVAL);
+
+        // 250 Hz, last frame 100 us early: only the next frame waits longer.
+        sync.correct(40000, 1000, start);
+        let next = sync.next_deadline(start);
+        assert_eq!(next, start + Duration::from_micros(4100));
+        sync.sent(next, next);
+        assert_eq!(sync.next_deadline(next), next + Duration::from_micros(4000));
+
+        // Far behind schedule: start over instead of bursting.
+        let late = next + Duration::from_millis(50);
+        assert_eq!(sync.next_deadline(late), late);
+    }
+
+    #[test]
+    fn test_rate_sync_reports_rate_and_jitter() {
+        let start = Instant::now();
+        let period = Duration::from_micros(4000);
+        let mut sync = RateSync::new();
+        let mut reports = Vec::new();
+        for idx in 0..=250u32 {
+            let deadline = start + period * idx;
+            if idx % 50 == 0 {
+                sync.correct(40000, 0, deadline);
+            }
+            // Every other frame goes out 20 us late.
+            let at = deadline + Duration::from_micros(20 * (idx % 2) as u64);
+            if let Some(report) = sync.sent(deadline, at) {
+                reports.push((idx, report));
+            }
+        }
+        assert_eq!(
+            reports,
+            vec![(
+                250,
+                CrsfRateMsg {
+                    synced: true,
+                    target_hz: 250,
+                    achieved_hz: 250,
+                    jitter_us: 20,
+                }
+            )]
+        );
+    }
+
+    #[test]
+    fn test_queued_frames_ride_between_rc_frames() {
+        let wire = Wire::default();
+        let queue_tx = get_new_tx_of_message::<CrsfFrameMsg>("crsf_tx").unwrap();
+        let mut output = CrsfOutput {
+            dev: Box::new(wire.clone()),
+            queued: get_new_rx_of_message::<CrsfFrameMsg>("crsf_tx").unwrap(),
+            sync: Arc::new(Mutex::new(RateSync::new())),
+            rate_tx: get_new_tx_of_message::<CrsfRateMsg>("crsf_rate").unwrap(),
+            synced: false,
+            stop: Arc::new(AtomicBool::new(false)),
+        };
+        let ping = Frame::DevicePing {
+            dest: crsf::ADDRESS_BROADCAST,
+            origin: crsf::ADDRESS_RADIO,
+        }
+        .encode(crsf::ADDRESS_SYNC);
+        let read = Frame::ParamRead {
+            dest: crsf::ADDRESS_MODULE,
+            origin: crsf::ADDRESS_HANDSET,
+            field_id: 1,
+            chunk: 0,
+        }
+        .encode(crsf::ADDRESS_SYNC);
+        queue_tx.send(CrsfFrameMsg {
+            frame: ping.clone(),
+        });
+        queue_tx.send(CrsfFrameMsg {
+            frame: read.clone(),
+        });
+
+        let frame = OutputFrame {
+            channels: MixerOutMsg::default(),
+            failsafe: false,
+        };
+        for _ in 0..3 {
+            output.send(&frame).unwrap();
+        }
+
+        let mut written = wire.0.lock().unwrap().clone();
+        let frames: Vec<u8> = crsf::extract_frames(&mut written)
+            .iter()
+            .map(|frame| frame[2])
+            .collect();
+        assert_eq!(
+            frames,
+            vec![
+                crsf::FRAME_RC_CHANNELS,
+                crsf::FRAME_PING_DEVICES,
+                crsf::FRAME_RC_CHANNELS,
+                crsf::FRAME_PARAM_READ,
+                crsf::FRAME_RC_CHANNELS,
+            ]
+        );
+    }
+}
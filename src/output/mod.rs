@@ -0,0 +1,422 @@
+//! Output manager: runs the driver for the active model's
+//! `OutputConfig::protocol` and feeds it `mixer_out`.
+
+mod crsf;
+mod ppm;
+mod sbus;
+mod usb_hid;
+
+use std::{
+    io,
+    time::{Duration, Instant},
+};
+
+use clap::Parser;
+use rpos::{
+    msg::{get_new_rx_of_message, get_new_tx_of_message},
+    thread_logln,
+};
+
+use crate::{
+    client_process_args,
+    config::{store, ControlRole, ModelConfig, OutputConfig, OutputProtocol},
+    messages::{ActiveModelMsg, CrsfFrameMsg, CrsfRateMsg},
+    mixer::{channel_order, MixerOutMsg},
+};
+
+pub use crsf::{open_module_port, CrsfOutput};
+pub use ppm::PpmOutput;
+pub use sbus::SbusOutput;
+pub use usb_hid::HidOutput;
+
+/// Mixer output older than this is replaced by the model's failsafe.
+pub const OUTPUT_FAILSAFE_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Delay before reopening a driver that failed to open or write.
+const DRIVER_RETRY_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Poll interval while the mixer has not produced a frame yet.
+const IDLE_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Channels handed to a driver, in the model's `channel_order`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutputFrame {
+    pub channels: MixerOutMsg,
+    /// Mixer output went stale and `channels` hold the model's failsafe.
+    pub failsafe: bool,
+}
+
+/// A protocol driver. Dropping it releases the device.
+pub trait OutputDriver: Send {
+    /// Time between frames the protocol expects.
+    fn frame_interval(&self) -> Duration;
+
+    fn send(&mut self, frame: &OutputFrame) -> io::Result<()>;
+}
+
+/// Device paths for the drivers, from the `output` command line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputDevices {
+    pub crsf: String,
+    /// Fixed CRSF rate; 0 negotiates one per model.
+    pub crsf_baudrate: u32,
+    pub hid: String,
+    pub sbus: String,
+    /// `gpio`, `pwm` or `file`, see `PpmOutput::open`.
+    pub ppm_sink: String,
+    pub ppm: String,
+}
+
+pub fn open_driver(
+    model: &ModelConfig,
+    devices: &OutputDevices,
+) -> io::Result<Box<dyn OutputDriver>> {
+    let output = &model.output;
+    match output.protocol {
+        OutputProtocol::Crsf => {
+            let frame_tx = get_new_tx_of_message::<CrsfFrameMsg>("crsf_rx").unwrap();
+            let queued = get_new_rx_of_message::<CrsfFrameMsg>("crsf_tx").unwrap();
+            let rate_tx = get_new_tx_of_message::<CrsfRateMsg>("crsf_rate").unwrap();
+            Ok(Box::new(CrsfOutput::open(
+                open_module_port(&devices.crsf, devices.crsf_baudrate, model)?,
+                frame_tx,
+                queued,
+                rate_tx,
+            )?))
+        }
+        OutputProtocol::UsbHid => Ok(Box::new(HidOutput::open(&devices.hid)?)),
+        OutputProtocol::Sbus => Ok(Box::new(SbusOutput::open(&devices.sbus, &output.sbus)?)),
+        OutputProtocol::Ppm => Ok(Box::new(PpmOutput::open(
+            &devices.ppm_sink,
+            &devices.ppm,
+            &output.ppm,
+        )?)),
+    }
+}
+
+/// Whether a driver opened for `opened` can keep running for `output`.
+fn same_driver(opened: &OutputConfig, output: &OutputConfig) -> bool {
+    opened.protocol == output.protocol
+        && match output.protocol {
+            OutputProtocol::Sbus => opened.sbus == output.sbus,
+            OutputProtocol::Ppm => opened.ppm == output.ppm,
+            OutputProtocol::Crsf => opened.crsf == output.crsf,
+            _ => true,
+        }
+}
+
+/// Mixer value for a failsafe entry in config units (-1000..1000). Slots
+/// without an entry hold center, except throttle, which goes to its low end.
+fn failsafe_value(value: Option<i16>, role: ControlRole) -> u16 {
+    match value {
+        Some(value) => (5000 + value as i32 * 5).clamp(0, 10000) as u16,
+        None if role == ControlRole::Thrust => 0,
+        None => 5000,
+    }
+}
+
+/// Reorders the latest mixer output into the model's `channel_order`, or
+/// builds the failsafe frame once it is older than `OUTPUT_FAILSAFE_TIMEOUT`.
+/// Nothing is sent before the mixer has produced a frame.
+pub fn output_frame(
+    model: &ModelConfig,
+    latest: Option<&(MixerOutMsg, Instant)>,
+    now: Instant,
+) -> Option<OutputFrame> {
+    let (mixer_out, at) = latest?;
+    let order = channel_order(model);
+    if now.saturating_duration_since(*at) < OUTPUT_FAILSAFE_TIMEOUT {
+        return Some(OutputFrame {
+            channels: MixerOutMsg::from_roles(order, |role| {
+                mixer_out.value_of(role).unwrap_or(5000)
+            }),
+            failsafe: false,
+        });
+    }
+
+    let mut failsafe = model.output.failsafe.iter().copied();
+    Some(OutputFrame {
+        channels: MixerOutMsg::from_roles(order, |role| failsafe_value(failsafe.next(), role)),
+        failsafe: true,
+    })
+}
+
+type DriverOpener = Box<dyn FnMut(&ModelConfig) -> io::Result<Box<dyn OutputDriver>> + Send>;
+
+/// Keeps exactly one driver open, matching the active model's protocol.
+pub struct OutputManager {
+    open: DriverOpener,
+    /// Settings the current driver was opened for.
+    opened: Option<OutputConfig>,
+    driver: Option<Box<dyn OutputDriver>>,
+    retry_at: Option<Instant>,
+}
+
+impl OutputManager {
+    pub fn new(open: DriverOpener) -> Self {
+        Self {
+            open,
+            opened: None,
+            driver: None,
+            retry_at: None,
+        }
+    }
+
+    /// Switches drivers when the protocol or its settings changed and
+    /// retries a failed one.
+    pub fn sync(&mut self, model: &ModelConfig, now: Instant) {
+        let output = &model.output;
+        if !self
+            .opened
+            .as_ref()
+            .is_some_and(|opened| same_driver(opened, output))
+        {
+            if let Some(previous) = &self.opened {
+                thread_logln!("output stop {}", previous.protocol.display_name());
+            }
+            self.opened = Some(output.clone());
+            self.driver = None;
+            self.retry_at = None;
+        }
+        if self.driver.is_some() || self.retry_at.is_some_and(|at| now < at) {
+            return;
+        }
+
+        match (self.open)(model) {
+            Ok(driver) => {
+                thread_logln!("output start {}", output.protocol.display_name());
+                self.driver = Some(driver);
+            }
+            Err(err) => {
+                thread_logln!(
+                    "output {} unavailable: {}",
+                    output.protocol.display_name(),
+                    err
+                );
+                self.retry_at = Some(now + DRIVER_RETRY_INTERVAL);
+            }
+        }
+    }
+
+    pub fn send(&mut self, frame: &OutputFrame, now: Instant) {
+        let Some(driver) = self.driver.as_mut() else {
+            return;
+        };
+        if let Err(err) = driver.send(frame) {
+            thread_logln!("output write failed, reopening: {}", err);
+            self.driver = None;
+            self.retry_at = Some(now + DRIVER_RETRY_INTERVAL);
+        }
+    }
+
+    pub fn frame_interval(&self) -> Duration {
+        self.driver
+            .as_ref()
+            .map_or(Duration::from_millis(10), |driver| driver.frame_interval())
+    }
+}
+
+#[derive(Parser)]
+#[command(name = "output", about = "Drives the active model's output protocol", long_about = None)]
+struct Cli {
+    /// CRSF transmitter module serial port
+    #[arg(long, default_value = "/dev/ttyS1")]
+    crsf_dev: String,
+
+    /// Fixed CRSF baud rate; 0 probes the module and proposes the model's
+    /// `output.crsf.baudrate`
+    #[arg(long, default_value_t = 0)]
+    crsf_baudrate: u32,
+
+    /// USB HID gamepad device
+    #[arg(long, default_value = "/dev/hidg0")]
+    hid_dev: String,
+
+    /// SBUS serial port, behind an inverter
+    #[arg(long, default_value = "/dev/ttyS2")]
+    sbus_dev: String,
+
+    /// PPM sink: gpio (sysfs value file), pwm (sysfs PWM channel) or file
+    #[arg(long, default_value = "gpio")]
+    ppm_sink: String,
+
+    #[arg(long, default_value = "/sys/class/gpio/gpio60/value")]
+    ppm_dev: String,
+}
+
+fn output_main(argc: u32, argv: *const &str) {
+    let Some(args) = client_process_args::<Cli>(argc, argv) else {
+        return;
+    };
+    let devices = OutputDevices {
+        crsf: args.crsf_dev,
+        crsf_baudrate: args.crsf_baudrate,
+        hid: args.hid_dev,
+        sbus: args.sbus_dev,
+        ppm_sink: args.ppm_sink,
+        ppm: args.ppm_dev,
+    };
+
+    let mut mixer_rx = get_new_rx_of_message::<MixerOutMsg>("mixer_out").unwrap();
+    let mut active_model_rx = get_new_rx_of_message::<ActiveModelMsg>("active_model").unwrap();
+    let mut model: ModelConfig = store::load_active_model().unwrap_or_default();
+    let mut manager = OutputManager::new(Box::new(move |model| open_driver(model, &devices)));
+    let mut latest = None;
+    thread_logln!("output start for model `{}`", model.id);
+
+    loop {
+        while let Some(active) = active_model_rx.try_read() {
+            model = active.model;
+        }
+        let now = Instant::now();
+        manager.sync(&model, now);
+
+        while let Some(mixer_out) = mixer_rx.try_read() {
+            latest = Some((mixer_out, now));
+        }
+        match output_frame(&model, latest.as_ref(), now) {
+            Some(frame) => {
+                manager.send(&frame, now);
+                std::thread::sleep(manager.frame_interval());
+            }
+            // Drivers that pace themselves in `send` report a zero interval.
+            None => std::thread::sleep(IDLE_INTERVAL),
+        }
+    }
+}
+
+#[rpos::ctor::ctor]
+fn register() {
+    rpos::module::Module::register("output", output_main);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+
+    struct FakeDriver {
+        protocol: OutputProtocol,
+        sent: Arc<Mutex<Vec<(OutputProtocol, OutputFrame)>>>,
+    }
+
+    impl OutputDriver for FakeDriver {
+        fn frame_interval(&self) -> Duration {
+            Duration::from_millis(4)
+        }
+
+        fn send(&mut self, frame: &OutputFrame) -> io::Result<()> {
+            self.sent.lock().unwrap().push((self.protocol, *frame));
+            Ok(())
+        }
+    }
+
+    fn model(protocol: OutputProtocol) -> ModelConfig {
+        let mut model = ModelConfig::default();
+        model.output.protocol = protocol;
+        model.output.channel_order =
+            vec![ControlRole::Thrust, ControlRole::Aileron, ControlRole::Arm];
+        model.output.failsafe = vec![-1000, 500];
+        model
+    }
+
+    #[test]
+    fn test_frame_follows_channel_order_then_failsafe() {
+        let model = model(OutputProtocol::Crsf);
+        let start = Instant::now();
+        let mixer_out = MixerOutMsg::from_roles(
+            &[ControlRole::Aileron, ControlRole::Thrust],
+            |role| match role {
+                ControlRole::Aileron => 7000,
+                _ => 1000,
+            },
+        );
+        assert_eq!(output_frame(&model, None, start), None);
+
+        let latest = (mixer_out, start);
+        let frame = output_frame(&model, Some(&latest), start).unwrap();
+        assert!(!frame.failsafe);
+        assert_eq!(frame.channels.active_channels(), &[1000, 7000, 5000]);
+        assert_eq!(frame.channels.roles[0], ControlRole::Thrust);
+
+        let frame = output_frame(&model, Some(&latest), start + OUTPUT_FAILSAFE_TIMEOUT).unwrap();
+        assert!(frame.failsafe);
+        assert_eq!(frame.channels.active_channels(), &[0, 7500, 5000]);
+
+        let mut model = model;
+        model.output.failsafe.clear();
+        let frame = output_frame(&model, Some(&latest), start + OUTPUT_FAILSAFE_TIMEOUT).unwrap();
+        assert_eq!(frame.channels.active_channels(), &[0, 5000, 5000]);
+    }
+
+    #[test]
+    fn test_manager_switches_driver_with_protocol_and_retries_failures() {
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let opened = Arc::new(Mutex::new(Vec::new()));
+        let mut manager = OutputManager::new(Box::new({
+            let sent = sent.clone();
+            let opened = opened.clone();
+            move |model: &ModelConfig| {
+                let protocol = model.output.protocol;
+                opened.lock().unwrap().push(protocol);
+                if protocol == OutputProtocol::Ppm {
+                    return Err(io::Error::new(io::ErrorKind::NotFound, "no port"));
+                }
+                Ok(Box::new(FakeDriver {
+                    protocol,
+                    sent: sent.clone(),
+                }) as Box<dyn OutputDriver>)
+            }
+        }));
+        let start = Instant::now();
+        let frame = OutputFrame {
+            channels: MixerOutMsg::default(),
+            failsafe: false,
+        };
+
+        let crsf = model(OutputProtocol::Crsf);
+        manager.sync(&crsf, start);
+        manager.sync(&crsf, start);
+        manager.send(&frame, start);
+        assert_eq!(manager.frame_interval(), Duration::from_millis(4));
+
+        manager.sync(&model(OutputProtocol::UsbHid), start);
+        manager.send(&frame, start);
+
+        let mut sbus = model(OutputProtocol::Sbus);
+        manager.sync(&sbus, start);
+        sbus.output.failsafe.clear();
+        manager.sync(&sbus, start);
+        sbus.output.sbus.frame_interval_ms = 7;
+        manager.sync(&sbus, start);
+
+        let ppm = model(OutputProtocol::Ppm);
+        manager.sync(&ppm, start);
+        manager.send(&frame, start);
+        manager.sync(&ppm, start + Duration::from_millis(100));
+        manager.sync(&ppm, start + DRIVER_RETRY_INTERVAL);
+
+        assert_eq!(
+            *opened.lock().unwrap(),
+            vec![
+                OutputProtocol::Crsf,
+                OutputProtocol::UsbHid,
+                OutputProtocol::Sbus,
+                OutputProtocol::Sbus,
+                OutputProtocol::Ppm,
+                OutputProtocol::Ppm
+            ]
+        );
+        let protocols: Vec<OutputProtocol> = sent
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(protocol, _)| *protocol)
+            .collect();
+        assert_eq!(
+            protocols,
+            vec![OutputProtocol::Crsf, OutputProtocol::UsbHid]
+        );
+    }
+}
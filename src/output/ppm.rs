@@ -0,0 +1,336 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use super::{OutputDriver, OutputFrame};
+use crate::config::{PpmConfig, PpmPolarity};
+
+const PPM_MAX_CHANNELS: usize = 16;
+/// Channel width at mixer 0 and 10000.
+const PPM_CHANNEL_MIN_US: u32 = 1000;
+const PPM_CHANNEL_MAX_US: u32 = 2000;
+/// Receivers need a gap this long to find the start of the train.
+const PPM_MIN_SYNC_US: u32 = 4000;
+/// Sleep until this close to a deadline, then spin.
+const SPIN_WINDOW: Duration = Duration::from_micros(500);
+
+/// One stretch of the waveform at a constant line level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PpmSegment {
+    pub high: bool,
+    pub us: u32,
+}
+
+fn channel_width_us(value: u16) -> u32 {
+    PPM_CHANNEL_MIN_US + value.min(10000) as u32 * (PPM_CHANNEL_MAX_US - PPM_CHANNEL_MIN_US) / 10000
+}
+
+/// One PPM train: a pulse and gap per channel, then a final pulse and the
+/// sync gap that pads the train to `frame_length_us`. Segments alternate
+/// pulse level and idle level, starting with a pulse.
+pub fn encode_frame(frame: &OutputFrame, config: &PpmConfig) -> Vec<PpmSegment> {
+    let pulse_level = config.polarity == PpmPolarity::Positive;
+    let channels = (config.channels as usize).clamp(1, PPM_MAX_CHANNELS);
+    let mut segments = Vec::with_capacity(channels * 2 + 2);
+    let mut used = 0;
+    for value in frame.channels.channels.iter().take(channels) {
+        let width = channel_width_us(*value);
+        let pulse = (config.pulse_us as u32).min(width);
+        segments.push(PpmSegment {
+            high: pulse_level,
+            us: pulse,
+        });
+        segments.push(PpmSegment {
+            high: !pulse_level,
+            us: width - pulse,
+        });
+        used += width;
+    }
+
+    let sync = config
+        .frame_length_us
+        .saturating_sub(used)
+        .max(PPM_MIN_SYNC_US);
+    let pulse = (config.pulse_us as u32).min(sync);
+    segments.push(PpmSegment {
+        high: pulse_level,
+        us: pulse,
+    });
+    segments.push(PpmSegment {
+        high: !pulse_level,
+        us: sync - pulse,
+    });
+    segments
+}
+
+fn wait_until(deadline: Instant) {
+    let now = Instant::now();
+    if deadline > now + SPIN_WINDOW {
+        std::thread::sleep(deadline - now - SPIN_WINDOW);
+    }
+    while Instant::now() < deadline {
+        std::hint::spin_loop();
+    }
+}
+
+/// Where a PPM train goes.
+trait PpmSink: Send {
+    /// Plays the train; returns once it has been sent in real time.
+    fn play(&mut self, segments: &[PpmSegment]) -> io::Result<()>;
+
+    /// Whether `play` takes the frame's duration itself.
+    fn paced(&self) -> bool;
+}
+
+/// A sysfs GPIO `value` file, toggled with busy-wait timing.
+struct GpioSink {
+    value: File,
+}
+
+impl GpioSink {
+    fn open(path: &Path) -> io::Result<Self> {
+        if let Some(dir) = path.parent() {
+            // Older kernels need the line switched to output first.
+            let _ = std::fs::write(dir.join("direction"), "out");
+        }
+        Ok(Self {
+            value: OpenOptions::new().write(true).open(path)?,
+        })
+    }
+
+    fn set(&mut self, high: bool) -> io::Result<()> {
+        self.value.seek(SeekFrom::Start(0))?;
+        self.value.write_all(if high { b"1" } else { b"0" })
+    }
+}
+
+impl PpmSink for GpioSink {
+    fn play(&mut self, segments: &[PpmSegment]) -> io::Result<()> {
+        let mut deadline = Instant::now();
+        for segment in segments {
+            self.set(segment.high)?;
+            deadline += Duration::from_micros(segment.us as u64);
+            wait_until(deadline);
+        }
+        Ok(())
+    }
+
+    fn paced(&self) -> bool {
+        true
+    }
+}
+
+/// A sysfs PWM channel directory: each pulse/gap pair becomes one period.
+struct PwmSink {
+    dir: PathBuf,
+}
+
+impl PwmSink {
+    fn open(dir: &Path, config: &PpmConfig) -> io::Result<Self> {
+        let _ = std::fs::write(dir.join("enable"), "0");
+        let polarity = match config.polarity {
+            PpmPolarity::Positive => "normal",
+            PpmPolarity::Negative => "inversed",
+        };
+        std::fs::write(dir.join("polarity"), polarity)?;
+        // Widest period first so the duty cycle always fits.
+        std::fs::write(dir.join("period"), "100000000")?;
+        std::fs::write(
+            dir.join("duty_cycle"),
+            (config.pulse_us as u64 * 1000).to_string(),
+        )?;
+        std::fs::write(dir.join("enable"), "1")?;
+        Ok(Self {
+            dir: dir.to_path_buf(),
+        })
+    }
+}
+
+impl PpmSink for PwmSink {
+    fn play(&mut self, segments: &[PpmSegment]) -> io::Result<()> {
+        let mut deadline = Instant::now();
+        for slot in segments.chunks(2) {
+            let period_us: u32 = slot.iter().map(|segment| segment.us).sum();
+            std::fs::write(
+                self.dir.join("period"),
+                (period_us as u64 * 1000).to_string(),
+            )?;
+            deadline += Duration::from_micros(period_us as u64);
+            wait_until(deadline);
+        }
+        Ok(())
+    }
+
+    fn paced(&self) -> bool {
+        true
+    }
+}
+
+impl Drop for PwmSink {
+    fn drop(&mut self) {
+        let _ = std::fs::write(self.dir.join("enable"), "0");
+    }
+}
+
+/// A file or FIFO that gets one line per train, e.g. `L300 H1200 L300 ...`,
+/// for checking timing without hardware.
+struct FileSink {
+    file: File,
+}
+
+impl PpmSink for FileSink {
+    fn play(&mut self, segments: &[PpmSegment]) -> io::Result<()> {
+        let line: Vec<String> = segments
+            .iter()
+            .map(|segment| format!("{}{}", if segment.high { 'H' } else { 'L' }, segment.us))
+            .collect();
+        writeln!(self.file, "{}", line.join(" "))
+    }
+
+    fn paced(&self) -> bool {
+        false
+    }
+}
+
+/// PPM train on a GPIO, a PWM channel or a file sink.
+pub struct PpmOutput {
+    sink: Box<dyn PpmSink>,
+    config: PpmConfig,
+}
+
+impl PpmOutput {
+    /// `sink` is `gpio` (sysfs `value` file), `pwm` (sysfs PWM channel
+    /// directory) or `file`. `pulse_us` has to be shorter than the narrowest
+    /// channel, or the PWM period could drop below its duty cycle.
+    pub fn open(sink: &str, path: &str, config: &PpmConfig) -> io::Result<Self> {
+        if config.pulse_us as u32 >= PPM_CHANNEL_MIN_US {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "ppm pulse_us {} must be below {}",
+                    config.pulse_us, PPM_CHANNEL_MIN_US
+                ),
+            ));
+        }
+        let path = Path::new(path);
+        let sink: Box<dyn PpmSink> = match sink {
+            "gpio" => Box::new(GpioSink::open(path)?),
+            "pwm" => Box::new(PwmSink::open(path, config)?),
+            "file" => Box::new(FileSink {
+                file: OpenOptions::new().create(true).append(true).open(path)?,
+            }),
+            other => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("unknown ppm sink `{other}`"),
+                ))
+            }
+        };
+        Ok(Self {
+            sink,
+            config: config.clone(),
+        })
+    }
+}
+
+impl OutputDriver for PpmOutput {
+    fn frame_interval(&self) -> Duration {
+        if self.sink.paced() {
+            Duration::ZERO
+        } else {
+            Duration::from_micros(self.config.frame_length_us as u64)
+        }
+    }
+
+    fn send(&mut self, frame: &OutputFrame) -> io::Result<()> {
+        self.sink.play(&encode_frame(frame, &self.config))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::ControlRole, mixer::MixerOutMsg};
+
+    fn frame(values: &[u16]) -> OutputFrame {
+        let roles = [
+            ControlRole::Aileron,
+            ControlRole::Elevator,
+            ControlRole::Thrust,
+        ];
+        let mut values = values.iter();
+        OutputFrame {
+            channels: MixerOutMsg::from_roles(&roles[..values.len()], |_| *values.next().unwrap()),
+            failsafe: false,
+        }
+    }
+
+    #[test]
+    fn test_encode_pads_train_to_frame_length() {
+        let config = PpmConfig {
+            channels: 3,
+            polarity: PpmPolarity::Positive,
+            ..PpmConfig::default()
+        };
+        let segments = encode_frame(&frame(&[0, 5000, 10000]), &config);
+        let timings: Vec<(bool, u32)> = segments
+            .iter()
+            .map(|segment| (segment.high, segment.us))
+            .collect();
+        assert_eq!(
+            timings,
+            vec![
+                (true, 300),
+                (false, 700),
+                (true, 300),
+                (false, 1200),
+                (true, 300),
+                (false, 1700),
+                (true, 300),
+                (false, 17700),
+            ]
+        );
+
+        let short = PpmConfig {
+            frame_length_us: 5000,
+            ..config
+        };
+        let segments = encode_frame(&frame(&[0, 5000, 10000]), &short);
+        assert_eq!(segments.last().unwrap().us, PPM_MIN_SYNC_US - 300);
+    }
+
+    #[test]
+    fn test_file_sink_writes_one_line_per_train() {
+        let path = std::env::temp_dir().join(format!("lintx-ppm-{}.txt", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let config = PpmConfig {
+            channels: 2,
+            frame_length_us: 10_000,
+            ..PpmConfig::default()
+        };
+        let mut output = PpmOutput::open("file", path.to_str().unwrap(), &config).unwrap();
+        assert_eq!(output.frame_interval(), Duration::from_millis(10));
+
+        output.send(&frame(&[5000, 5000])).unwrap();
+        output.send(&frame(&[10000, 0])).unwrap();
+        let written = std::fs::read_to_string(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(
+            written,
+            "L300 H1200 L300 H1200 L300 H6700\nL300 H1700 L300 H700 L300 H6700\n"
+        );
+
+        let wide_pulse = PpmConfig {
+            pulse_us: 1000,
+            ..config
+        };
+        let err = PpmOutput::open("file", path.to_str().unwrap(), &wide_pulse)
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,196 @@
+use std::{
+    fs,
+    io::{self, Write},
+    path::Path,
+    time::Duration,
+};
+
+use rpos::thread_logln;
+use serialport::{DataBits, Parity, SerialPort, StopBits};
+
+use super::{OutputDriver, OutputFrame};
+use crate::config::{ControlRole, SbusConfig};
+
+pub const SBUS_BAUDRATE: u32 = 100_000;
+pub const SBUS_FRAME_LEN: usize = 25;
+const SBUS_HEADER: u8 = 0x0F;
+const SBUS_FOOTER: u8 = 0x00;
+const SBUS_CHANNELS: usize = 16;
+/// Channel values receivers map to 1000us..2000us.
+const SBUS_VALUE_MIN: u32 = 172;
+const SBUS_VALUE_MAX: u32 = 1811;
+/// A 25-byte frame takes 3 ms on the wire at 8E2.
+const SBUS_MIN_FRAME_INTERVAL_MS: u16 = 4;
+
+const FLAG_CH17: u8 = 0x01;
+const FLAG_CH18: u8 = 0x02;
+const FLAG_FRAME_LOST: u8 = 0x04;
+const FLAG_FAILSAFE: u8 = 0x08;
+
+fn mixer_to_sbus(value: u16) -> u16 {
+    let span = SBUS_VALUE_MAX - SBUS_VALUE_MIN;
+    (SBUS_VALUE_MIN + (value.min(10000) as u32 * span + 5000) / 10000) as u16
+}
+
+/// Packs 16 11-bit channels LSB first after the header, then the flags byte:
+/// digital channels 17/18, frame lost and failsafe.
+pub fn encode_frame(frame: &OutputFrame, digital_channels: &[ControlRole]) -> [u8; SBUS_FRAME_LEN] {
+    let mut data = [0u8; SBUS_FRAME_LEN];
+    data[0] = SBUS_HEADER;
+
+    let mut bits: u32 = 0;
+    let mut bit_count = 0;
+    let mut index = 1;
+    for value in frame.channels.channels.iter().take(SBUS_CHANNELS) {
+        bits |= (mixer_to_sbus(*value) as u32 & 0x7FF) << bit_count;
+        bit_count += 11;
+        while bit_count >= 8 {
+            data[index] = bits as u8;
+            bits >>= 8;
+            bit_count -= 8;
+            index += 1;
+        }
+    }
+
+    let digital_on = |slot: usize| {
+        digital_channels
+            .get(slot)
+            .and_then(|role| frame.channels.value_of(*role))
+            .is_some_and(|value| value > 5000)
+    };
+    let mut flags = 0;
+    if digital_on(0) {
+        flags |= FLAG_CH17;
+    }
+    if digital_on(1) {
+        flags |= FLAG_CH18;
+    }
+    if frame.failsafe {
+        flags |= FLAG_FRAME_LOST | FLAG_FAILSAFE;
+    }
+    data[23] = flags;
+    data[24] = SBUS_FOOTER;
+    data
+}
+
+/// Switches the board's inverter to match `config.inverted`. Linux has no
+/// generic serial API to invert a UART, so without `inverter_gpio` the level
+/// is up to the UART's device-tree setting or the wiring.
+fn set_inverter(config: &SbusConfig) -> io::Result<()> {
+    if config.inverter_gpio.is_empty() {
+        if config.inverted {
+            thread_logln!("sbus: no inverter_gpio, the UART or wiring must invert the line");
+        }
+        return Ok(());
+    }
+    let path = Path::new(&config.inverter_gpio);
+    if let Some(dir) = path.parent() {
+        // Older kernels need the line switched to output first.
+        let _ = fs::write(dir.join("direction"), "out");
+    }
+    fs::write(path, if config.inverted { "1" } else { "0" })
+}
+
+/// SBUS on a serial port at 100000 baud 8E2, inverted through
+/// `SbusConfig::inverter_gpio` when set.
+pub struct SbusOutput {
+    dev: Box<dyn SerialPort>,
+    config: SbusConfig,
+}
+
+impl SbusOutput {
+    pub fn open(dev_name: &str, config: &SbusConfig) -> io::Result<Self> {
+        set_inverter(config)?;
+        let dev = serialport::new(dev_name, SBUS_BAUDRATE)
+            .data_bits(DataBits::Eight)
+            .parity(Parity::Even)
+            .stop_bits(StopBits::Two)
+            .timeout(Duration::from_millis(100))
+            .open()?;
+        Ok(Self {
+            dev,
+            config: config.clone(),
+        })
+    }
+}
+
+impl OutputDriver for SbusOutput {
+    fn frame_interval(&self) -> Duration {
+        Duration::from_millis(
+            self.config
+                .frame_interval_ms
+                .max(SBUS_MIN_FRAME_INTERVAL_MS) as u64,
+        )
+    }
+
+    fn send(&mut self, frame: &OutputFrame) -> io::Result<()> {
+        self.dev
+            .write_all(&encode_frame(frame, &self.config.digital_channels))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mixer::MixerOutMsg;
+
+    fn unpack_channels(data: &[u8; SBUS_FRAME_LEN]) -> Vec<u16> {
+        (0..SBUS_CHANNELS)
+            .map(|channel| {
+                let bit = channel * 11;
+                let raw = data[1 + bit / 8] as u32
+                    | (data[2 + bit / 8] as u32) << 8
+                    | (data[3 + bit / 8] as u32) << 16;
+                ((raw >> (bit % 8)) & 0x7FF) as u16
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_encode_packs_channels_and_flags() {
+        let order = [ControlRole::Aileron, ControlRole::Thrust, ControlRole::Arm];
+        let frame = OutputFrame {
+            channels: MixerOutMsg::from_roles(&order, |role| match role {
+                ControlRole::Aileron => 0,
+                ControlRole::Thrust => 10000,
+                _ => 7500,
+            }),
+            failsafe: false,
+        };
+
+        let data = encode_frame(&frame, &[ControlRole::Arm, ControlRole::Mode]);
+        assert_eq!((data[0], data[24]), (SBUS_HEADER, SBUS_FOOTER));
+        let channels = unpack_channels(&data);
+        assert_eq!(&channels[..4], &[172, 1811, 1401, 992]);
+        assert!(channels[4..].iter().all(|value| *value == 992));
+        assert_eq!(data[23], FLAG_CH17);
+
+        let failsafe = OutputFrame {
+            failsafe: true,
+            ..frame
+        };
+        assert_eq!(
+            encode_frame(&failsafe, &[])[23],
+            FLAG_FRAME_LOST | FLAG_FAILSAFE
+        );
+    }
+
+    #[test]
+    fn test_inverter_gpio_follows_config() {
+        let dir = std::env::temp_dir().join(format!("lintx-sbus-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let value = dir.join("value");
+        let mut config = SbusConfig {
+            inverter_gpio: value.display().to_string(),
+            ..SbusConfig::default()
+        };
+
+        set_inverter(&config).unwrap();
+        assert_eq!(fs::read_to_string(&value).unwrap(), "1");
+        assert_eq!(fs::read_to_string(dir.join("direction")).unwrap(), "out");
+        config.inverted = false;
+        set_inverter(&config).unwrap();
+        assert_eq!(fs::read_to_string(&value).unwrap(), "0");
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,137 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, Write},
+    time::Duration,
+};
+
+use super::{OutputDriver, OutputFrame};
+use crate::{config::ControlRole, mixer::MixerOutMsg};
+
+const HID_FRAME_INTERVAL: Duration = Duration::from_millis(10);
+
+/// USB HID Gamepad Report Format (7 bytes) - PS4/PS5 风格布局:
+///
+/// Byte 0-1: 16 buttons (bit flags, 2 bytes)
+///   Button 1-4 = Arm / Mode / Aux1 / Aux2 开关 (通道值 > 7500 视为按下)
+///
+/// 左摇杆 (Throttle + Rudder):
+///   Byte 2: X axis = Rudder/Direction (CH4 in AETR, -127~127, 回中)
+///   Byte 3: Y axis = Throttle (CH3 in AETR, -127~127, 不回中)
+///
+/// 右摇杆 (Aileron + Elevator):  
+///   Byte 4: Rx axis = Aileron/Roll (CH1 in AETR, -127~127, 回中)
+///   Byte 5: Ry axis = Elevator/Pitch (CH2 in AETR, -127~127, 回中)
+///
+/// Byte 6: Reserved (padding)
+///
+/// AETR 通道顺序: CH1=Aileron, CH2=Elevator, CH3=Throttle, CH4=Rudder
+#[repr(C, packed)]
+struct HidGamepadReport {
+    buttons_lo: u8, // Buttons 1-8
+    buttons_hi: u8, // Buttons 9-16
+    left_x: i8,     // 左摇杆X = Rudder/Direction (方向舵)
+    left_y: i8,     // 左摇杆Y = Throttle (油门)
+    right_x: i8,    // 右摇杆X = Aileron (副翼)
+    right_y: i8,    // 右摇杆Y = Elevator (升降)
+    _reserved: u8,  // 填充字节
+}
+
+impl HidGamepadReport {
+    fn new() -> Self {
+        Self {
+            buttons_lo: 0,
+            buttons_hi: 0,
+            left_x: 0,    // Rudder 中位
+            left_y: -127, // Throttle 最低 (对应 -127)
+            right_x: 0,   // Aileron 中位
+            right_y: 0,   // Elevator 中位
+            _reserved: 0,
+        }
+    }
+
+    fn to_bytes(&self) -> [u8; 7] {
+        [
+            self.buttons_lo,
+            self.buttons_hi,
+            self.left_x as u8,
+            self.left_y as u8,
+            self.right_x as u8,
+            self.right_y as u8,
+            self._reserved,
+        ]
+    }
+}
+
+/// Convert mixer value (0~10000) to HID axis value (-127~127)
+/// Mixer 输出范围: 0 ~ 10000, 中心值 5000
+/// HID 双向轴范围: -127 ~ 127, 中心值 0
+fn mixer_to_hid_axis(mixer_value: u16) -> i8 {
+    let normalized = (mixer_value as i32 - 5000) as f32 / 5000.0; // -1.0 ~ +1.0
+    let hid_value = (normalized * 127.0) as i32;
+    hid_value.clamp(-127, 127) as i8
+}
+
+/// Switch roles reported as HID buttons, in button bit order.
+const BUTTON_ROLES: [ControlRole; 4] = [
+    ControlRole::Arm,
+    ControlRole::Mode,
+    ControlRole::Aux1,
+    ControlRole::Aux2,
+];
+
+fn mixer_to_hid_buttons(msg: &MixerOutMsg) -> u16 {
+    BUTTON_ROLES
+        .iter()
+        .enumerate()
+        .filter(|(_, role)| msg.value_of(**role).is_some_and(|value| value > 7500))
+        .fold(0u16, |bits, (bit, _)| bits | (1 << bit))
+}
+
+/// Convert mixer throttle (0~10000) to HID axis (-127~127)
+/// Mixer 油门: 0 = 最低, 10000 = 最高
+/// HID 轴: -127 = 最低, 127 = 最高
+fn mixer_throttle_to_hid_axis(mixer_value: u16) -> i8 {
+    let normalized = (mixer_value as i32 - 5000) as f32 / 5000.0; // -1.0 ~ +1.0
+    let hid_value = (normalized * 127.0) as i32;
+    hid_value.clamp(-127, 127) as i8
+}
+
+/// Gamepad report on a USB gadget HID device. Axes are taken by
+/// `ControlRole`, so `channel_order` does not matter here.
+pub struct HidOutput {
+    device: File,
+}
+
+impl HidOutput {
+    pub fn open(path: &str) -> io::Result<Self> {
+        Ok(Self {
+            device: OpenOptions::new().write(true).open(path)?,
+        })
+    }
+}
+
+impl OutputDriver for HidOutput {
+    fn frame_interval(&self) -> Duration {
+        HID_FRAME_INTERVAL
+    }
+
+    fn send(&mut self, frame: &OutputFrame) -> io::Result<()> {
+        let msg = &frame.channels;
+        let axis = |role| msg.value_of(role).unwrap_or(5000);
+        let mut report = HidGamepadReport::new();
+
+        // PS4/PS5 风格 AETR 映射:
+        // 左摇杆: X=Rudder/Direction, Y=Throttle
+        // 右摇杆: X=Aileron, Y=Elevator
+        report.left_x = mixer_to_hid_axis(axis(ControlRole::Direction)); // 左摇杆X = Rudder
+        report.left_y = mixer_throttle_to_hid_axis(axis(ControlRole::Thrust)); // 左摇杆Y = Throttle
+        report.right_x = mixer_to_hid_axis(axis(ControlRole::Aileron)); // 右摇杆X = Aileron
+        report.right_y = mixer_to_hid_axis(axis(ControlRole::Elevator)); // 右摇杆Y = Elevator
+
+        let buttons = mixer_to_hid_buttons(msg);
+        report.buttons_lo = (buttons & 0xFF) as u8; // Buttons 1-8
+        report.buttons_hi = (buttons >> 8) as u8; // Buttons 9-16
+
+        self.device.write_all(&report.to_bytes())
+    }
+}
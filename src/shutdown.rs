@@ -0,0 +1,48 @@
+//! Cleanup modules register for when the server is stopped with SIGTERM
+//! (`stop_lintx.sh`) or Ctrl-C, e.g. saving state they only keep in memory.
+
+use std::sync::Mutex;
+
+use rpos::thread_logln;
+
+type Hook = Box<dyn FnOnce() + Send>;
+
+static HOOKS: Mutex<Vec<Hook>> = Mutex::new(Vec::new());
+
+/// Runs `hook` once when the server shuts down.
+pub fn on_shutdown(hook: impl FnOnce() + Send + 'static) {
+    HOOKS
+        .lock()
+        .unwrap_or_else(|err| err.into_inner())
+        .push(Box::new(hook));
+}
+
+#[cfg(target_os = "linux")]
+fn run_hooks() {
+    let hooks = std::mem::take(&mut *HOOKS.lock().unwrap_or_else(|err| err.into_inner()));
+    for hook in hooks {
+        hook();
+    }
+}
+
+/// Blocks SIGTERM and SIGINT in this thread and every thread started after
+/// it, and waits for them on a thread of its own that runs the hooks and
+/// exits. Call before any module starts.
+#[cfg(target_os = "linux")]
+pub fn install() {
+    let set = unsafe {
+        let mut set: libc::sigset_t = std::mem::zeroed();
+        libc::sigemptyset(&mut set);
+        libc::sigaddset(&mut set, libc::SIGTERM);
+        libc::sigaddset(&mut set, libc::SIGINT);
+        libc::pthread_sigmask(libc::SIG_BLOCK, &set, std::ptr::null_mut());
+        set
+    };
+    std::thread::spawn(move || {
+        let mut signal = 0;
+        unsafe { libc::sigwait(&set, &mut signal) };
+        thread_logln!("signal {} received, shutting down", signal);
+        run_hooks();
+        std::process::exit(0);
+    });
+}
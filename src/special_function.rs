@@ -0,0 +1,169 @@
+use std::{
+    fs,
+    io::{self, Write},
+    path::PathBuf,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    config::{ControlRole, SpecialAction, SpecialFunctionConfig},
+    logical_switch::switch_active,
+};
+
+pub const LOGS_DIR: &str = "logs";
+
+/// What the special functions want from the current mixer cycle.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct SpecialFunctionOutput<'a> {
+    /// `(slot, value)` pairs, value in 0..10000.
+    pub overrides: Vec<(usize, u16)>,
+    /// Logging interval while a logging function is on.
+    pub logging: Option<Duration>,
+    /// One-shot actions whose switch turned on this cycle.
+    pub triggered: Vec<&'a SpecialAction>,
+}
+
+/// Tracks switch edges so one-shot actions fire once per activation.
+#[derive(Debug, Default)]
+pub struct SpecialFunctionEngine {
+    was_on: Vec<bool>,
+}
+
+impl SpecialFunctionEngine {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn evaluate<'a>(
+        &mut self,
+        functions: &'a [SpecialFunctionConfig],
+        inputs: &[(ControlRole, i32)],
+        logical_states: &[bool],
+    ) -> SpecialFunctionOutput<'a> {
+        if self.was_on.len() != functions.len() {
+            // A new model starts with every switch "already on" so nothing
+            // fires just because the model was loaded.
+            self.was_on = functions
+                .iter()
+                .map(|function| switch_active(function.switch, inputs, logical_states))
+                .collect();
+        }
+
+        let mut output = SpecialFunctionOutput::default();
+        for (function, was_on) in functions.iter().zip(self.was_on.iter_mut()) {
+            let on = switch_active(function.switch, inputs, logical_states);
+            let rising = on && !*was_on;
+            *was_on = on;
+            if !on {
+                continue;
+            }
+
+            match &function.action {
+                SpecialAction::OverrideChannel { channel, value } => output.overrides.push((
+                    *channel as usize,
+                    ((*value as i32 + 1000) * 5).clamp(0, 10000) as u16,
+                )),
+                SpecialAction::Logging { interval_ms } => {
+                    output.logging = Some(Duration::from_millis((*interval_ms).max(1) as u64));
+                }
+                action if rising => output.triggered.push(action),
+                _ => {}
+            }
+        }
+        output
+    }
+}
+
+/// CSV log of the output channels, one file per logging session.
+pub struct ChannelLogger {
+    file: fs::File,
+}
+
+impl ChannelLogger {
+    pub fn create(model_id: &str, channels: &[ControlRole]) -> io::Result<Self> {
+        fs::create_dir_all(LOGS_DIR)?;
+        let started = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let path = PathBuf::from(LOGS_DIR).join(format!("{model_id}-{started}.csv"));
+        let mut file = fs::File::create(path)?;
+        let header: Vec<&str> = channels.iter().map(|role| role.short_name()).collect();
+        writeln!(file, "time_ms,{}", header.join(","))?;
+        Ok(Self { file })
+    }
+
+    /// Appends a row taken at `time_ms` (Unix time).
+    pub fn log(&mut self, time_ms: u128, values: &[u16]) -> io::Result<()> {
+        let row: Vec<String> = values.iter().map(|value| value.to_string()).collect();
+        writeln!(self.file, "{time_ms},{}", row.join(","))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{SwitchRef, UiPageRef};
+
+    #[test]
+    fn test_one_shot_actions_fire_on_rising_edge_only() {
+        let functions = [
+            SpecialFunctionConfig {
+                switch: SwitchRef::Logical(0),
+                action: SpecialAction::OverrideChannel {
+                    channel: 2,
+                    value: -1000,
+                },
+            },
+            SpecialFunctionConfig {
+                switch: SwitchRef::Logical(0),
+                action: SpecialAction::ElrsBind,
+            },
+            SpecialFunctionConfig {
+                switch: SwitchRef::Input(ControlRole::Aux1),
+                action: SpecialAction::OpenPage {
+                    page: UiPageRef::Control,
+                },
+            },
+        ];
+        let mut engine = SpecialFunctionEngine::new();
+        let aux_on = [(ControlRole::Aux1, 5000)];
+
+        let output = engine.evaluate(&functions, &aux_on, &[false]);
+        assert!(output.overrides.is_empty());
+        assert!(
+            output.triggered.is_empty(),
+            "switches already on at load do not fire"
+        );
+
+        let output = engine.evaluate(&functions, &[], &[true]);
+        assert_eq!(output.overrides, vec![(2, 0)]);
+        assert_eq!(output.triggered, vec![&SpecialAction::ElrsBind]);
+
+        let output = engine.evaluate(&functions, &aux_on, &[true]);
+        assert_eq!(output.overrides, vec![(2, 0)]);
+        assert_eq!(
+            output.triggered,
+            vec![&SpecialAction::OpenPage {
+                page: UiPageRef::Control
+            }]
+        );
+    }
+
+    #[test]
+    fn test_logging_stays_on_with_switch() {
+        let functions = [SpecialFunctionConfig {
+            switch: SwitchRef::Logical(0),
+            action: SpecialAction::Logging { interval_ms: 250 },
+        }];
+        let mut engine = SpecialFunctionEngine::new();
+
+        assert_eq!(engine.evaluate(&functions, &[], &[false]).logging, None);
+        for _ in 0..2 {
+            assert_eq!(
+                engine.evaluate(&functions, &[], &[true]).logging,
+                Some(Duration::from_millis(250))
+            );
+        }
+    }
+}
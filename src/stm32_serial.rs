@@ -1,4 +1,4 @@
-use crate::{client_process_args, messages::AdcRawMsg};
+use crate::{client_process_args, config::InputSource, messages::InputFrameMsg};
 use clap::Parser;
 use crc::{Crc, CRC_8_DVB_S2};
 use rpos::{msg::get_new_tx_of_message, thread_logln};
@@ -34,7 +34,7 @@ pub fn stm32_serial_main(argc: u32, argv: *const &str) {
         .open()
         .expect("Failed to open serial port");
 
-    let adc_raw_tx = get_new_tx_of_message::<AdcRawMsg>("adc_raw").unwrap();
+    let input_tx = get_new_tx_of_message::<InputFrameMsg>("input_frame").unwrap();
     let crc_alg = Crc::<u8>::new(&CRC_8_DVB_S2);
 
     let mut read_buffer = [0u8; 64];
@@ -74,7 +74,7 @@ pub fn stm32_serial_main(argc: u32, argv: *const &str) {
                                 let computed_crc = crc_alg.checksum(&payload[0..data_len - 1]);
 
                                 if computed_crc == received_crc {
-                                    handle_packet(&payload, &adc_raw_tx);
+                                    handle_packet(&payload, &input_tx);
                                 } else {
                                     // CRC Error, don't log every time to avoid spamming if baudrate is wrong
                                 }
@@ -93,7 +93,7 @@ pub fn stm32_serial_main(argc: u32, argv: *const &str) {
     }
 }
 
-fn handle_packet(payload: &[u8], tx: &rpos::channel::Sender<AdcRawMsg>) {
+fn handle_packet(payload: &[u8], tx: &rpos::channel::Sender<InputFrameMsg>) {
     if payload.is_empty() {
         return;
     }
@@ -106,7 +106,7 @@ fn handle_packet(payload: &[u8], tx: &rpos::channel::Sender<AdcRawMsg>) {
             // Payload passed here contains [Type ... CRC]
             if payload.len() >= 10 {
                 // Type(1) + 4*U16(8) + CRC(1) = 10
-                let mut channels = [0i16; 4];
+                let mut channels = vec![0i16; 4];
                 for i in 0..4 {
                     let start = 1 + i * 2;
                     if start + 1 < payload.len() {
@@ -114,7 +114,13 @@ fn handle_packet(payload: &[u8], tx: &rpos::channel::Sender<AdcRawMsg>) {
                             u16::from_le_bytes([payload[start], payload[start + 1]]) as i16;
                     }
                 }
-                tx.send(AdcRawMsg { value: channels });
+                let mut frame = InputFrameMsg::from_axes(InputSource::Stm32Serial, channels);
+                // Buttons byte follows the sticks; older firmware ends at the CRC instead.
+                if payload.len() >= 11 {
+                    let buttons = payload[9];
+                    frame.buttons = (0..8).map(|bit| buttons & (1 << bit) != 0).collect();
+                }
+                tx.send(frame);
             }
         }
         _ => {
@@ -1,11 +1,11 @@
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use clap::Parser;
-use rpos::msg::get_new_tx_of_message;
+use rpos::msg::{get_new_rx_of_message, get_new_tx_of_message};
 
 use crate::{
     client_process_args,
-    messages::{SystemConfigMsg, SystemStatusMsg},
+    messages::{SystemConfigMsg, SystemStatusMsg, TelemetryMsg},
 };
 
 #[derive(Parser)]
@@ -23,6 +23,9 @@ fn system_state_mock_main(argc: u32, argv: *const &str) {
 
     let status_tx = get_new_tx_of_message::<SystemStatusMsg>("system_status").unwrap();
     let config_tx = get_new_tx_of_message::<SystemConfigMsg>("system_config").unwrap();
+    let mut telemetry_rx = get_new_rx_of_message::<TelemetryMsg>("telemetry").unwrap();
+    let mut telemetry = TelemetryMsg::default();
+    let mut telemetry_seen = false;
 
     let interval = Duration::from_millis((1000 / args.hz.max(1)) as u64);
     let mut tick: u64 = 0;
@@ -33,10 +36,28 @@ fn system_state_mock_main(argc: u32, argv: *const &str) {
             .unwrap_or_else(|_| Duration::from_secs(0))
             .as_secs();
 
+        while let Some(msg) = telemetry_rx.try_read() {
+            telemetry_seen |= msg.link_quality.is_some() || msg.battery_remaining.is_some();
+            telemetry = msg;
+        }
+
+        // Link quality and pack level are only mocked until the `telemetry`
+        // module reported them once; after that a lost link reads 0.
+        let (signal, aircraft_battery) = if telemetry_seen {
+            (
+                telemetry.link_quality.unwrap_or(0),
+                telemetry.battery_remaining.unwrap_or(0),
+            )
+        } else {
+            (
+                (60 + (tick % 40) as u8).min(100),
+                (95_u64.saturating_sub((tick / 15) % 90)) as u8,
+            )
+        };
         let status = SystemStatusMsg {
             remote_battery_percent: (100_u64.saturating_sub((tick / 10) % 100)) as u8,
-            aircraft_battery_percent: (95_u64.saturating_sub((tick / 15) % 90)) as u8,
-            signal_strength_percent: (60 + (tick % 40) as u8).min(100),
+            aircraft_battery_percent: aircraft_battery,
+            signal_strength_percent: signal,
             unix_time_secs: now,
         };
 
@@ -0,0 +1,662 @@
+use std::time::{Duration, Instant};
+
+use clap::Parser;
+use rpos::{
+    msg::{get_new_rx_of_message, get_new_tx_of_message},
+    thread_logln,
+};
+
+use crate::{
+    client_process_args,
+    config::{store, DerivedSensor, ModelConfig, TelemetryConfig},
+    crsf::Frame,
+    messages::{ActiveModelMsg, CrsfFrameMsg, SensorReading, TelemetryCommandMsg, TelemetryMsg},
+};
+
+/// Link statistics older than this mean the link is lost.
+pub const LINK_LOST_TIMEOUT: Duration = Duration::from_millis(1000);
+
+/// Uplink power levels indexed by the link statistics `tx_power` field, in mW.
+const TX_POWER_MW: [i32; 9] = [0, 10, 25, 100, 500, 1000, 2000, 250, 50];
+
+#[derive(Parser)]
+#[command(name = "telemetry", about = "Decode CRSF telemetry into model sensors", long_about = None)]
+struct Cli {
+    #[arg(long, default_value_t = 10)]
+    hz: u32,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LinkStats {
+    /// Uplink RSSI per antenna in dBm (negative).
+    pub rssi_1: i16,
+    pub rssi_2: i16,
+    /// Uplink link quality in percent.
+    pub link_quality: u8,
+    pub snr: i8,
+    pub antenna: u8,
+    pub rf_mode: u8,
+    pub tx_power_mw: i32,
+    pub rx_rssi: i16,
+    pub rx_quality: u8,
+    pub rx_snr: i8,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Battery {
+    /// 0.1 V.
+    pub voltage: u16,
+    /// 0.1 A.
+    pub current: u16,
+    /// mAh drawn.
+    pub capacity: u32,
+    pub remaining: u8,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Gps {
+    /// Degrees * 1e7.
+    pub latitude: i32,
+    pub longitude: i32,
+    /// 0.1 km/h.
+    pub ground_speed: u16,
+    /// 0.01 degrees.
+    pub heading: u16,
+    /// Meters.
+    pub altitude: i32,
+    pub satellites: u8,
+}
+
+/// Attitude in 0.1 degrees.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Attitude {
+    pub pitch: i32,
+    pub roll: i32,
+    pub yaw: i32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TelemetryFrame {
+    LinkStats(LinkStats),
+    Battery(Battery),
+    Gps(Gps),
+    Attitude(Attitude),
+    FlightMode(String),
+    /// Altitude in dm, plus vertical speed in cm/s when the sender includes it.
+    Baro {
+        altitude: i32,
+        vario: Option<i16>,
+    },
+    /// Vertical speed in cm/s.
+    Vario(i16),
+}
+
+/// CRSF sends angles as radians * 10000.
+fn decidegrees(raw: i16) -> i32 {
+    (raw as f32 * 1800.0 / std::f32::consts::PI / 10000.0).round() as i32
+}
+
+/// Decodes one complete CRSF frame into telemetry. Frames that fail the CRC,
+/// are not telemetry, or are too short give `None`.
+pub fn decode_frame(frame: &[u8]) -> Option<TelemetryFrame> {
+    match Frame::decode(frame)?.1 {
+        Frame::LinkStatistics(link) => Some(TelemetryFrame::LinkStats(LinkStats {
+            rssi_1: -(link.uplink_rssi_1 as i16),
+            rssi_2: -(link.uplink_rssi_2 as i16),
+            link_quality: link.uplink_link_quality,
+            snr: link.uplink_snr,
+            antenna: link.active_antenna,
+            rf_mode: link.rf_mode,
+            tx_power_mw: TX_POWER_MW
+                .get(link.uplink_tx_power as usize)
+                .copied()
+                .unwrap_or(0),
+            rx_rssi: -(link.downlink_rssi as i16),
+            rx_quality: link.downlink_link_quality,
+            rx_snr: link.downlink_snr,
+        })),
+        Frame::Battery(battery) => Some(TelemetryFrame::Battery(Battery {
+            voltage: battery.voltage,
+            current: battery.current,
+            capacity: battery.capacity,
+            remaining: battery.remaining,
+        })),
+        Frame::Gps(gps) => Some(TelemetryFrame::Gps(Gps {
+            latitude: gps.latitude,
+            longitude: gps.longitude,
+            ground_speed: gps.ground_speed,
+            heading: gps.heading,
+            altitude: gps.altitude as i32 - 1000,
+            satellites: gps.satellites,
+        })),
+        Frame::Attitude(attitude) => Some(TelemetryFrame::Attitude(Attitude {
+            pitch: decidegrees(attitude.pitch),
+            roll: decidegrees(attitude.roll),
+            yaw: decidegrees(attitude.yaw),
+        })),
+        Frame::FlightMode(mode) => Some(TelemetryFrame::FlightMode(mode)),
+        Frame::BaroAltitude {
+            altitude: raw,
+            vario,
+        } => {
+            // MSB set: whole meters, otherwise dm with a 10000 dm offset.
+            let altitude = if raw & 0x8000 != 0 {
+                (raw & 0x7FFF) as i32 * 10
+            } else {
+                raw as i32 - 10000
+            };
+            Some(TelemetryFrame::Baro { altitude, vario })
+        }
+        Frame::Vario(vario) => Some(TelemetryFrame::Vario(vario)),
+        _ => None,
+    }
+}
+
+/// Latest decoded values with the time their frame arrived. Link values drop
+/// out after `LINK_LOST_TIMEOUT`.
+#[derive(Debug, Default)]
+pub struct TelemetryState {
+    link: Option<(LinkStats, Instant)>,
+    battery: Option<(Battery, Instant)>,
+    gps: Option<(Gps, Instant)>,
+    attitude: Option<(Attitude, Instant)>,
+    flight_mode: Option<String>,
+    altitude: Option<(i32, Instant)>,
+    vario: Option<(i16, Instant)>,
+}
+
+impl TelemetryState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn apply(&mut self, frame: TelemetryFrame, now: Instant) {
+        match frame {
+            TelemetryFrame::LinkStats(link) => self.link = Some((link, now)),
+            TelemetryFrame::Battery(battery) => self.battery = Some((battery, now)),
+            TelemetryFrame::Gps(gps) => self.gps = Some((gps, now)),
+            TelemetryFrame::Attitude(attitude) => self.attitude = Some((attitude, now)),
+            TelemetryFrame::FlightMode(mode) => self.flight_mode = Some(mode),
+            TelemetryFrame::Baro { altitude, vario } => {
+                self.altitude = Some((altitude, now));
+                if let Some(vario) = vario {
+                    self.vario = Some((vario, now));
+                }
+            }
+            TelemetryFrame::Vario(vario) => self.vario = Some((vario, now)),
+        }
+    }
+
+    fn link(&self, now: Instant) -> Option<&(LinkStats, Instant)> {
+        self.link
+            .as_ref()
+            .filter(|(_, at)| now.saturating_duration_since(*at) < LINK_LOST_TIMEOUT)
+    }
+
+    /// Raw value of a sensor by `TelemetrySensorConfig::key`, in the units the
+    /// CRSF frame carries (see the README sensor table), and when it arrived.
+    pub fn reading(&self, key: &str, now: Instant) -> Option<(i32, Instant)> {
+        fn with_time<T>(
+            group: Option<&(T, Instant)>,
+            value: impl Fn(&T) -> i32,
+        ) -> Option<(i32, Instant)> {
+            group.map(|(data, at)| (value(data), *at))
+        }
+
+        let link = self.link(now);
+        let battery = self.battery.as_ref();
+        let gps = self.gps.as_ref();
+        let attitude = self.attitude.as_ref();
+        match key {
+            "rssi" => with_time(link, |link| link.rssi_1.max(link.rssi_2) as i32),
+            "rssi_1" => with_time(link, |link| link.rssi_1 as i32),
+            "rssi_2" => with_time(link, |link| link.rssi_2 as i32),
+            "link_quality" => with_time(link, |link| link.link_quality as i32),
+            "snr" => with_time(link, |link| link.snr as i32),
+            "antenna" => with_time(link, |link| link.antenna as i32),
+            "rf_mode" => with_time(link, |link| link.rf_mode as i32),
+            "tx_power" => with_time(link, |link| link.tx_power_mw),
+            "rx_rssi" => with_time(link, |link| link.rx_rssi as i32),
+            "rx_quality" => with_time(link, |link| link.rx_quality as i32),
+            "rx_snr" => with_time(link, |link| link.rx_snr as i32),
+            "voltage" => with_time(battery, |battery| battery.voltage as i32),
+            "current" => with_time(battery, |battery| battery.current as i32),
+            "capacity" => with_time(battery, |battery| battery.capacity as i32),
+            "battery_remaining" => with_time(battery, |battery| battery.remaining as i32),
+            "gps_lat" => with_time(gps, |gps| gps.latitude),
+            "gps_lon" => with_time(gps, |gps| gps.longitude),
+            "gps_speed" => with_time(gps, |gps| gps.ground_speed as i32),
+            "gps_heading" => with_time(gps, |gps| gps.heading as i32),
+            "gps_altitude" => with_time(gps, |gps| gps.altitude),
+            "gps_sats" => with_time(gps, |gps| gps.satellites as i32),
+            "pitch" => with_time(attitude, |attitude| attitude.pitch),
+            "roll" => with_time(attitude, |attitude| attitude.roll),
+            "yaw" => with_time(attitude, |attitude| attitude.yaw),
+            "altitude" => with_time(self.altitude.as_ref(), |altitude| *altitude),
+            "vario" => with_time(self.vario.as_ref(), |vario| *vario as i32),
+            _ => None,
+        }
+    }
+
+    pub fn message(&self, sensors: Vec<SensorReading>, now: Instant) -> TelemetryMsg {
+        let link = self.link(now).map(|(link, _)| link);
+        let battery = self.battery.as_ref().map(|(battery, _)| battery);
+        TelemetryMsg {
+            sensors,
+            flight_mode: self.flight_mode.clone().unwrap_or_default(),
+            link_quality: link.map(|link| link.link_quality),
+            rssi: link.map(|link| link.rssi_1.max(link.rssi_2)),
+            battery_voltage: battery.map(|battery| battery.voltage),
+            battery_remaining: battery.map(|battery| battery.remaining),
+        }
+    }
+}
+
+/// Pack voltage above which another cell is assumed, in 0.1 V.
+const CELL_DETECT_MAX: i32 = 43;
+const EARTH_RADIUS_M: f64 = 6_371_000.0;
+
+/// Ground distance in meters between two positions in degrees * 1e7.
+fn gps_distance(from: (i32, i32), to: (i32, i32)) -> i32 {
+    let to_rad = |value: i32| (value as f64 * 1e-7).to_radians();
+    let mean_lat = (to_rad(from.0) + to_rad(to.0)) / 2.0;
+    let x = (to_rad(to.1) - to_rad(from.1)) * mean_lat.cos();
+    let y = to_rad(to.0) - to_rad(from.0);
+    (EARTH_RADIUS_M * (x * x + y * y).sqrt()).round() as i32
+}
+
+#[derive(Debug, Clone, Default)]
+struct SensorRuntime {
+    min: Option<i32>,
+    max: Option<i32>,
+    /// `Consumption`: charge drawn in 0.1 A * ms.
+    consumed: Option<u64>,
+    /// `Consumption`: arrival of the last current sample integrated.
+    last_sample: Option<Instant>,
+    /// `Distance`: first GPS fix of the flight.
+    home: Option<(i32, i32)>,
+    /// `CellVoltage`: detected cell count.
+    cells: Option<i32>,
+}
+
+/// Per-flight sensor values: scaling, lost detection, min/max and derived
+/// sensors. `reset` starts a new flight.
+#[derive(Debug, Default)]
+pub struct SensorTracker {
+    sensors: Vec<SensorRuntime>,
+}
+
+impl SensorTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn reset(&mut self) {
+        self.sensors.clear();
+    }
+
+    pub fn update(
+        &mut self,
+        config: &TelemetryConfig,
+        state: &TelemetryState,
+        now: Instant,
+    ) -> Vec<SensorReading> {
+        if !config.enabled {
+            return Vec::new();
+        }
+        if self.sensors.len() != config.sensors.len() {
+            self.sensors = vec![SensorRuntime::default(); config.sensors.len()];
+        }
+
+        let mut readings = Vec::new();
+        for (sensor, runtime) in config.sensors.iter().zip(self.sensors.iter_mut()) {
+            if !sensor.enabled {
+                continue;
+            }
+
+            let raw = match &sensor.derived {
+                None => state.reading(&sensor.key, now),
+                Some(derived) => Self::derive(derived, runtime, state, now),
+            };
+            let value = raw
+                .filter(|(_, at)| {
+                    sensor.lost_timeout_ms == 0
+                        || now.saturating_duration_since(*at)
+                            < Duration::from_millis(sensor.lost_timeout_ms as u64)
+                })
+                .map(|(raw, _)| {
+                    (raw as i64 * sensor.ratio_percent as i64 / 100 + sensor.offset as i64) as i32
+                });
+            if let Some(value) = value {
+                runtime.min = Some(runtime.min.map_or(value, |min| min.min(value)));
+                runtime.max = Some(runtime.max.map_or(value, |max| max.max(value)));
+            }
+
+            readings.push(SensorReading {
+                key: sensor.key.clone(),
+                unit: sensor.unit.clone(),
+                precision: sensor.precision,
+                value,
+                min: runtime.min,
+                max: runtime.max,
+            });
+        }
+        readings
+    }
+
+    fn derive(
+        derived: &DerivedSensor,
+        runtime: &mut SensorRuntime,
+        state: &TelemetryState,
+        now: Instant,
+    ) -> Option<(i32, Instant)> {
+        match derived {
+            DerivedSensor::Consumption { current } => {
+                // Each new sample counts for the time since the one before;
+                // the same sample read again adds nothing.
+                if let Some((current, at)) = state.reading(current, now) {
+                    let elapsed = runtime
+                        .last_sample
+                        .map_or(Duration::ZERO, |last| at.saturating_duration_since(last));
+                    *runtime.consumed.get_or_insert(0) +=
+                        current.max(0) as u64 * elapsed.as_millis() as u64;
+                    runtime.last_sample = Some(at);
+                }
+                // Ages like the current sensor it is integrated from.
+                runtime
+                    .consumed
+                    .zip(runtime.last_sample)
+                    .map(|(consumed, at)| ((consumed / 36_000) as i32, at))
+            }
+            DerivedSensor::Distance => {
+                let (lat, at) = state.reading("gps_lat", now)?;
+                let (lon, _) = state.reading("gps_lon", now)?;
+                let has_fix = state
+                    .reading("gps_sats", now)
+                    .is_some_and(|(sats, _)| sats >= 4);
+                if !has_fix {
+                    return None;
+                }
+                let home = *runtime.home.get_or_insert((lat, lon));
+                Some((gps_distance(home, (lat, lon)), at))
+            }
+            DerivedSensor::CellVoltage { voltage, cells } => {
+                let (voltage, at) = state.reading(voltage, now)?;
+                let cells = if *cells > 0 {
+                    *cells as i32
+                } else {
+                    *runtime
+                        .cells
+                        .get_or_insert(((voltage + CELL_DETECT_MAX - 1) / CELL_DETECT_MAX).max(1))
+                };
+                Some((voltage * 10 / cells, at))
+            }
+        }
+    }
+}
+
+fn telemetry_main(argc: u32, argv: *const &str) {
+    let Some(args) = client_process_args::<Cli>(argc, argv) else {
+        return;
+    };
+
+    let mut frame_rx = get_new_rx_of_message::<CrsfFrameMsg>("crsf_rx").unwrap();
+    let mut active_model_rx = get_new_rx_of_message::<ActiveModelMsg>("active_model").unwrap();
+    let mut cmd_rx = get_new_rx_of_message::<TelemetryCommandMsg>("telemetry_cmd").unwrap();
+    let telemetry_tx = get_new_tx_of_message::<TelemetryMsg>("telemetry").unwrap();
+
+    let mut model: ModelConfig = store::load_active_model().unwrap_or_default();
+    let mut state = TelemetryState::new();
+    let mut tracker = SensorTracker::new();
+    let interval = Duration::from_millis((1000 / args.hz.max(1)) as u64);
+    thread_logln!("telemetry start for model `{}`", model.id);
+
+    loop {
+        while let Some(active) = active_model_rx.try_read() {
+            if active.model.id != model.id {
+                tracker.reset();
+            }
+            model = active.model;
+        }
+        while let Some(cmd) = cmd_rx.try_read() {
+            match cmd {
+                TelemetryCommandMsg::ResetStats => tracker.reset(),
+            }
+        }
+
+        let now = Instant::now();
+        while let Some(msg) = frame_rx.try_read() {
+            if let Some(frame) = decode_frame(&msg.frame) {
+                state.apply(frame, now);
+            }
+        }
+
+        let sensors = tracker.update(&model.telemetry, &state, now);
+        telemetry_tx.send(state.message(sensors, now));
+        std::thread::sleep(interval);
+    }
+}
+
+#[rpos::ctor::ctor]
+fn register() {
+    rpos::module::Module::register("telemetry", telemetry_main);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::TelemetrySensorConfig;
+
+    fn frame(frame_type: u8, payload: &[u8]) -> Vec<u8> {
+        crate::crsf::encode_raw(0xEA, frame_type, payload)
+    }
+
+    #[test]
+    fn test_decode_link_battery_and_gps() {
+        assert_eq!(
+            decode_frame(&frame(0x14, &[60, 72, 98, 9, 0, 6, 3, 55, 100, 0xF6])),
+            Some(TelemetryFrame::LinkStats(LinkStats {
+                rssi_1: -60,
+                rssi_2: -72,
+                link_quality: 98,
+                snr: 9,
+                antenna: 0,
+                rf_mode: 6,
+                tx_power_mw: 100,
+                rx_rssi: -55,
+                rx_quality: 100,
+                rx_snr: -10,
+            }))
+        );
+        assert_eq!(
+            decode_frame(&frame(
+                0x08,
+                &[0x00, 0xA8, 0x00, 0x64, 0x00, 0x04, 0xB0, 75]
+            )),
+            Some(TelemetryFrame::Battery(Battery {
+                voltage: 168,
+                current: 100,
+                capacity: 1200,
+                remaining: 75,
+            }))
+        );
+        let mut gps = Vec::new();
+        gps.extend_from_slice(&473_977_418_i32.to_be_bytes());
+        gps.extend_from_slice(&85_455_940_i32.to_be_bytes());
+        gps.extend_from_slice(&[0x01, 0x2C, 0x46, 0x50, 0x04, 0x1A, 11]);
+        assert_eq!(
+            decode_frame(&frame(0x02, &gps)),
+            Some(TelemetryFrame::Gps(Gps {
+                latitude: 473_977_418,
+                longitude: 85_455_940,
+                ground_speed: 300,
+                heading: 18000,
+                altitude: 50,
+                satellites: 11,
+            }))
+        );
+    }
+
+    #[test]
+    fn test_decode_attitude_mode_and_baro() {
+        // 0.5236 rad = 30 degrees.
+        assert_eq!(
+            decode_frame(&frame(0x1E, &[0x14, 0x74, 0xEB, 0x8C, 0x00, 0x00])),
+            Some(TelemetryFrame::Attitude(Attitude {
+                pitch: 300,
+                roll: -300,
+                yaw: 0,
+            }))
+        );
+        assert_eq!(
+            decode_frame(&frame(0x21, b"ACRO\0")),
+            Some(TelemetryFrame::FlightMode("ACRO".to_string()))
+        );
+        assert_eq!(
+            decode_frame(&frame(0x09, &[0x27, 0x7A, 0x00, 0x32])),
+            Some(TelemetryFrame::Baro {
+                altitude: 106,
+                vario: Some(50),
+            })
+        );
+        assert_eq!(decode_frame(&frame(0x16, &[0; 22])), None);
+    }
+
+    fn sensors(keys: &[&str]) -> TelemetryConfig {
+        TelemetryConfig {
+            enabled: true,
+            sensors: keys
+                .iter()
+                .map(|key| TelemetrySensorConfig::new(key, ""))
+                .collect(),
+        }
+    }
+
+    fn values(readings: &[SensorReading]) -> Vec<(&str, Option<i32>)> {
+        readings
+            .iter()
+            .map(|reading| (reading.key.as_str(), reading.value))
+            .collect()
+    }
+
+    #[test]
+    fn test_sensors_scale_track_extremes_and_go_lost() {
+        let mut config = sensors(&["rssi", "link_quality", "voltage", "altitude"]);
+        config.sensors[2].ratio_percent = 50;
+        config.sensors[2].offset = 2;
+        config.sensors[3].enabled = false;
+        let start = Instant::now();
+        let mut state = TelemetryState::new();
+        let mut tracker = SensorTracker::new();
+        let link = |lq: u8| {
+            TelemetryFrame::LinkStats(LinkStats {
+                rssi_1: -60,
+                rssi_2: -72,
+                link_quality: lq,
+                ..LinkStats::default()
+            })
+        };
+        let battery = |voltage: u16| {
+            TelemetryFrame::Battery(Battery {
+                voltage,
+                ..Battery::default()
+            })
+        };
+
+        state.apply(link(98), start);
+        state.apply(battery(168), start);
+        let readings = tracker.update(&config, &state, start);
+        assert_eq!(
+            values(&readings),
+            vec![
+                ("rssi", Some(-60)),
+                ("link_quality", Some(98)),
+                ("voltage", Some(86))
+            ]
+        );
+
+        let later = start + Duration::from_millis(500);
+        state.apply(link(40), later);
+        tracker.update(&config, &state, later);
+
+        let lost = later + LINK_LOST_TIMEOUT;
+        let readings = tracker.update(&config, &state, lost);
+        assert_eq!(
+            values(&readings),
+            vec![
+                ("rssi", None),
+                ("link_quality", None),
+                ("voltage", Some(86))
+            ]
+        );
+        assert_eq!((readings[1].min, readings[1].max), (Some(40), Some(98)));
+        assert_eq!(state.message(readings, lost).link_quality, None);
+
+        let readings = tracker.update(&config, &state, start + Duration::from_secs(5));
+        assert_eq!(readings[2].value, None, "voltage lost after its timeout");
+
+        tracker.reset();
+        let readings = tracker.update(&config, &state, lost);
+        assert_eq!((readings[1].min, readings[1].max), (None, None));
+    }
+
+    #[test]
+    fn test_derived_consumption_distance_and_cell_voltage() {
+        let mut config = sensors(&["mah", "dist", "cell"]);
+        config.sensors[0].derived = Some(DerivedSensor::Consumption {
+            current: "current".to_string(),
+        });
+        config.sensors[1].derived = Some(DerivedSensor::Distance);
+        config.sensors[2].derived = Some(DerivedSensor::CellVoltage {
+            voltage: "voltage".to_string(),
+            cells: 0,
+        });
+        let start = Instant::now();
+        let mut state = TelemetryState::new();
+        let mut tracker = SensorTracker::new();
+        let gps = |latitude: i32| {
+            TelemetryFrame::Gps(Gps {
+                latitude,
+                longitude: 85_455_940,
+                satellites: 8,
+                ..Gps::default()
+            })
+        };
+
+        // 10 A and a 4S pack at 16.8 V.
+        state.apply(
+            TelemetryFrame::Battery(Battery {
+                voltage: 168,
+                current: 100,
+                ..Battery::default()
+            }),
+            start,
+        );
+        state.apply(gps(473_977_418), start);
+        tracker.update(&config, &state, start);
+
+        // 0.001 degrees of latitude north is about 111 m; one minute at 10 A is 166 mAh.
+        let later = start + Duration::from_secs(60);
+        state.apply(gps(473_987_418), later);
+        state.apply(
+            TelemetryFrame::Battery(Battery {
+                voltage: 148,
+                current: 100,
+                ..Battery::default()
+            }),
+            later,
+        );
+        let readings = tracker.update(&config, &state, later);
+        assert_eq!(
+            values(&readings),
+            vec![("mah", Some(166)), ("dist", Some(111)), ("cell", Some(370))]
+        );
+
+        // Battery frames stop: the total holds, then goes lost with the sensor.
+        let readings = tracker.update(&config, &state, later + Duration::from_secs(1));
+        assert_eq!(readings[0].value, Some(166));
+        let readings = tracker.update(&config, &state, later + Duration::from_secs(60));
+        assert_eq!(readings[0].value, None);
+        assert_eq!(readings[0].max, Some(166));
+    }
+}
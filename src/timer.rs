@@ -0,0 +1,372 @@
+use std::time::{Duration, Instant};
+
+use crate::{
+    config::{ControlRole, ModelConfig, TimerConfig, TimerDirection, TimerRun},
+    logical_switch::switch_active,
+    messages::{TimerState, TimerStateMsg},
+};
+
+/// Throttle counts as active above 5% of its travel (centered units).
+const THROTTLE_IDLE: i32 = -4500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimerAlert {
+    Minute { timer: usize, minutes: i64 },
+    Countdown { timer: usize, secs: i64 },
+    Elapsed { timer: usize },
+}
+
+impl TimerAlert {
+    /// Name published on `audio_cue`.
+    pub fn sound_name(&self) -> String {
+        match self {
+            Self::Minute { timer, minutes } => format!("timer{}_minute_{}", timer + 1, minutes),
+            Self::Countdown { timer, secs } => format!("timer{}_countdown_{}", timer + 1, secs),
+            Self::Elapsed { timer } => format!("timer{}_elapsed", timer + 1),
+        }
+    }
+}
+
+fn display_secs(config: &TimerConfig, value_us: u64) -> i64 {
+    let elapsed = (value_us / 1_000_000) as i64;
+    match config.direction {
+        TimerDirection::Up => elapsed,
+        TimerDirection::Down => config.start_secs as i64 - elapsed,
+    }
+}
+
+fn alert_for(timer: usize, config: &TimerConfig, secs: i64) -> Option<TimerAlert> {
+    let minute = config.minute_alert && secs > 0 && secs % 60 == 0;
+    match config.direction {
+        TimerDirection::Up => minute.then_some(TimerAlert::Minute {
+            timer,
+            minutes: secs / 60,
+        }),
+        TimerDirection::Down if secs == 0 => Some(TimerAlert::Elapsed { timer }),
+        TimerDirection::Down if secs > 0 && secs <= config.countdown_secs as i64 => {
+            Some(TimerAlert::Countdown { timer, secs })
+        }
+        TimerDirection::Down => minute.then_some(TimerAlert::Minute {
+            timer,
+            minutes: secs / 60,
+        }),
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct TimerRuntime {
+    value_us: u64,
+    /// `StartOnSwitch` has seen its switch since the last reset.
+    latched: bool,
+    running: bool,
+}
+
+/// Runs the active model's timers from the mixer cycle.
+#[derive(Debug, Default)]
+pub struct TimerEngine {
+    model_id: String,
+    timers: Vec<TimerRuntime>,
+    last_tick: Option<Instant>,
+    /// A persistent timer changed since the last write.
+    dirty: bool,
+    /// A persistent timer stopped or was reset; write on the next `take_persist`.
+    persist_now: bool,
+}
+
+impl TimerEngine {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Picks up the model's timers when the active model changes, persistent
+    /// ones starting from `stored_ms`. Returns the previous model's id and
+    /// values if they still need to be written.
+    pub fn sync_model(
+        &mut self,
+        model: &ModelConfig,
+        stored_ms: &[u64],
+    ) -> Option<(String, Vec<u64>)> {
+        if self.model_id == model.id && self.timers.len() == model.timers.len() {
+            return None;
+        }
+
+        let previous = self.take_dirty();
+        self.model_id = model.id.clone();
+        self.timers = model
+            .timers
+            .iter()
+            .enumerate()
+            .map(|(index, timer)| TimerRuntime {
+                value_us: if timer.persistent {
+                    stored_ms.get(index).copied().unwrap_or(0) * 1000
+                } else {
+                    0
+                },
+                ..TimerRuntime::default()
+            })
+            .collect();
+        self.last_tick = None;
+        self.dirty = false;
+        self.persist_now = false;
+        previous
+    }
+
+    pub fn reset(&mut self, timers: &[TimerConfig], index: usize) {
+        let Some(runtime) = self.timers.get_mut(index) else {
+            return;
+        };
+        runtime.value_us = 0;
+        runtime.latched = false;
+        if timers.get(index).is_some_and(|timer| timer.persistent) {
+            self.dirty = true;
+            self.persist_now = true;
+        }
+    }
+
+    pub fn tick(
+        &mut self,
+        timers: &[TimerConfig],
+        inputs: &[(ControlRole, i32)],
+        logical_states: &[bool],
+        now: Instant,
+    ) -> Vec<TimerAlert> {
+        let dt = self
+            .last_tick
+            .map_or(Duration::ZERO, |last| now.saturating_duration_since(last));
+        self.last_tick = Some(now);
+
+        let throttle = inputs
+            .iter()
+            .find(|(role, _)| *role == ControlRole::Thrust)
+            .map_or(-5000, |(_, value)| *value);
+        let mut alerts = Vec::new();
+        for (index, (config, runtime)) in timers.iter().zip(self.timers.iter_mut()).enumerate() {
+            let speed_permille: u64 = match config.run {
+                TimerRun::Always => 1000,
+                TimerRun::Switch { switch } => {
+                    if switch_active(switch, inputs, logical_states) {
+                        1000
+                    } else {
+                        0
+                    }
+                }
+                TimerRun::Throttle => {
+                    if throttle > THROTTLE_IDLE {
+                        1000
+                    } else {
+                        0
+                    }
+                }
+                TimerRun::ThrottleProportional => ((throttle + 5000).clamp(0, 10000) / 10) as u64,
+                TimerRun::StartOnSwitch { switch } => {
+                    runtime.latched |= switch_active(switch, inputs, logical_states);
+                    if runtime.latched {
+                        1000
+                    } else {
+                        0
+                    }
+                }
+            };
+
+            let was_running = runtime.running;
+            runtime.running = speed_permille > 0;
+            if config.persistent && was_running && !runtime.running {
+                self.persist_now = true;
+            }
+            if !runtime.running {
+                continue;
+            }
+
+            let before = display_secs(config, runtime.value_us);
+            runtime.value_us += dt.as_micros() as u64 * speed_permille / 1000;
+            let after = display_secs(config, runtime.value_us);
+            if config.persistent {
+                self.dirty = true;
+            }
+            if after != before {
+                alerts.extend(alert_for(index, config, after));
+            }
+        }
+        alerts
+    }
+
+    /// Values to write back, if a persistent timer stopped or was reset.
+    /// Running timers are only written on stop, model switch or shutdown.
+    pub fn take_persist(&mut self) -> Option<Vec<u64>> {
+        if !self.dirty || !self.persist_now {
+            return None;
+        }
+
+        self.dirty = false;
+        self.persist_now = false;
+        Some(self.values_ms())
+    }
+
+    /// The current model's id and values if they changed since the last
+    /// write, e.g. to save them on shutdown.
+    pub fn take_dirty(&mut self) -> Option<(String, Vec<u64>)> {
+        if !self.dirty || self.model_id.is_empty() {
+            return None;
+        }
+
+        self.dirty = false;
+        self.persist_now = false;
+        Some((self.model_id.clone(), self.values_ms()))
+    }
+
+    pub fn state(&self, timers: &[TimerConfig]) -> TimerStateMsg {
+        TimerStateMsg {
+            timers: timers
+                .iter()
+                .zip(&self.timers)
+                .enumerate()
+                .map(|(index, (config, runtime))| TimerState {
+                    name: if config.name.is_empty() {
+                        format!("T{}", index + 1)
+                    } else {
+                        config.name.clone()
+                    },
+                    value_secs: display_secs(config, runtime.value_us),
+                    running: runtime.running,
+                })
+                .collect(),
+        }
+    }
+
+    fn values_ms(&self) -> Vec<u64> {
+        self.timers
+            .iter()
+            .map(|runtime| runtime.value_us / 1000)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SwitchRef;
+
+    fn timer(direction: TimerDirection, run: TimerRun) -> TimerConfig {
+        TimerConfig {
+            name: String::new(),
+            direction,
+            start_secs: 0,
+            run,
+            minute_alert: false,
+            countdown_secs: 0,
+            persistent: false,
+        }
+    }
+
+    fn model(timers: Vec<TimerConfig>) -> ModelConfig {
+        ModelConfig {
+            timers,
+            ..ModelConfig::default()
+        }
+    }
+
+    fn secs(value: u64) -> Duration {
+        Duration::from_secs(value)
+    }
+
+    #[test]
+    fn test_countdown_raises_minute_countdown_and_elapsed_alerts() {
+        let model = model(vec![TimerConfig {
+            start_secs: 125,
+            minute_alert: true,
+            countdown_secs: 3,
+            ..timer(TimerDirection::Down, TimerRun::Always)
+        }]);
+        let start = Instant::now();
+        let mut engine = TimerEngine::new();
+        engine.sync_model(&model, &[]);
+
+        let mut alerts = Vec::new();
+        for second in 0..=127 {
+            alerts.extend(engine.tick(&model.timers, &[], &[], start + secs(second)));
+        }
+
+        assert_eq!(
+            alerts,
+            vec![
+                TimerAlert::Minute {
+                    timer: 0,
+                    minutes: 2
+                },
+                TimerAlert::Minute {
+                    timer: 0,
+                    minutes: 1
+                },
+                TimerAlert::Countdown { timer: 0, secs: 3 },
+                TimerAlert::Countdown { timer: 0, secs: 2 },
+                TimerAlert::Countdown { timer: 0, secs: 1 },
+                TimerAlert::Elapsed { timer: 0 },
+            ]
+        );
+        assert_eq!(engine.state(&model.timers).timers[0].value_secs, -2);
+    }
+
+    #[test]
+    fn test_throttle_and_switch_run_modes() {
+        let model = model(vec![
+            timer(TimerDirection::Up, TimerRun::Throttle),
+            timer(TimerDirection::Up, TimerRun::ThrottleProportional),
+            timer(
+                TimerDirection::Up,
+                TimerRun::StartOnSwitch {
+                    switch: SwitchRef::Input(ControlRole::Arm),
+                },
+            ),
+        ]);
+        let start = Instant::now();
+        let mut engine = TimerEngine::new();
+        engine.sync_model(&model, &[]);
+
+        let half_throttle_armed = [(ControlRole::Thrust, 0), (ControlRole::Arm, 5000)];
+        let idle_disarmed = [(ControlRole::Thrust, -5000), (ControlRole::Arm, -5000)];
+        engine.tick(&model.timers, &idle_disarmed, &[], start);
+        engine.tick(&model.timers, &half_throttle_armed, &[], start + secs(10));
+        engine.tick(&model.timers, &idle_disarmed, &[], start + secs(20));
+
+        let values: Vec<i64> = engine
+            .state(&model.timers)
+            .timers
+            .iter()
+            .map(|timer| timer.value_secs)
+            .collect();
+        assert_eq!(values, vec![10, 5, 20], "start-on-switch keeps running");
+    }
+
+    #[test]
+    fn test_persistent_values_survive_model_switch_and_reset() {
+        let arm = SwitchRef::Input(ControlRole::Arm);
+        let mut quad = model(vec![TimerConfig {
+            persistent: true,
+            ..timer(TimerDirection::Up, TimerRun::Switch { switch: arm })
+        }]);
+        quad.id = "quad".to_string();
+        let armed = [(ControlRole::Arm, 5000)];
+        let start = Instant::now();
+        let mut engine = TimerEngine::new();
+        assert_eq!(engine.sync_model(&quad, &[60_000]), None);
+
+        engine.tick(&quad.timers, &armed, &[], start);
+        engine.tick(&quad.timers, &armed, &[], start + secs(5));
+        assert_eq!(engine.take_persist(), None, "not written while running");
+        engine.tick(&quad.timers, &[], &[], start + secs(10));
+        assert_eq!(engine.take_persist(), Some(vec![65_000]), "written on stop");
+
+        engine.tick(&quad.timers, &armed, &[], start + secs(11));
+        engine.tick(&quad.timers, &armed, &[], start + secs(12));
+        let rover = model(Vec::new());
+        assert_eq!(
+            engine.sync_model(&rover, &[]),
+            Some(("quad".to_string(), vec![67_000]))
+        );
+
+        engine.sync_model(&quad, &[67_000]);
+        assert_eq!(engine.state(&quad.timers).timers[0].value_secs, 67);
+        engine.reset(&quad.timers, 0);
+        assert_eq!(engine.take_persist(), Some(vec![0]));
+    }
+}
@@ -6,9 +6,11 @@ use rpos::{
 };
 
 use crate::{
-    config::store,
+    config::{store, UiPageRef},
     messages::{
-        ActiveModelMsg, AdcRawMsg, ElrsCommandMsg, ElrsStateMsg, SystemConfigMsg, SystemStatusMsg,
+        ActiveModelMsg, ConfigChangeMsg, ElrsCommandMsg, ElrsStateMsg, FlightModeMsg,
+        InputFrameMsg, SystemConfigMsg, SystemStatusMsg, TelemetryCommandMsg, TelemetryMsg,
+        TimerStateMsg, UiCommandMsg,
     },
     mixer::MixerOutMsg,
 };
@@ -238,6 +240,63 @@ impl UiApp {
         }
     }
 
+    /// Shows a rejected config file until it is fixed, and picks up edited
+    /// model files for the MODELS page.
+    fn apply_config_change(&mut self, change: ConfigChangeMsg) -> bool {
+        if !change.error.is_empty() {
+            let error = format!("{}: {}", change.path, change.error);
+            return Self::update_field(&mut self.frame.config_error, error);
+        }
+        let before = (
+            self.frame.model_entries.clone(),
+            self.frame.model_active_idx,
+        );
+        self.reload_models();
+        let mut dirty = before
+            != (
+                self.frame.model_entries.clone(),
+                self.frame.model_active_idx,
+            );
+        if self
+            .frame
+            .config_error
+            .starts_with(&format!("{}: ", change.path))
+        {
+            self.frame.config_error.clear();
+            dirty = true;
+        }
+        dirty
+    }
+
+    /// Keeps the MODELS page in sync when something else (e.g. a special
+    /// function) switches the active model.
+    fn sync_active_model(&mut self, model_id: &str) -> bool {
+        let Some(idx) = self
+            .frame
+            .model_entries
+            .iter()
+            .position(|entry| entry.id == model_id)
+        else {
+            return false;
+        };
+        Self::update_field(&mut self.frame.model_active_idx, idx)
+    }
+
+    fn page_for(page: UiPageRef) -> UiPage {
+        match page {
+            UiPageRef::Launcher => UiPage::Launcher,
+            UiPageRef::System => UiPage::App(AppId::System),
+            UiPageRef::Control => UiPage::App(AppId::Control),
+            UiPageRef::Models => UiPage::App(AppId::Models),
+            UiPageRef::Flight => UiPage::App(AppId::Flight),
+            UiPageRef::Cloud => UiPage::App(AppId::Cloud),
+            UiPageRef::Sensor => UiPage::App(AppId::Sensor),
+            UiPageRef::Trainer => UiPage::App(AppId::Trainer),
+            UiPageRef::Elrs => UiPage::App(AppId::Scripts),
+            UiPageRef::About => UiPage::App(AppId::About),
+        }
+    }
+
     fn publish_config(&self, config_tx: &Sender<SystemConfigMsg>) {
         config_tx.send(self.frame.config);
     }
@@ -327,6 +386,7 @@ impl UiApp {
         config_tx: &Sender<SystemConfigMsg>,
         active_model_tx: &Sender<ActiveModelMsg>,
         elrs_cmd_tx: &Sender<ElrsCommandMsg>,
+        telemetry_cmd_tx: &Sender<TelemetryCommandMsg>,
     ) {
         match app {
             AppId::System => match event {
@@ -387,6 +447,9 @@ impl UiApp {
                     }
                 }
             }
+            AppId::Sensor if event == UiInputEvent::Open => {
+                telemetry_cmd_tx.send(TelemetryCommandMsg::ResetStats)
+            }
             AppId::Scripts => match event {
                 UiInputEvent::Back | UiInputEvent::PagePrev => {
                     elrs_cmd_tx.send(ElrsCommandMsg::Back)
@@ -409,6 +472,7 @@ impl UiApp {
         config_tx: &Sender<SystemConfigMsg>,
         active_model_tx: &Sender<ActiveModelMsg>,
         elrs_cmd_tx: &Sender<ElrsCommandMsg>,
+        telemetry_cmd_tx: &Sender<TelemetryCommandMsg>,
     ) -> bool {
         match event {
             UiInputEvent::Quit => return false,
@@ -422,6 +486,7 @@ impl UiApp {
                         config_tx,
                         active_model_tx,
                         elrs_cmd_tx,
+                        telemetry_cmd_tx,
                     );
                 } else {
                     self.frame.page = UiPage::Launcher;
@@ -437,28 +502,56 @@ impl UiApp {
                         self.frame.page = UiPage::App(app);
                     }
                 } else if let UiPage::App(app) = self.frame.page {
-                    self.apply_event_in_app(app, event, config_tx, active_model_tx, elrs_cmd_tx);
+                    self.apply_event_in_app(
+                        app,
+                        event,
+                        config_tx,
+                        active_model_tx,
+                        elrs_cmd_tx,
+                        telemetry_cmd_tx,
+                    );
                 }
             }
             UiInputEvent::Left => {
                 if self.frame.page == UiPage::Launcher {
                     self.move_left();
                 } else if let UiPage::App(app) = self.frame.page {
-                    self.apply_event_in_app(app, event, config_tx, active_model_tx, elrs_cmd_tx);
+                    self.apply_event_in_app(
+                        app,
+                        event,
+                        config_tx,
+                        active_model_tx,
+                        elrs_cmd_tx,
+                        telemetry_cmd_tx,
+                    );
                 }
             }
             UiInputEvent::Right => {
                 if self.frame.page == UiPage::Launcher {
                     self.move_right();
                 } else if let UiPage::App(app) = self.frame.page {
-                    self.apply_event_in_app(app, event, config_tx, active_model_tx, elrs_cmd_tx);
+                    self.apply_event_in_app(
+                        app,
+                        event,
+                        config_tx,
+                        active_model_tx,
+                        elrs_cmd_tx,
+                        telemetry_cmd_tx,
+                    );
                 }
             }
             UiInputEvent::Up | UiInputEvent::Down => {
                 if self.frame.page == UiPage::Launcher {
                     self.move_selection_vertical(if event == UiInputEvent::Up { -1 } else { 1 });
                 } else if let UiPage::App(app) = self.frame.page {
-                    self.apply_event_in_app(app, event, config_tx, active_model_tx, elrs_cmd_tx);
+                    self.apply_event_in_app(
+                        app,
+                        event,
+                        config_tx,
+                        active_model_tx,
+                        elrs_cmd_tx,
+                        telemetry_cmd_tx,
+                    );
                 }
             }
             UiInputEvent::PagePrev => {
@@ -483,12 +576,21 @@ impl UiApp {
         super::debug_log(&format!("UiApp::run start fps={fps}"));
         let mut status_rx = get_new_rx_of_message::<SystemStatusMsg>("system_status").unwrap();
         let mut config_rx = get_new_rx_of_message::<SystemConfigMsg>("system_config").unwrap();
-        let mut adc_raw_rx = get_new_rx_of_message::<AdcRawMsg>("adc_raw").unwrap();
+        let mut input_rx = get_new_rx_of_message::<InputFrameMsg>("input_frame").unwrap();
         let mut mixer_out_rx = get_new_rx_of_message::<MixerOutMsg>("mixer_out").unwrap();
+        let mut flight_mode_rx = get_new_rx_of_message::<FlightModeMsg>("flight_mode").unwrap();
+        let mut timers_rx = get_new_rx_of_message::<TimerStateMsg>("timers").unwrap();
+        let mut telemetry_rx = get_new_rx_of_message::<TelemetryMsg>("telemetry").unwrap();
+        let mut active_model_rx = get_new_rx_of_message::<ActiveModelMsg>("active_model").unwrap();
+        let mut ui_cmd_rx = get_new_rx_of_message::<UiCommandMsg>("ui_cmd").unwrap();
         let mut elrs_rx = get_new_rx_of_message::<ElrsStateMsg>("elrs_state").unwrap();
+        let mut config_change_rx =
+            get_new_rx_of_message::<ConfigChangeMsg>("config_change").unwrap();
         let config_tx = get_new_tx_of_message::<SystemConfigMsg>("system_config").unwrap();
         let active_model_tx = get_new_tx_of_message::<ActiveModelMsg>("active_model").unwrap();
         let elrs_cmd_tx = get_new_tx_of_message::<ElrsCommandMsg>("elrs_cmd").unwrap();
+        let telemetry_cmd_tx =
+            get_new_tx_of_message::<TelemetryCommandMsg>("telemetry_cmd").unwrap();
 
         let elrs_state_tx = get_new_tx_of_message::<ElrsStateMsg>("elrs_state").unwrap();
         elrs_state_tx.send(ElrsStateMsg::default());
@@ -521,18 +623,46 @@ impl UiApp {
                 dirty |= Self::update_field(&mut self.frame.config, cfg);
             }
 
-            while let Some(adc_raw) = adc_raw_rx.try_read() {
-                dirty |= Self::update_field(&mut self.frame.adc_raw, adc_raw);
+            while let Some(input) = input_rx.try_read() {
+                dirty |= Self::update_field(&mut self.frame.input, input);
             }
 
             while let Some(mixer_out) = mixer_out_rx.try_read() {
                 dirty |= Self::update_field(&mut self.frame.mixer_out, mixer_out);
             }
 
+            while let Some(flight_mode) = flight_mode_rx.try_read() {
+                dirty |= Self::update_field(&mut self.frame.flight_mode, flight_mode);
+            }
+
+            while let Some(timers) = timers_rx.try_read() {
+                dirty |= Self::update_field(&mut self.frame.timers, timers);
+            }
+
+            while let Some(telemetry) = telemetry_rx.try_read() {
+                dirty |= Self::update_field(&mut self.frame.telemetry, telemetry);
+            }
+
+            while let Some(active_model) = active_model_rx.try_read() {
+                dirty |= self.sync_active_model(&active_model.model.id);
+            }
+
+            while let Some(cmd) = ui_cmd_rx.try_read() {
+                match cmd {
+                    UiCommandMsg::OpenPage(page) => {
+                        dirty |= Self::update_field(&mut self.frame.page, Self::page_for(page));
+                    }
+                }
+            }
+
             while let Some(elrs) = elrs_rx.try_read() {
                 dirty |= Self::update_field(&mut self.frame.elrs, elrs);
             }
 
+            while let Some(change) = config_change_rx.try_read() {
+                dirty |= self.apply_config_change(change);
+            }
+
             if self.frame.cloud_connected
                 && self.frame.status.unix_time_secs
                     >= self.frame.cloud_last_sync_secs.saturating_add(5)
@@ -542,7 +672,13 @@ impl UiApp {
             }
 
             while let Some(evt) = backend.poll_event() {
-                if !self.apply_event(evt, &config_tx, &active_model_tx, &elrs_cmd_tx) {
+                if !self.apply_event(
+                    evt,
+                    &config_tx,
+                    &active_model_tx,
+                    &elrs_cmd_tx,
+                    &telemetry_cmd_tx,
+                ) {
                     backend.shutdown();
                     return;
                 }
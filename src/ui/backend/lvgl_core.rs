@@ -3,7 +3,10 @@ use crate::ui::{
     model::{AppId, UiFrame, UiPage},
 };
 
-use super::{elrs_list_lines, signal_grade};
+use super::{
+    elrs_list_lines, flight_mode_label, format_timer, mixer_out_lines, sensor_lines, signal_grade,
+    timer_lines, timer_status,
+};
 
 pub(super) const TOP_BAR_HEIGHT: i32 = 44;
 pub(super) const LVGL_DRAW_BUF_PIXELS: usize = 800 * 480;
@@ -393,33 +396,94 @@ impl LvglUiCore {
             },
             AppId::Control => {
                 let left_avg =
-                    ((frame.adc_raw.value[0] as i32 + frame.adc_raw.value[1] as i32) / 2).max(0);
+                    ((frame.input.axis(0) as i32 + frame.input.axis(1) as i32) / 2).max(0);
                 let right_avg =
-                    ((frame.adc_raw.value[2] as i32 + frame.adc_raw.value[3] as i32) / 2).max(0);
+                    ((frame.input.axis(2) as i32 + frame.input.axis(3) as i32) / 2).max(0);
                 AppTemplateData {
                     accent: spec.accent,
                     badge: "CONTROL".to_string(),
                     title: "Input Pipeline Monitor".to_string(),
-                    subtitle: "Sensor input and mixer output diagnostics".to_string(),
+                    subtitle: format!(
+                        "Sensor input and mixer output diagnostics   Mode: {}",
+                        flight_mode_label(frame)
+                    ),
                     metric_titles: ["ADC CH1/2".to_string(), "ADC CH3/4".to_string()],
                     metric_values: [
-                        format!("{}/{}", frame.adc_raw.value[0], frame.adc_raw.value[1]),
-                        format!("{}/{}", frame.adc_raw.value[2], frame.adc_raw.value[3]),
+                        format!("{}/{}", frame.input.axis(0), frame.input.axis(1)),
+                        format!("{}/{}", frame.input.axis(2), frame.input.axis(3)),
                     ],
                     metric_progress: [
                         Self::clamp_pct(left_avg * 100 / 2048),
                         Self::clamp_pct(right_avg * 100 / 2048),
                     ],
                     list_title: "Mixer Out".to_string(),
-                    list_lines: [
-                        format!("Thrust: {}", frame.mixer_out.thrust),
-                        format!("Direction: {}", frame.mixer_out.direction),
-                        format!("Aileron: {}", frame.mixer_out.aileron),
-                        format!("Elevator: {}", frame.mixer_out.elevator),
-                    ],
+                    list_lines: mixer_out_lines(frame),
                     hint: "Use for ADC -> mixer chain validation   ESC: Back".to_string(),
                 }
             }
+            AppId::Flight => {
+                let first = frame.timers.timers.first();
+                AppTemplateData {
+                    accent: spec.accent,
+                    badge: "FLIGHT".to_string(),
+                    title: "Flight Screen".to_string(),
+                    subtitle: format!("Mode: {}", flight_mode_label(frame)),
+                    metric_titles: [
+                        first.map_or_else(|| "Timer".to_string(), |timer| timer.name.clone()),
+                        "Link".to_string(),
+                    ],
+                    metric_values: [
+                        first.map_or_else(
+                            || "--:--".to_string(),
+                            |timer| format_timer(timer.value_secs),
+                        ),
+                        format!("{}%", frame.status.signal_strength_percent),
+                    ],
+                    metric_progress: [
+                        if first.is_some_and(|timer| timer.running) {
+                            100
+                        } else {
+                            0
+                        },
+                        frame.status.signal_strength_percent,
+                    ],
+                    list_title: "Timers".to_string(),
+                    list_lines: timer_lines(frame),
+                    hint: "ESC: Back".to_string(),
+                }
+            }
+            AppId::Sensor => {
+                let telemetry = &frame.telemetry;
+                AppTemplateData {
+                    accent: spec.accent,
+                    badge: "SENSOR".to_string(),
+                    title: "Telemetry Sensors".to_string(),
+                    subtitle: format!(
+                        "Aircraft mode: {}",
+                        if telemetry.flight_mode.is_empty() {
+                            "--"
+                        } else {
+                            &telemetry.flight_mode
+                        }
+                    ),
+                    metric_titles: ["Link Quality".to_string(), "RX Battery".to_string()],
+                    metric_values: [
+                        telemetry
+                            .link_quality
+                            .map_or_else(|| "--".to_string(), |lq| format!("{lq}%")),
+                        telemetry
+                            .battery_remaining
+                            .map_or_else(|| "--".to_string(), |pct| format!("{pct}%")),
+                    ],
+                    metric_progress: [
+                        telemetry.link_quality.unwrap_or(0),
+                        telemetry.battery_remaining.unwrap_or(0),
+                    ],
+                    list_title: "Sensors (min..max)".to_string(),
+                    list_lines: sensor_lines(frame),
+                    hint: "ENTER: Reset Min/Max   ESC: Back".to_string(),
+                }
+            }
             AppId::Models => {
                 let model_count = frame.model_entries.len().max(1);
                 let focus = frame.model_focus_idx.min(model_count.saturating_sub(1));
@@ -541,8 +605,8 @@ impl LvglUiCore {
                     metric_titles: ["Packet / Telemetry".to_string(), "TX / WiFi".to_string()],
                     metric_values: [
                         format!(
-                            "{} · {}",
-                            frame.elrs.packet_rate, frame.elrs.telemetry_ratio
+                            "{} · {} · RC {}",
+                            frame.elrs.packet_rate, frame.elrs.telemetry_ratio, frame.elrs.rc_rate
                         ),
                         format!(
                             "{} · {}",
@@ -563,12 +627,20 @@ impl LvglUiCore {
                             "Edit {} = {}",
                             frame.elrs.editor_label, frame.elrs.editor_buffer
                         )
+                    } else if frame.elrs.command_confirm {
+                        format!("Confirm {}?", frame.elrs.command_label)
+                    } else if frame.elrs.command_active {
+                        format!("{}: {}", frame.elrs.command_label, frame.elrs.command_info)
                     } else {
                         format!("{} / {}", frame.elrs.device_name, frame.elrs.version)
                     },
                     list_lines,
                     hint: if frame.elrs.editor_active {
                         "UP/DOWN: Char   LEFT/RIGHT: Move   ENTER: Save   ESC: Cancel".to_string()
+                    } else if frame.elrs.command_confirm {
+                        "ENTER: Confirm   ESC: Cancel".to_string()
+                    } else if frame.elrs.command_active {
+                        "ESC: Cancel".to_string()
                     } else {
                         "UP/DOWN: Select   LEFT/RIGHT: Adjust   ENTER: Open/Apply   ]: Refresh   ESC: Back"
                             .to_string()
@@ -1671,15 +1743,25 @@ impl LvglUiCore {
         }
 
         if prev_frame
-            .map(|prev| prev.status != frame.status)
+            .map(|prev| {
+                prev.status != frame.status
+                    || prev.timers != frame.timers
+                    || prev.config_error != frame.config_error
+            })
             .unwrap_or(true)
         {
-            let status = format!(
+            let mut status = format!(
                 "R {}%  A {}%  S {}%",
                 frame.status.remote_battery_percent,
                 frame.status.aircraft_battery_percent,
                 frame.status.signal_strength_percent,
             );
+            if let Some(timer) = timer_status(frame) {
+                status.push_str(&format!("  {timer}"));
+            }
+            if !frame.config_error.is_empty() {
+                status = format!("Config rejected: {}", frame.config_error);
+            }
             Self::set_label_text(ui.status_label, &status);
 
             let secs = frame.status.unix_time_secs % 86400;
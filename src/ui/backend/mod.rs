@@ -120,6 +120,136 @@ fn elrs_list_lines(frame: &UiFrame) -> [String; 4] {
     ]
 }
 
+/// Mixer output as up to 8 channels, two per line.
+fn mixer_out_lines(frame: &UiFrame) -> [String; 4] {
+    let out = &frame.mixer_out;
+    let cell = |slot: usize| {
+        if slot < out.count as usize {
+            format!(
+                "CH{} {}: {}",
+                slot + 1,
+                out.roles[slot].short_name(),
+                out.channels[slot]
+            )
+        } else {
+            String::new()
+        }
+    };
+    let line = |row: usize| format!("{:<16}{}", cell(row * 2), cell(row * 2 + 1));
+    if out.count == 0 {
+        return [
+            "No mixer output".to_string(),
+            String::new(),
+            String::new(),
+            String::new(),
+        ];
+    }
+    [line(0), line(1), line(2), line(3)]
+}
+
+fn flight_mode_label(frame: &UiFrame) -> String {
+    let mode = &frame.flight_mode;
+    match (mode.name.is_empty(), mode.fading) {
+        (true, _) => "--".to_string(),
+        (false, true) => format!("{} (fading)", mode.name),
+        (false, false) => mode.name.clone(),
+    }
+}
+
+/// `MM:SS`, with a leading `-` once a countdown has run out.
+fn format_timer(secs: i64) -> String {
+    let sign = if secs < 0 { "-" } else { "" };
+    let secs = secs.unsigned_abs();
+    format!("{sign}{:02}:{:02}", secs / 60, secs % 60)
+}
+
+/// First model timer for the status bar, if the model has any.
+fn timer_status(frame: &UiFrame) -> Option<String> {
+    frame
+        .timers
+        .timers
+        .first()
+        .map(|timer| format!("{} {}", timer.name, format_timer(timer.value_secs)))
+}
+
+/// Up to four timers, one per line.
+fn timer_lines(frame: &UiFrame) -> [String; 4] {
+    let line = |index: usize| {
+        frame
+            .timers
+            .timers
+            .get(index)
+            .map_or_else(String::new, |timer| {
+                format!(
+                    "{}: {}{}",
+                    timer.name,
+                    format_timer(timer.value_secs),
+                    if timer.running { "" } else { " (stopped)" }
+                )
+            })
+    };
+    if frame.timers.timers.is_empty() {
+        return [
+            "No timers on this model".to_string(),
+            String::new(),
+            String::new(),
+            String::new(),
+        ];
+    }
+    [line(0), line(1), line(2), line(3)]
+}
+
+/// Fixed-point sensor value with `precision` decimals, e.g. 1234 at 2 -> `12.34`.
+fn format_sensor_value(value: i32, precision: u8) -> String {
+    if precision == 0 {
+        return value.to_string();
+    }
+    let scale = 10_i64.pow(precision as u32);
+    let abs = (value as i64).abs();
+    let sign = if value < 0 { "-" } else { "" };
+    format!(
+        "{sign}{}.{:0width$}",
+        abs / scale,
+        abs % scale,
+        width = precision as usize
+    )
+}
+
+/// Up to four sensors with value and min/max since the last reset.
+fn sensor_lines(frame: &UiFrame) -> [String; 4] {
+    let format = |value: Option<i32>, precision: u8| {
+        value.map_or_else(
+            || "--".to_string(),
+            |value| format_sensor_value(value, precision),
+        )
+    };
+    let line = |index: usize| {
+        frame
+            .telemetry
+            .sensors
+            .get(index)
+            .map_or_else(String::new, |sensor| {
+                format!(
+                    "{}: {}{} ({}..{})",
+                    sensor.key,
+                    format(sensor.value, sensor.precision),
+                    sensor.unit,
+                    format(sensor.min, sensor.precision),
+                    format(sensor.max, sensor.precision),
+                )
+            })
+    };
+    if frame.telemetry.sensors.is_empty() {
+        return [
+            "No telemetry sensors".to_string(),
+            String::new(),
+            String::new(),
+            String::new(),
+        ];
+    }
+    [line(0), line(1), line(2), line(3)]
+}
+
 pub fn new_backend(kind: BackendKind) -> Box<dyn LvglBackend> {
     match kind {
         BackendKind::PcApi => {
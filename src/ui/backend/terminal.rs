@@ -1,7 +1,8 @@
 use std::io::Write;
 
 use super::{
-    battery_grade, elrs_list_lines, signal_grade, LvglBackend, MODEL_NAMES, MODEL_PROTOCOLS,
+    battery_grade, elrs_list_lines, flight_mode_label, mixer_out_lines, sensor_lines, signal_grade,
+    timer_lines, timer_status, LvglBackend, MODEL_NAMES, MODEL_PROTOCOLS,
 };
 use crate::ui::{
     catalog::{app_at, app_spec, page, PAGE_SPECS},
@@ -33,17 +34,56 @@ fn format_app_detail(frame: &UiFrame, app: AppId) -> String {
             frame.config.backlight_percent,
             frame.config.sound_percent,
         ),
-        AppId::Control => format!(
-            "ADC Raw\nCH1:{}  CH2:{}\nCH3:{}  CH4:{}\n\nMixer Out (0..10000)\nThrust:{}\nDirection:{}\nAileron:{}\nElevator:{}\n\nUse this page to validate input chain.\nEsc Back",
-            frame.adc_raw.value[0],
-            frame.adc_raw.value[1],
-            frame.adc_raw.value[2],
-            frame.adc_raw.value[3],
-            frame.mixer_out.thrust,
-            frame.mixer_out.direction,
-            frame.mixer_out.aileron,
-            frame.mixer_out.elevator,
-        ),
+        AppId::Control => {
+            let mixer_lines = mixer_out_lines(frame);
+            format!(
+                "Input {:?} (axes:{} sw:{} btn:{})\nCH1:{}  CH2:{}\nCH3:{}  CH4:{}\n\nMixer Out (0..10000)  Flight Mode: {}\n{}\n{}\n{}\n{}\n\nUse this page to validate input chain.\nEsc Back",
+                frame.input.source,
+                frame.input.axes.len(),
+                frame.input.switches.len(),
+                frame.input.buttons.len(),
+                frame.input.axis(0),
+                frame.input.axis(1),
+                frame.input.axis(2),
+                frame.input.axis(3),
+                flight_mode_label(frame),
+                mixer_lines[0],
+                mixer_lines[1],
+                mixer_lines[2],
+                mixer_lines[3],
+            )
+        }
+        AppId::Flight => {
+            let timers = timer_lines(frame);
+            let mixer_lines = mixer_out_lines(frame);
+            format!(
+                "Flight Mode: {}\n\nTimers\n{}\n{}\n{}\n{}\n\nChannels\n{}\n{}\n{}\n{}\n\nEsc Back",
+                flight_mode_label(frame),
+                timers[0],
+                timers[1],
+                timers[2],
+                timers[3],
+                mixer_lines[0],
+                mixer_lines[1],
+                mixer_lines[2],
+                mixer_lines[3],
+            )
+        }
+        AppId::Sensor => {
+            let sensors = sensor_lines(frame);
+            format!(
+                "Flight Mode: {}\n\nSensors (min..max)\n{}\n{}\n{}\n{}\n\nEnter: reset min/max\nEsc Back",
+                if frame.telemetry.flight_mode.is_empty() {
+                    "--"
+                } else {
+                    &frame.telemetry.flight_mode
+                },
+                sensors[0],
+                sensors[1],
+                sensors[2],
+                sensors[3],
+            )
+        }
         AppId::Models => {
             let focus = frame.model_focus_idx.min(MODEL_NAMES.len().saturating_sub(1));
             let active = frame.model_active_idx.min(MODEL_NAMES.len().saturating_sub(1));
@@ -95,16 +135,27 @@ fn format_app_detail(frame: &UiFrame, app: AppId) -> String {
                     frame.elrs.editor_buffer,
                     frame.elrs.editor_cursor.saturating_add(1)
                 )
+            } else if frame.elrs.command_active {
+                format!(
+                    "\nCommand: {}\n{}\n",
+                    frame.elrs.command_label,
+                    if frame.elrs.command_confirm {
+                        "Confirm?"
+                    } else {
+                        frame.elrs.command_info.as_str()
+                    }
+                )
             } else {
                 String::new()
             };
             format!(
-                "Link: {} ({})\nModule: {}\nDevice: {}\nVersion: {}\nPath: {}\nStatus: {}\n{}\n{}\n{}\n{}\n{}\n\n{}\nEsc Back",
+                "Link: {} ({})\nModule: {}\nDevice: {}\nVersion: {}\nRC: {}\nPath: {}\nStatus: {}\n{}\n{}\n{}\n{}\n{}\n\n{}\nEsc Back",
                 connected,
                 busy,
                 frame.elrs.module_name,
                 frame.elrs.device_name,
                 frame.elrs.version,
+                frame.elrs.rc_rate,
                 frame.elrs.path,
                 frame.elrs.status_text,
                 editor,
@@ -114,6 +165,10 @@ fn format_app_detail(frame: &UiFrame, app: AppId) -> String {
                 lines[3],
                 if frame.elrs.editor_active {
                     "Up/Down: char  Left/Right: move  Enter: save  Esc: cancel"
+                } else if frame.elrs.command_confirm {
+                    "Enter: confirm  Esc: cancel"
+                } else if frame.elrs.command_active {
+                    "Esc: cancel"
                 } else {
                     "Up/Down: select  Left/Right: adjust  Enter: open/apply  ]: refresh"
                 },
@@ -143,12 +198,18 @@ impl LvglBackend for TerminalBackend {
         print!("\x1B[2J\x1B[1;1H");
         println!("LinTX Launcher [{}]", self.backend_name);
         println!(
-            "STATUS | REM:{}% AIR:{}% SIG:{}% T:{}",
+            "STATUS | REM:{}% AIR:{}% SIG:{}% T:{}{}",
             frame.status.remote_battery_percent,
             frame.status.aircraft_battery_percent,
             frame.status.signal_strength_percent,
-            frame.status.unix_time_secs
+            frame.status.unix_time_secs,
+            timer_status(frame)
+                .map(|timer| format!(" | {timer}"))
+                .unwrap_or_default()
         );
+        if !frame.config_error.is_empty() {
+            println!("CONFIG | rejected {}", frame.config_error);
+        }
         println!("-----------------------------------------------");
         match frame.page {
             UiPage::Launcher => {
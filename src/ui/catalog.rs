@@ -16,7 +16,7 @@ pub struct PageSpec {
     pub apps: &'static [AppId],
 }
 
-pub const APP_SPECS: [AppSpec; 8] = [
+pub const APP_SPECS: [AppSpec; 9] = [
     AppSpec {
         id: AppId::System,
         title: "SYSTEM",
@@ -35,6 +35,12 @@ pub const APP_SPECS: [AppSpec; 8] = [
         icon_text: "MOD",
         accent: (255, 181, 92),
     },
+    AppSpec {
+        id: AppId::Flight,
+        title: "FLIGHT",
+        icon_text: "FLT",
+        accent: (120, 232, 120),
+    },
     AppSpec {
         id: AppId::Cloud,
         title: "CLOUD",
@@ -67,7 +73,7 @@ pub const APP_SPECS: [AppSpec; 8] = [
     },
 ];
 
-const PAGE0_APPS: [AppId; 4] = [AppId::System, AppId::Control, AppId::Models, AppId::Cloud];
+const PAGE0_APPS: [AppId; 4] = [AppId::System, AppId::Control, AppId::Models, AppId::Flight];
 const PAGE1_APPS: [AppId; 8] = [
     AppId::System,
     AppId::Control,
@@ -1,5 +1,8 @@
 use crate::{
-    messages::{AdcRawMsg, ElrsStateMsg, SystemConfigMsg, SystemStatusMsg},
+    messages::{
+        ElrsStateMsg, FlightModeMsg, InputFrameMsg, SystemConfigMsg, SystemStatusMsg, TelemetryMsg,
+        TimerStateMsg,
+    },
     mixer::MixerOutMsg,
 };
 
@@ -8,6 +11,7 @@ pub enum AppId {
     System,
     Control,
     Models,
+    Flight,
     Cloud,
     Sensor,
     Trainer,
@@ -43,14 +47,19 @@ pub struct UiFrame {
     pub selected_col: usize,
     pub status: SystemStatusMsg,
     pub config: SystemConfigMsg,
-    pub adc_raw: AdcRawMsg,
+    pub input: InputFrameMsg,
     pub mixer_out: MixerOutMsg,
+    pub flight_mode: FlightModeMsg,
+    pub timers: TimerStateMsg,
+    pub telemetry: TelemetryMsg,
     pub model_entries: Vec<UiModelEntry>,
     pub model_focus_idx: usize,
     pub model_active_idx: usize,
     pub cloud_connected: bool,
     pub cloud_last_sync_secs: u64,
     pub elrs: ElrsStateMsg,
+    /// Last config file `config_watch` rejected, with the reason.
+    pub config_error: String,
     pub debug: UiDebugStats,
 }
 
@@ -63,19 +72,18 @@ impl Default for UiFrame {
             selected_col: 0,
             status: SystemStatusMsg::default(),
             config: SystemConfigMsg::default(),
-            adc_raw: AdcRawMsg::default(),
-            mixer_out: MixerOutMsg {
-                thrust: 5000,
-                direction: 5000,
-                aileron: 5000,
-                elevator: 5000,
-            },
+            input: InputFrameMsg::default(),
+            mixer_out: MixerOutMsg::default(),
+            flight_mode: FlightModeMsg::default(),
+            timers: TimerStateMsg::default(),
+            telemetry: TelemetryMsg::default(),
             model_entries: Vec::new(),
             model_focus_idx: 0,
             model_active_idx: 0,
             cloud_connected: false,
             cloud_last_sync_secs: 0,
             elrs: ElrsStateMsg::default(),
+            config_error: String::new(),
             debug: UiDebugStats::default(),
         }
     }